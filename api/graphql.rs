@@ -1,11 +1,10 @@
 use axum::{routing::get, Router};
 use dds::db::DbConnection;
-use dds::graphql::{create_router, create_schema};
+use dds::graphql::{auth_provider_from_env, create_router, create_schema_with_auth, ExportState};
+use dds::server::bind_addr_from_env;
 use dotenv::dotenv;
-use std::net::SocketAddr;
 use tokio::net::TcpListener;
 use tokio::sync::broadcast;
-use tracing;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -24,8 +23,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::debug!("GraphQL event channel created");
 
     // Create GraphQL schema and router
-    let schema = create_schema(db.pool.clone(), event_sender);
-    let graphql_router = create_router(schema);
+    let auth_provider = auth_provider_from_env(db.pool.clone());
+    let schema = create_schema_with_auth(db.pool.clone(), event_sender, auth_provider.clone());
+    let graphql_router = create_router(
+        schema,
+        ExportState {
+            pool: db.pool.clone(),
+            auth_provider,
+        },
+    )?;
 
     // Create the main router with the /api prefix
     let app = Router::new()
@@ -36,8 +42,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Start the GraphQL server
     // Default to port 8080 to match client expectations
-    let port = std::env::var("PORT").unwrap_or_else(|_| "8080".to_string());
-    let addr = format!("0.0.0.0:{}", port).parse::<SocketAddr>()?;
+    let addr = bind_addr_from_env(8080)?;
 
     tracing::info!("Starting HTTP GraphQL server on http://{}", addr);
     tracing::info!(
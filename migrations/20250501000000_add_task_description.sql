@@ -0,0 +1,2 @@
+-- Tasks carry an optional description, matching the Task model
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS description TEXT;
@@ -0,0 +1,4 @@
+-- A task may depend on another task that must complete before it runs
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS depends_on UUID REFERENCES tasks(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_tasks_depends_on ON tasks(depends_on);
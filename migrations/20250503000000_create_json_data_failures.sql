@@ -0,0 +1,11 @@
+-- Dead-letter table for ETL files that failed to load
+CREATE TABLE IF NOT EXISTS json_data_failures (
+    id SERIAL PRIMARY KEY,
+    file_name TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    error_kind TEXT NOT NULL,
+    error_detail TEXT NOT NULL,
+    failed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_json_data_failures_file_path ON json_data_failures(file_path);
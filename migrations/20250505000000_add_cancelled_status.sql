@@ -0,0 +1,2 @@
+-- Allow jobs, tasks and pipeline runs to be cancelled
+ALTER TYPE status ADD VALUE IF NOT EXISTS 'Cancelled';
@@ -0,0 +1,12 @@
+-- Make json_data.file_name unique so files can be upserted on reprocess
+
+-- Keep only the most recently inserted row for each file
+DELETE FROM json_data a
+USING json_data b
+WHERE a.file_name = b.file_name
+  AND a.id < b.id;
+
+ALTER TABLE json_data ADD CONSTRAINT json_data_file_name_key UNIQUE (file_name);
+
+-- The unique constraint's index replaces the plain one
+DROP INDEX IF EXISTS idx_json_data_file_name;
@@ -0,0 +1,17 @@
+-- Optional webhook called when a job reaches a terminal status
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS callback_url TEXT;
+
+-- Outcome of each webhook delivery
+CREATE TABLE IF NOT EXISTS job_callback_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    job_id UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    job_status status NOT NULL,
+    succeeded BOOLEAN NOT NULL,
+    attempts INTEGER NOT NULL,
+    response_status INTEGER,
+    error TEXT,
+    delivered_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_callback_deliveries_job_id ON job_callback_deliveries(job_id);
@@ -0,0 +1,8 @@
+-- Composite indexes matching the (created_at DESC, id DESC) ordering used by the jobs and
+-- pipeline_runs resolvers, so ordered and keyset-paginated reads avoid a full sort
+CREATE INDEX IF NOT EXISTS idx_jobs_created_id ON jobs (created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS idx_pipeline_runs_job_created_id ON pipeline_runs (job_id, created_at DESC, id DESC);
+
+-- idx_pipeline_runs_job_created_id starts with job_id, so it already serves lookups by job;
+-- the single-column index only let the planner pick an index that still needs a sort
+DROP INDEX IF EXISTS idx_pipeline_runs_job_id;
@@ -0,0 +1,7 @@
+-- Tenant (business group) that owns each job; NULL marks a globally-scoped job.
+-- Tasks and pipeline runs belong to the tenant of their job.
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS business_group_id BIGINT;
+
+-- Tenant-scoped listing in the jobs resolver's (created_at DESC, id DESC) order
+CREATE INDEX IF NOT EXISTS idx_jobs_business_group_created_id
+    ON jobs (business_group_id, created_at DESC, id DESC);
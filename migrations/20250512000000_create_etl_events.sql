@@ -0,0 +1,10 @@
+-- Events delivered to `etl_events` subscribers, kept so reconnecting clients can catch up
+CREATE TABLE IF NOT EXISTS etl_events (
+    id BIGSERIAL PRIMARY KEY,
+    event_type TEXT NOT NULL,
+    entity_id UUID NOT NULL,
+    status status,
+    previous_status status,
+    data TEXT,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
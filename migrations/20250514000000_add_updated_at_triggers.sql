@@ -0,0 +1,31 @@
+-- Keep updated_at current on every UPDATE, including writes that bypass the API.
+-- An UPDATE that sets updated_at explicitly (e.g. a backfill) keeps the value it set.
+CREATE OR REPLACE FUNCTION set_updated_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.updated_at IS NOT DISTINCT FROM OLD.updated_at THEN
+        NEW.updated_at = NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS set_jobs_updated_at ON jobs;
+CREATE TRIGGER set_jobs_updated_at
+    BEFORE UPDATE ON jobs
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+DROP TRIGGER IF EXISTS set_tasks_updated_at ON tasks;
+CREATE TRIGGER set_tasks_updated_at
+    BEFORE UPDATE ON tasks
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+DROP TRIGGER IF EXISTS set_pipeline_runs_updated_at ON pipeline_runs;
+CREATE TRIGGER set_pipeline_runs_updated_at
+    BEFORE UPDATE ON pipeline_runs
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+DROP TRIGGER IF EXISTS set_users_updated_at ON users;
+CREATE TRIGGER set_users_updated_at
+    BEFORE UPDATE ON users
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
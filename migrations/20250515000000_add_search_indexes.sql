@@ -0,0 +1,6 @@
+-- Full-text indexes over job and task names and descriptions for the search resolver.
+-- The expressions must match SEARCH_TEXT in src/graphql/mod.rs for the planner to use them.
+CREATE INDEX IF NOT EXISTS idx_jobs_search ON jobs
+    USING GIN (to_tsvector('english', name || COALESCE(' ' || description, '')));
+CREATE INDEX IF NOT EXISTS idx_tasks_search ON tasks
+    USING GIN (to_tsvector('english', name || COALESCE(' ' || description, '')));
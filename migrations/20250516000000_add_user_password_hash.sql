@@ -0,0 +1,3 @@
+-- Argon2 password hashes of local accounts (AUTH_PROVIDER=local); NULL for users that
+-- sign in through Auth0/Okta
+ALTER TABLE public.users ADD COLUMN IF NOT EXISTS password_hash TEXT;
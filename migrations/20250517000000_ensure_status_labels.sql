@@ -0,0 +1,7 @@
+-- Ensure the status enum has every label of the Rust `Status` enum; safe to re-run.
+-- DbConnection::new refuses to start if the labels have drifted.
+ALTER TYPE status ADD VALUE IF NOT EXISTS 'Pending';
+ALTER TYPE status ADD VALUE IF NOT EXISTS 'Running';
+ALTER TYPE status ADD VALUE IF NOT EXISTS 'Completed';
+ALTER TYPE status ADD VALUE IF NOT EXISTS 'Failed';
+ALTER TYPE status ADD VALUE IF NOT EXISTS 'Cancelled';
@@ -0,0 +1,24 @@
+-- Tasks and pipeline runs must belong to a job. Databases whose tables predate the
+-- job_id foreign keys may hold orphans; remove them, then add the missing constraints
+-- so deleting a job cascades and new orphans can't form. Safe to re-run.
+DELETE FROM tasks t WHERE NOT EXISTS (SELECT 1 FROM jobs j WHERE j.id = t.job_id);
+DELETE FROM pipeline_runs r WHERE NOT EXISTS (SELECT 1 FROM jobs j WHERE j.id = r.job_id);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM pg_constraint
+        WHERE conrelid = 'tasks'::regclass AND confrelid = 'jobs'::regclass AND contype = 'f'
+    ) THEN
+        ALTER TABLE tasks
+            ADD CONSTRAINT tasks_job_id_fkey FOREIGN KEY (job_id) REFERENCES jobs(id) ON DELETE CASCADE;
+    END IF;
+
+    IF NOT EXISTS (
+        SELECT 1 FROM pg_constraint
+        WHERE conrelid = 'pipeline_runs'::regclass AND confrelid = 'jobs'::regclass AND contype = 'f'
+    ) THEN
+        ALTER TABLE pipeline_runs
+            ADD CONSTRAINT pipeline_runs_job_id_fkey FOREIGN KEY (job_id) REFERENCES jobs(id) ON DELETE CASCADE;
+    END IF;
+END $$;
@@ -0,0 +1,20 @@
+-- Every json_data row records when its file was first loaded (created_at) and last
+-- (re)loaded (updated_at). Older rows may have NULLs from before the columns were
+-- required; backfill them, then keep the columns set and updated_at current.
+UPDATE json_data SET created_at = COALESCE(created_at, updated_at, NOW())
+    WHERE created_at IS NULL;
+UPDATE json_data SET updated_at = created_at WHERE updated_at IS NULL;
+
+ALTER TABLE json_data
+    ALTER COLUMN created_at SET DEFAULT NOW(),
+    ALTER COLUMN created_at SET NOT NULL,
+    ALTER COLUMN updated_at SET DEFAULT NOW(),
+    ALTER COLUMN updated_at SET NOT NULL;
+
+DROP TRIGGER IF EXISTS set_json_data_updated_at ON json_data;
+CREATE TRIGGER set_json_data_updated_at
+    BEFORE UPDATE ON json_data
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+-- ingestHistory lists loads newest first
+CREATE INDEX IF NOT EXISTS idx_json_data_updated_at ON json_data(updated_at DESC);
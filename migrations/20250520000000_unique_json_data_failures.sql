@@ -0,0 +1,12 @@
+-- One dead-letter row per file: keep each file's latest failure, then enforce it so
+-- repeated runs over the same bad file update that row instead of adding duplicates
+DELETE FROM json_data_failures f
+USING json_data_failures newer
+WHERE newer.file_path = f.file_path
+  AND (newer.failed_at, newer.id) > (f.failed_at, f.id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_json_data_failures_file_path_unique
+    ON json_data_failures(file_path);
+
+-- The unique index serves lookups by path
+DROP INDEX IF EXISTS idx_json_data_failures_file_path;
@@ -0,0 +1,5 @@
+-- Tenant of the job an event is about, so subscriptions only replay their own tenant's events
+ALTER TABLE etl_events ADD COLUMN IF NOT EXISTS business_group_id BIGINT;
+
+CREATE INDEX IF NOT EXISTS idx_etl_events_business_group
+    ON etl_events (business_group_id, id);
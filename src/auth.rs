@@ -1,11 +1,19 @@
-use async_graphql::{Context, Error, ErrorExtensions, Result};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use async_graphql::{Context, Error, ErrorExtensions, Guard, Result};
 use async_trait::async_trait;
-use jsonwebtoken::{decode, DecodingKey, Validation};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use moka::future::Cache;
+use moka::Expiry;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool, Row};
 use std::env;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use crate::graphql::GraphQLContext;
+use crate::graphql::{GqlError, GraphQLContext};
 use crate::models::etl::{DateTimeScalar, UuidScalar};
 use crate::models::user::User;
 
@@ -16,9 +24,19 @@ pub trait AuthProvider: Send + Sync {
     async fn validate_token(&self, token: &str) -> Result<TokenClaims>;
 }
 
+/// Default timeout of each request to the auth provider, in seconds
+pub const DEFAULT_AUTH_HTTP_TIMEOUT_SECS: u64 = 10;
+
+/// Message of the error returned when the auth provider doesn't answer in time
+pub const AUTH_TIMEOUT_MESSAGE: &str = "authentication provider timed out";
+
 /// Auth0/Okta implementation of the auth provider
+///
+/// Every request to the provider times out after `AUTH_HTTP_TIMEOUT_SECS` (default 10s)
+/// and fails with `AUTH_TIMEOUT_MESSAGE`.
 pub struct Auth0Okta {
     client: Client,
+    base_url: String,
     domain: String,
     client_id: String,
     client_secret: String,
@@ -33,17 +51,270 @@ impl Auth0Okta {
             env::var("AUTH0_CLIENT_SECRET").expect("AUTH0_CLIENT_SECRET must be set");
         let audience =
             env::var("AUTH0_AUDIENCE").unwrap_or_else(|_| format!("https://{}/api/v2/", domain));
+        let timeout = env::var("AUTH_HTTP_TIMEOUT_SECS")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_AUTH_HTTP_TIMEOUT_SECS);
+
+        Self::from_credentials(domain, client_id, client_secret, audience)
+            .with_timeout(Duration::from_secs(timeout))
+    }
 
+    /// Creates a provider for the tenant at `https://{domain}` with the default timeout.
+    pub fn from_credentials(
+        domain: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+        audience: impl Into<String>,
+    ) -> Self {
+        let domain = domain.into();
         Self {
-            client: Client::new(),
+            client: Self::client(Duration::from_secs(DEFAULT_AUTH_HTTP_TIMEOUT_SECS)),
+            base_url: format!("https://{}", domain),
             domain,
-            client_id,
-            client_secret,
-            audience,
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            audience: audience.into(),
+        }
+    }
+
+    /// Sets the timeout of each request to the provider.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = Self::client(timeout);
+        self
+    }
+
+    /// Sends requests to `base_url` instead of `https://{domain}`.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    fn client(timeout: Duration) -> Client {
+        Client::builder()
+            .timeout(timeout)
+            .build()
+            .unwrap_or_default()
+    }
+}
+
+/// Converts a failed request to the provider into an error, reporting timeouts as
+/// `AUTH_TIMEOUT_MESSAGE`
+fn provider_error(context: &str, e: reqwest::Error) -> Error {
+    if e.is_timeout() {
+        tracing::error!("{}: auth provider timed out", context);
+        return GqlError::Internal(AUTH_TIMEOUT_MESSAGE.to_string()).into();
+    }
+    tracing::error!("{}: {}", context, e);
+    GqlError::Internal(format!("{}: {}", context, e)).into()
+}
+
+/// Default lifetime of tokens issued by `LocalAuthProvider`, in seconds
+pub const DEFAULT_LOCAL_TOKEN_TTL_SECS: u64 = 3600;
+
+/// Message of the error returned for an unknown email or a wrong password, which are
+/// deliberately indistinguishable
+pub const INVALID_CREDENTIALS_MESSAGE: &str = "Invalid email or password";
+
+/// Hashes a password with Argon2id and a random salt, returning the PHC string to store
+/// in `users.password_hash`.
+pub fn hash_password(password: &str) -> std::result::Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+/// Returns whether `password` matches a PHC string produced by `hash_password`.
+///
+/// A malformed hash never matches.
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    PasswordHash::new(password_hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+/// Auth provider for local accounts, selected with `AUTH_PROVIDER=local`
+///
+/// `login` checks the password against the user's `password_hash` and issues an HS256
+/// token signed with `LOCAL_AUTH_SECRET`, valid for `LOCAL_AUTH_TOKEN_TTL_SECS` (default
+/// 3600) seconds. No refresh tokens are issued, so `refreshToken` is empty.
+pub struct LocalAuthProvider {
+    pool: PgPool,
+    secret: String,
+    token_ttl: Duration,
+}
+
+impl LocalAuthProvider {
+    pub fn new(pool: PgPool, secret: impl Into<String>) -> Self {
+        Self {
+            pool,
+            secret: secret.into(),
+            token_ttl: Duration::from_secs(DEFAULT_LOCAL_TOKEN_TTL_SECS),
+        }
+    }
+
+    /// Creates a provider from `LOCAL_AUTH_SECRET` and `LOCAL_AUTH_TOKEN_TTL_SECS`.
+    pub fn from_env(pool: PgPool) -> Self {
+        let secret = env::var("LOCAL_AUTH_SECRET").expect("LOCAL_AUTH_SECRET must be set");
+        let token_ttl = env::var("LOCAL_AUTH_TOKEN_TTL_SECS")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_LOCAL_TOKEN_TTL_SECS);
+
+        Self::new(pool, secret).with_token_ttl(Duration::from_secs(token_ttl))
+    }
+
+    /// Sets how long issued tokens stay valid.
+    pub fn with_token_ttl(mut self, token_ttl: Duration) -> Self {
+        self.token_ttl = token_ttl;
+        self
+    }
+}
+
+#[async_trait]
+impl AuthProvider for LocalAuthProvider {
+    async fn login(&self, email: String, password: String) -> Result<AuthResponse> {
+        let invalid = || GqlError::Unauthorized(INVALID_CREDENTIALS_MESSAGE.into());
+
+        let row = sqlx::query("SELECT * FROM public.users WHERE email = $1")
+            .bind(&email)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to look up local account: {}", e);
+                GqlError::Internal("Failed to look up account".into())
+            })?
+            .ok_or_else(invalid)?;
+        let password_hash: Option<String> = row
+            .try_get("password_hash")
+            .map_err(|e| GqlError::Internal(e.to_string()))?;
+        let user = User::from_row(&row).map_err(|e| GqlError::Internal(e.to_string()))?;
+
+        // Argon2 is deliberately slow, so keep it off the async workers
+        let password_hash = password_hash.ok_or_else(invalid)?;
+        let matches =
+            tokio::task::spawn_blocking(move || verify_password(&password, &password_hash))
+                .await
+                .map_err(|e| GqlError::Internal(e.to_string()))?;
+        if !matches {
+            tracing::debug!("Rejected local login for {}", email);
+            return Err(invalid().into());
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let claims = TokenClaims {
+            sub: user.id.0.to_string(),
+            exp: (now + self.token_ttl).as_secs() as usize,
+            iat: now.as_secs() as usize,
+            iss: None,
+            aud: None,
+            email: Some(user.email.clone()),
+            business_group_id: None,
+        };
+        let token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(self.secret.as_bytes()),
+        )
+        .map_err(|e| GqlError::Internal(format!("Failed to issue token: {}", e)))?;
+
+        Ok(AuthResponse {
+            token,
+            refresh_token: String::new(),
+            user,
+        })
+    }
+
+    async fn validate_token(&self, token: &str) -> Result<TokenClaims> {
+        let token_data = decode::<TokenClaims>(
+            token,
+            &DecodingKey::from_secret(self.secret.as_bytes()),
+            &Validation::default(),
+        )
+        .map_err(|e| GqlError::Unauthorized(format!("Invalid token: {}", e)))?;
+
+        Ok(token_data.claims)
+    }
+}
+
+/// Default number of validated tokens kept by `CachedAuthProvider`
+pub const DEFAULT_TOKEN_CACHE_CAPACITY: u64 = 10_000;
+
+/// Wraps an `AuthProvider`, remembering validated tokens until they expire
+///
+/// A token that `inner` accepted is served from memory, without re-verification, until
+/// its `exp` passes. Tokens are keyed by their SHA-256 hash, so raw tokens are never
+/// stored. Rejected tokens are never cached.
+pub struct CachedAuthProvider<P> {
+    inner: P,
+    cache: Cache<[u8; 32], TokenClaims>,
+}
+
+impl<P: AuthProvider> CachedAuthProvider<P> {
+    /// Caches up to `DEFAULT_TOKEN_CACHE_CAPACITY` tokens validated by `inner`.
+    pub fn new(inner: P) -> Self {
+        Self::with_capacity(inner, DEFAULT_TOKEN_CACHE_CAPACITY)
+    }
+
+    /// Caches up to `capacity` tokens validated by `inner`.
+    pub fn with_capacity(inner: P, capacity: u64) -> Self {
+        Self {
+            inner,
+            cache: Cache::builder()
+                .max_capacity(capacity)
+                .expire_after(TokenExpiry)
+                .build(),
         }
     }
 }
 
+/// Expires cached claims when the token itself expires
+struct TokenExpiry;
+
+impl Expiry<[u8; 32], TokenClaims> for TokenExpiry {
+    fn expire_after_create(
+        &self,
+        _key: &[u8; 32],
+        claims: &TokenClaims,
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        Some(claims.remaining_lifetime())
+    }
+}
+
+#[async_trait]
+impl<P: AuthProvider> AuthProvider for CachedAuthProvider<P> {
+    async fn login(&self, email: String, password: String) -> Result<AuthResponse> {
+        self.inner.login(email, password).await
+    }
+
+    async fn validate_token(&self, token: &str) -> Result<TokenClaims> {
+        let key: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+        if let Some(claims) = self.cache.get(&key).await {
+            if !claims.remaining_lifetime().is_zero() {
+                return Ok(claims);
+            }
+        }
+
+        let claims = self.inner.validate_token(token).await?;
+        if !claims.remaining_lifetime().is_zero() {
+            self.cache.insert(key, claims.clone()).await;
+        }
+        Ok(claims)
+    }
+}
+
+impl Default for Auth0Okta {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait]
 impl AuthProvider for Auth0Okta {
     async fn login(&self, email: String, password: String) -> Result<AuthResponse> {
@@ -57,14 +328,14 @@ impl AuthProvider for Auth0Okta {
                 self.client_id.is_empty(),
                 self.audience.is_empty()
             );
-            return Err(Error::new("Auth0/Okta configuration is incomplete"));
+            return Err(GqlError::Internal("Auth0/Okta configuration is incomplete".into()).into());
         }
 
         // Add more helpful logging
         tracing::debug!("Using Auth0 domain: {}", self.domain);
         tracing::debug!("Using Auth0 audience: {}", self.audience);
 
-        let token_url = format!("https://{}/oauth/token", self.domain);
+        let token_url = format!("{}/oauth/token", self.base_url);
         tracing::debug!("Requesting token from: {}", token_url);
 
         let params = [
@@ -95,13 +366,13 @@ impl AuthProvider for Auth0Okta {
         }
 
         // Send the actual request to Auth0/Okta
-        let response = match self.client.post(&token_url).form(&params).send().await {
-            Ok(resp) => resp,
-            Err(e) => {
-                tracing::error!("Failed to send Auth0 request: {}", e);
-                return Err(Error::new(format!("Failed to send request: {}", e)));
-            }
-        };
+        let response = self
+            .client
+            .post(&token_url)
+            .form(&params)
+            .send()
+            .await
+            .map_err(|e| provider_error("Failed to send request", e))?;
 
         if !response.status().is_success() {
             let error_text = response
@@ -109,51 +380,41 @@ impl AuthProvider for Auth0Okta {
                 .await
                 .unwrap_or_else(|_| "Unknown error".to_string());
             tracing::error!("Auth0 authentication failed: {}", error_text);
-            return Err(Error::new("Authentication failed")
-                .extend_with(|_, e| e.set("details", error_text)));
+            return Err(
+                Error::from(GqlError::Unauthorized("Authentication failed".into()))
+                    .extend_with(|_, e| e.set("details", error_text)),
+            );
         }
 
         // Parse the token response
-        let token_response: TokenResponse = match response.json().await {
-            Ok(json) => json,
-            Err(e) => {
-                tracing::error!("Failed to parse Auth0 response: {}", e);
-                return Err(Error::new(format!("Failed to parse response: {}", e)));
-            }
-        };
+        let token_response: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| provider_error("Failed to parse response", e))?;
 
         tracing::debug!("Successfully obtained token");
 
         // Get user info
-        let user_info_url = format!("https://{}/userinfo", self.domain);
+        let user_info_url = format!("{}/userinfo", self.base_url);
         tracing::debug!("Requesting user info from: {}", user_info_url);
 
-        let user_info_response = match self
+        let user_info_response = self
             .client
             .get(&user_info_url)
             .bearer_auth(&token_response.access_token)
             .send()
             .await
-        {
-            Ok(resp) => resp,
-            Err(e) => {
-                tracing::error!("Failed to get user info: {}", e);
-                return Err(Error::new(format!("Failed to get user info: {}", e)));
-            }
-        };
+            .map_err(|e| provider_error("Failed to get user info", e))?;
 
         if !user_info_response.status().is_success() {
             tracing::error!("Failed to get user info: {}", user_info_response.status());
-            return Err(Error::new("Failed to get user info"));
+            return Err(GqlError::Internal("Failed to get user info".into()).into());
         }
 
-        let user_info: UserInfo = match user_info_response.json().await {
-            Ok(json) => json,
-            Err(e) => {
-                tracing::error!("Failed to parse user info: {}", e);
-                return Err(Error::new(format!("Failed to parse user info: {}", e)));
-            }
-        };
+        let user_info: UserInfo = user_info_response
+            .json()
+            .await
+            .map_err(|e| provider_error("Failed to parse user info", e))?;
 
         tracing::info!("Login successful for user: {}", email);
 
@@ -190,7 +451,7 @@ impl AuthProvider for Auth0Okta {
             &DecodingKey::from_secret(secret.as_bytes()),
             &Validation::default(),
         )
-        .map_err(|e| Error::new(format!("Invalid token: {}", e)))?;
+        .map_err(|e| GqlError::Unauthorized(format!("Invalid token: {}", e)))?;
 
         Ok(token_data.claims)
     }
@@ -205,7 +466,7 @@ pub struct TokenResponse {
     pub expires_in: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenClaims {
     pub sub: String,
     pub exp: usize,
@@ -213,6 +474,20 @@ pub struct TokenClaims {
     pub iss: Option<String>,
     pub aud: Option<String>,
     pub email: Option<String>,
+    /// Tenant (business group) the token is scoped to; tokens without one only see
+    /// globally-scoped rows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub business_group_id: Option<i64>,
+}
+
+impl TokenClaims {
+    /// Returns how long until `exp`, or zero once the token has expired.
+    pub fn remaining_lifetime(&self) -> Duration {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Duration::from_secs(self.exp as u64).saturating_sub(now)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -233,8 +508,34 @@ pub struct AuthResponse {
     pub user: User,
 }
 
-// Helper function to get user id from context
+/// The authenticated user of a single request, attached with `Request::data`
+#[derive(Clone, Copy, Debug)]
+pub struct CurrentUser(pub UuidScalar);
+
+/// The raw `Authorization: Bearer` token of a single request, attached with `Request::data`
+#[derive(Clone, Debug)]
+pub struct BearerToken(pub String);
+
+/// The tenant (business group) of a single request, attached with `Request::data`
+#[derive(Clone, Copy, Debug)]
+pub struct CurrentTenant(pub i64);
+
+/// Returns the tenant whose rows the request may read, preferring the request's
+/// `CurrentTenant` over `GraphQLContext::tenant_id`.
+///
+/// `None` means the request only sees globally-scoped rows.
+pub fn get_current_tenant_id(ctx: &Context<'_>) -> Result<Option<i64>> {
+    if let Some(CurrentTenant(tenant_id)) = ctx.data_opt::<CurrentTenant>() {
+        return Ok(Some(*tenant_id));
+    }
+    Ok(ctx.data::<GraphQLContext>()?.tenant_id)
+}
+
+// Helper function to get user id from context, preferring the request's `CurrentUser`
 pub fn get_current_user_id(ctx: &Context<'_>) -> Result<Option<UuidScalar>> {
+    if let Some(CurrentUser(user_id)) = ctx.data_opt::<CurrentUser>() {
+        return Ok(Some(*user_id));
+    }
     if let Ok(ctx_data) = ctx.data::<GraphQLContext>() {
         if let Some(user_id) = &ctx_data.current_user_id {
             return Ok(Some(*user_id));
@@ -242,3 +543,42 @@ pub fn get_current_user_id(ctx: &Context<'_>) -> Result<Option<UuidScalar>> {
     }
     Ok(None)
 }
+
+/// Guard that only admits requests made by an authenticated user
+pub struct AuthenticatedGuard;
+
+impl Guard for AuthenticatedGuard {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        match get_current_user_id(ctx)? {
+            Some(_) => Ok(()),
+            None => Err(GqlError::Unauthorized("Authentication required".into()).into()),
+        }
+    }
+}
+
+/// Returns true when `user_id` is listed in `ADMIN_USER_IDS`, ignoring case.
+pub fn is_admin(user_id: &str) -> bool {
+    env::var("ADMIN_USER_IDS").is_ok_and(|ids| {
+        ids.split(',')
+            .any(|id| id.trim().eq_ignore_ascii_case(user_id))
+    })
+}
+
+/// Guard that only admits authenticated users listed in `ADMIN_USER_IDS`
+///
+/// `ADMIN_USER_IDS` is a comma-separated list of user UUIDs; when it is unset nobody is
+/// an admin.
+pub struct AdminGuard;
+
+impl Guard for AdminGuard {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        AuthenticatedGuard.check(ctx).await?;
+        let user_id = get_current_user_id(ctx)?;
+
+        if user_id.is_some_and(|id| is_admin(&id.0.to_string())) {
+            Ok(())
+        } else {
+            Err(GqlError::Forbidden("Admin access required".into()).into())
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use crate::auth::{
+    Auth0Okta, AuthProvider, AuthResponse, CachedAuthProvider, LocalAuthProvider, TokenClaims,
+    AUTH_TIMEOUT_MESSAGE, INVALID_CREDENTIALS_MESSAGE,
+};
+use crate::db::DbConnection;
+use crate::graphql::GqlError;
+use crate::models::etl::NonEmptyString;
+use crate::models::user::CreateUser;
+use async_graphql::Result;
+use async_trait::async_trait;
+use axum::{
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::TcpListener;
+
+/// Serves a mock Auth0 tenant on an ephemeral local port and returns its base URL.
+///
+/// The token and userinfo endpoints answer after `token_delay` and `userinfo_delay`.
+async fn spawn_auth0(token_delay: Duration, userinfo_delay: Duration) -> String {
+    let router = Router::new()
+        .route(
+            "/oauth/token",
+            post(move || async move {
+                tokio::time::sleep(token_delay).await;
+                Json(json!({
+                    "access_token": "access",
+                    "refresh_token": "refresh",
+                    "token_type": "Bearer",
+                    "expires_in": 3600
+                }))
+            }),
+        )
+        .route(
+            "/userinfo",
+            get(move || async move {
+                tokio::time::sleep(userinfo_delay).await;
+                Json(json!({ "sub": "auth0|1", "email": "user@example.com", "nickname": "user" }))
+            }),
+        );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+fn provider(base_url: &str) -> Auth0Okta {
+    Auth0Okta::from_credentials("example.auth0.com", "client", "secret", "audience")
+        .with_base_url(base_url)
+        .with_timeout(Duration::from_millis(200))
+}
+
+#[tokio::test]
+async fn test_login_succeeds_against_responsive_provider() {
+    let base_url = spawn_auth0(Duration::ZERO, Duration::ZERO).await;
+
+    let response = provider(&base_url)
+        .login("user@example.com".to_string(), "password".to_string())
+        .await
+        .unwrap();
+
+    assert_eq!(response.token, "access");
+    assert_eq!(response.user.username, "user");
+}
+
+#[tokio::test]
+async fn test_login_times_out_on_slow_provider() {
+    for (token_delay, userinfo_delay) in [
+        (Duration::from_secs(5), Duration::ZERO),
+        (Duration::ZERO, Duration::from_secs(5)),
+    ] {
+        let base_url = spawn_auth0(token_delay, userinfo_delay).await;
+
+        let started = std::time::Instant::now();
+        let err = provider(&base_url)
+            .login("user@example.com".to_string(), "password".to_string())
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.message, AUTH_TIMEOUT_MESSAGE);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
+
+/// Provider that accepts tokens starting with `valid` and counts every verification
+struct CountingProvider {
+    verifications: Arc<AtomicUsize>,
+    lifetime_secs: u64,
+}
+
+#[async_trait]
+impl AuthProvider for CountingProvider {
+    async fn login(&self, _email: String, _password: String) -> Result<AuthResponse> {
+        Err(GqlError::Internal("not supported".into()).into())
+    }
+
+    async fn validate_token(&self, token: &str) -> Result<TokenClaims> {
+        self.verifications.fetch_add(1, Ordering::SeqCst);
+        if !token.starts_with("valid") {
+            return Err(GqlError::Unauthorized("Invalid token".into()).into());
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        Ok(TokenClaims {
+            sub: token.to_string(),
+            exp: (now + self.lifetime_secs) as usize,
+            iat: now as usize,
+            iss: None,
+            aud: None,
+            email: None,
+            business_group_id: None,
+        })
+    }
+}
+
+fn cached_provider(lifetime_secs: u64) -> (CachedAuthProvider<CountingProvider>, Arc<AtomicUsize>) {
+    let verifications = Arc::new(AtomicUsize::new(0));
+    let provider = CachedAuthProvider::new(CountingProvider {
+        verifications: verifications.clone(),
+        lifetime_secs,
+    });
+    (provider, verifications)
+}
+
+#[tokio::test]
+async fn test_repeat_validation_is_served_from_cache() {
+    let (provider, verifications) = cached_provider(3600);
+
+    let first = provider.validate_token("valid-a").await.unwrap();
+    let second = provider.validate_token("valid-a").await.unwrap();
+    assert_eq!(verifications.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        (first.sub, second.sub),
+        ("valid-a".into(), "valid-a".into())
+    );
+
+    // Other tokens are verified separately
+    let other = provider.validate_token("valid-b").await.unwrap();
+    assert_eq!(other.sub, "valid-b");
+    assert_eq!(verifications.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_invalid_tokens_are_never_cached() {
+    let (provider, verifications) = cached_provider(3600);
+
+    assert!(provider.validate_token("forged").await.is_err());
+    assert!(provider.validate_token("forged").await.is_err());
+    assert_eq!(verifications.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_cached_tokens_are_evicted_at_expiry() {
+    let (provider, verifications) = cached_provider(1);
+
+    provider.validate_token("valid-short").await.unwrap();
+    provider.validate_token("valid-short").await.unwrap();
+    assert_eq!(verifications.load(Ordering::SeqCst), 1);
+
+    tokio::time::sleep(Duration::from_millis(2100)).await;
+    provider.validate_token("valid-short").await.unwrap();
+    assert_eq!(verifications.load(Ordering::SeqCst), 2);
+}
+
+/// Creates a local account inside the test transaction and returns its email
+async fn local_account(db: &DbConnection<sqlx::Postgres>, password: &str) -> String {
+    let email = format!("local_{}@example.com", uuid::Uuid::new_v4());
+    db.create_user(CreateUser {
+        username: NonEmptyString(format!("local_{}", uuid::Uuid::new_v4())),
+        email: email.clone(),
+        password: Some(password.to_string()),
+    })
+    .await
+    .unwrap();
+    email
+}
+
+#[tokio::test]
+async fn test_local_login_with_correct_password_issues_valid_token() {
+    let db = DbConnection::new_for_test().await.unwrap();
+    let email = local_account(&db, "correct horse battery staple").await;
+
+    let stored: String = sqlx::query_scalar("SELECT password_hash FROM users WHERE email = $1")
+        .bind(&email)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert!(stored.starts_with("$argon2id$"));
+
+    let provider = LocalAuthProvider::new(db.pool.clone(), "test-secret");
+    let response = provider
+        .login(email.clone(), "correct horse battery staple".to_string())
+        .await
+        .unwrap();
+    assert_eq!(response.user.email, email);
+
+    let claims = provider.validate_token(&response.token).await.unwrap();
+    assert_eq!(claims.sub, response.user.id.0.to_string());
+    assert_eq!(claims.email.as_deref(), Some(email.as_str()));
+}
+
+#[tokio::test]
+async fn test_local_login_rejects_wrong_password_and_unknown_email() {
+    let db = DbConnection::new_for_test().await.unwrap();
+    let email = local_account(&db, "correct horse battery staple").await;
+    let provider = LocalAuthProvider::new(db.pool.clone(), "test-secret");
+
+    for (email, password) in [
+        (email, "Tr0ub4dor&3"),
+        (
+            "nobody@example.com".to_string(),
+            "correct horse battery staple",
+        ),
+    ] {
+        let err = provider
+            .login(email, password.to_string())
+            .await
+            .unwrap_err();
+        assert_eq!(err.message, INVALID_CREDENTIALS_MESSAGE);
+    }
+}
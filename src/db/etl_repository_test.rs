@@ -0,0 +1,212 @@
+use crate::db::{DbConnection, TaskOrderError};
+use crate::models::etl::{CreateJob, CreateTaskInput, JsonValueScalar, UuidScalar};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use uuid::Uuid;
+
+async fn setup_test_db() -> DbConnection<sqlx::Postgres> {
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .expect("Failed to create test database");
+
+    DbConnection { pool }
+}
+
+fn task_input(name: String) -> CreateTaskInput {
+    CreateTaskInput {
+        name,
+        description: None,
+        input_data: Some(JsonValueScalar(json!({ "source": "test" }))),
+        depends_on: None,
+    }
+}
+
+#[tokio::test]
+async fn test_create_job_with_tasks() {
+    let db = setup_test_db().await;
+
+    let job = CreateJob {
+        name: format!("testjob_{}", Uuid::new_v4()),
+        description: Some("created with tasks".to_string()),
+        callback_url: None,
+    };
+    let tasks = vec![
+        task_input("extract".to_string()),
+        task_input("load".to_string()),
+    ];
+
+    let (job, tasks) = db.create_job_with_tasks(job, tasks).await.unwrap();
+    assert_eq!(tasks.len(), 2);
+    assert!(tasks.iter().all(|task| task.job_id.0 == job.id.0));
+}
+
+#[tokio::test]
+async fn test_create_job_with_invalid_task_persists_nothing() {
+    let db = setup_test_db().await;
+    let job_name = format!("testjob_{}", Uuid::new_v4());
+
+    let job = CreateJob {
+        name: job_name.clone(),
+        description: None,
+        callback_url: None,
+    };
+    // Task names are VARCHAR(255), so this insert fails after the job and first task
+    let tasks = vec![
+        task_input("extract".to_string()),
+        task_input("x".repeat(300)),
+    ];
+
+    let result = db.create_job_with_tasks(job, tasks).await;
+    assert!(result.is_err());
+
+    let persisted: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM jobs WHERE name = $1")
+        .bind(&job_name)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(persisted, 0);
+}
+
+async fn set_dependency(
+    db: &DbConnection<sqlx::Postgres>,
+    task: UuidScalar,
+    depends_on: UuidScalar,
+) {
+    sqlx::query("UPDATE tasks SET depends_on = $1 WHERE id = $2")
+        .bind(depends_on.0)
+        .bind(task.0)
+        .execute(&db.pool)
+        .await
+        .expect("Failed to set task dependency");
+}
+
+#[tokio::test]
+async fn test_task_execution_order_linear_chain() {
+    let db = setup_test_db().await;
+
+    let job = CreateJob {
+        name: format!("testjob_{}", Uuid::new_v4()),
+        description: None,
+        callback_url: None,
+    };
+    // Created in reverse of the intended execution order
+    let tasks = vec![
+        task_input("load".to_string()),
+        task_input("transform".to_string()),
+        task_input("extract".to_string()),
+    ];
+    let (job, tasks) = db.create_job_with_tasks(job, tasks).await.unwrap();
+    let (load, transform, extract) = (tasks[0].id, tasks[1].id, tasks[2].id);
+
+    set_dependency(&db, transform, extract).await;
+    set_dependency(&db, load, transform).await;
+
+    let ordered = db.task_execution_order(job.id).await.unwrap();
+    let names: Vec<&str> = ordered.iter().map(|task| task.name.as_str()).collect();
+    assert_eq!(names, vec!["extract", "transform", "load"]);
+}
+
+#[tokio::test]
+async fn test_task_execution_order_detects_cycle() {
+    let db = setup_test_db().await;
+
+    let job = CreateJob {
+        name: format!("testjob_{}", Uuid::new_v4()),
+        description: None,
+        callback_url: None,
+    };
+    let tasks = vec![task_input("a".to_string()), task_input("b".to_string())];
+    let (job, tasks) = db.create_job_with_tasks(job, tasks).await.unwrap();
+
+    set_dependency(&db, tasks[0].id, tasks[1].id).await;
+    set_dependency(&db, tasks[1].id, tasks[0].id).await;
+
+    match db.task_execution_order(job.id).await {
+        Err(TaskOrderError::Cycle(ids)) => assert_eq!(ids.len(), 2),
+        other => panic!("expected a cycle error, got {:?}", other.map(|t| t.len())),
+    }
+}
+
+#[tokio::test]
+async fn test_updates_advance_updated_at_without_setting_it() {
+    let db = DbConnection::new_for_test().await.unwrap();
+    let (job_id, task_id, run_id, user_id) = (
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+    );
+
+    // Rows start with a stale timestamp so NOW() in the test transaction is later
+    let stale = "NOW() - INTERVAL '1 hour'";
+    sqlx::query(&format!(
+        "INSERT INTO jobs (id, name, status, created_at, updated_at) VALUES ($1, 'trigger_job', 'Pending', {stale}, {stale})"
+    ))
+    .bind(job_id)
+    .execute(&db.pool)
+    .await
+    .unwrap();
+    sqlx::query(&format!(
+        "INSERT INTO tasks (id, job_id, name, status, created_at, updated_at) VALUES ($1, $2, 'trigger_task', 'Pending', {stale}, {stale})"
+    ))
+    .bind(task_id)
+    .bind(job_id)
+    .execute(&db.pool)
+    .await
+    .unwrap();
+    sqlx::query(&format!(
+        "INSERT INTO pipeline_runs (id, job_id, status, created_at, updated_at) VALUES ($1, $2, 'Pending', {stale}, {stale})"
+    ))
+    .bind(run_id)
+    .bind(job_id)
+    .execute(&db.pool)
+    .await
+    .unwrap();
+    sqlx::query(&format!(
+        "INSERT INTO users (id, username, email, created_at, updated_at) VALUES ($1, $1::text, $1::text || '@example.com', {stale}, {stale})"
+    ))
+    .bind(user_id)
+    .execute(&db.pool)
+    .await
+    .unwrap();
+
+    for (table, id, update) in [
+        (
+            "jobs",
+            job_id,
+            "UPDATE jobs SET status = 'Running' WHERE id = $1",
+        ),
+        (
+            "tasks",
+            task_id,
+            "UPDATE tasks SET status = 'Running' WHERE id = $1",
+        ),
+        (
+            "pipeline_runs",
+            run_id,
+            "UPDATE pipeline_runs SET status = 'Running' WHERE id = $1",
+        ),
+        (
+            "users",
+            user_id,
+            "UPDATE users SET username = username || '_renamed' WHERE id = $1",
+        ),
+    ] {
+        sqlx::query(update)
+            .bind(id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        let advanced: bool = sqlx::query_scalar(&format!(
+            "SELECT updated_at > created_at FROM {} WHERE id = $1",
+            table
+        ))
+        .bind(id)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+        assert!(advanced, "{} updated_at was not advanced", table);
+    }
+}
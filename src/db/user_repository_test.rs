@@ -1,29 +1,12 @@
 use crate::db::DbConnection;
-use crate::models::etl::UuidScalar;
+use crate::models::etl::{NonEmptyString, UuidScalar};
 use crate::models::user::{CreateUser, UpdateUser};
-use sqlx::postgres::PgPoolOptions;
 use uuid::Uuid;
 
 async fn setup_test_db() -> DbConnection<sqlx::Postgres> {
-    let pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+    DbConnection::new_for_test()
         .await
-        .expect("Failed to create test database");
-
-    // Start a transaction that will be rolled back after the test
-    let mut tx = pool.begin().await.expect("Failed to start transaction");
-
-    // Clear the users table within the transaction
-    sqlx::query("DELETE FROM users")
-        .execute(&mut *tx)
-        .await
-        .expect("Failed to clear users table");
-
-    // Commit the transaction
-    tx.commit().await.expect("Failed to commit transaction");
-
-    DbConnection { pool }
+        .expect("Failed to create test database")
 }
 
 #[tokio::test]
@@ -31,8 +14,9 @@ async fn test_create_user() {
     let db = setup_test_db().await;
 
     let user = CreateUser {
-        username: format!("testuser_{}", Uuid::new_v4()),
+        username: NonEmptyString(format!("testuser_{}", Uuid::new_v4())),
         email: format!("test_{}@example.com", Uuid::new_v4()),
+        password: None,
     };
 
     let created = db.create_user(user).await.unwrap();
@@ -45,8 +29,9 @@ async fn test_get_user() {
     let db = setup_test_db().await;
 
     let user = CreateUser {
-        username: format!("testuser_{}", Uuid::new_v4()),
+        username: NonEmptyString(format!("testuser_{}", Uuid::new_v4())),
         email: format!("test_{}@example.com", Uuid::new_v4()),
+        password: None,
     };
 
     let created = db.create_user(user).await.unwrap();
@@ -62,14 +47,15 @@ async fn test_update_user() {
     let db = setup_test_db().await;
 
     let user = CreateUser {
-        username: format!("testuser_{}", Uuid::new_v4()),
+        username: NonEmptyString(format!("testuser_{}", Uuid::new_v4())),
         email: format!("test_{}@example.com", Uuid::new_v4()),
+        password: None,
     };
 
     let created = db.create_user(user).await.unwrap();
 
     let update = UpdateUser {
-        username: Some(format!("updateduser_{}", Uuid::new_v4())),
+        username: Some(NonEmptyString(format!("updateduser_{}", Uuid::new_v4()))),
         email: Some(format!("updated_{}@example.com", Uuid::new_v4())),
     };
 
@@ -83,8 +69,9 @@ async fn test_delete_user() {
     let db = setup_test_db().await;
 
     let user = CreateUser {
-        username: format!("testuser_{}", Uuid::new_v4()),
+        username: NonEmptyString(format!("testuser_{}", Uuid::new_v4())),
         email: format!("test_{}@example.com", Uuid::new_v4()),
+        password: None,
     };
 
     let created = db.create_user(user).await.unwrap();
@@ -105,3 +92,68 @@ async fn test_get_nonexistent_user() {
         .expect("Failed to get user");
     assert!(retrieved_user.is_none());
 }
+
+#[tokio::test]
+async fn test_changes_are_rolled_back_after_the_test() {
+    let db = setup_test_db().await;
+
+    let user = CreateUser {
+        username: NonEmptyString(format!("testuser_{}", Uuid::new_v4())),
+        email: format!("test_{}@example.com", Uuid::new_v4()),
+        password: None,
+    };
+    let created = db.create_user(user).await.unwrap();
+    assert!(db.get_user(created.id).await.unwrap().is_some());
+    db.pool.close().await;
+
+    let db = setup_test_db().await;
+    assert!(db.get_user(created.id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_search_users_filters_by_email_prefix() {
+    let db = setup_test_db().await;
+    let run = Uuid::new_v4().simple().to_string();
+
+    for local in ["alice", "Alicia", "bob", "al_x", "alyx"] {
+        db.create_user(CreateUser {
+            username: NonEmptyString(format!("{}_{}", local, Uuid::new_v4())),
+            email: format!("{}{}@example.com", run, local),
+            password: None,
+        })
+        .await
+        .unwrap();
+    }
+    let emails = |users: Vec<crate::models::user::User>| -> Vec<String> {
+        let mut emails: Vec<String> = users
+            .into_iter()
+            .map(|user| user.email.trim_start_matches(&run).to_string())
+            .collect();
+        emails.sort();
+        emails
+    };
+
+    let found = db
+        .search_users(&format!("{}ALI", run), 10, 0)
+        .await
+        .unwrap();
+    assert_eq!(
+        emails(found),
+        vec!["Alicia@example.com", "alice@example.com"]
+    );
+
+    // `_` is matched literally rather than as a wildcard
+    let found = db
+        .search_users(&format!("{}al_", run), 10, 0)
+        .await
+        .unwrap();
+    assert_eq!(emails(found), vec!["al_x@example.com"]);
+
+    let all = db.search_users(&run, 10, 0).await.unwrap();
+    assert_eq!(all.len(), 5);
+    let page = db.search_users(&run, 2, 1).await.unwrap();
+    let ids = |users: &[crate::models::user::User]| -> Vec<_> {
+        users.iter().map(|user| user.id.0).collect()
+    };
+    assert_eq!(ids(&page), ids(&all[1..3]));
+}
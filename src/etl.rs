@@ -1,15 +1,277 @@
 use anyhow::Result;
-use serde_json::Value;
+use futures::{Stream, StreamExt};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use notify::{EventKind, RecursiveMode, Watcher};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
 use sqlx::postgres::PgPool;
+use sqlx::{Postgres, QueryBuilder};
+use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
+use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::db::{classify_error, DbConnection, DbErrorKind};
+use crate::event_bus::EventSink;
+use crate::graphql::ETLEvent;
+use crate::models::etl::{Status, UuidScalar};
+
+/// Default time a watched file's size must stay unchanged before it is ingested
+pub const DEFAULT_WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Default largest file the pipeline will read, in bytes (100 MiB)
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Default deepest nesting of JSON arrays and objects the pipeline will parse
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 128;
+
+/// Default table that loaded documents are written to
+pub const DEFAULT_TARGET_TABLE: &str = "json_data";
+
+/// Returns the Postgres advisory lock key that serializes `process_directory` runs over
+/// `dir_path`: the first eight bytes of the SHA-256 of its canonical path, so every
+/// process and spelling of the same directory agrees on the key.
+pub fn directory_lock_key(dir_path: &Path) -> i64 {
+    let path = fs::canonicalize(dir_path).unwrap_or_else(|_| dir_path.to_path_buf());
+    let digest = Sha256::digest(path.to_string_lossy().as_bytes());
+    i64::from_be_bytes(
+        digest[..8]
+            .try_into()
+            .expect("SHA-256 digests are 32 bytes"),
+    )
+}
+
+/// Longest identifier Postgres keeps without truncating it
+const MAX_IDENTIFIER_LEN: usize = 63;
+
+/// Returns whether `name` is a plain, unquoted SQL identifier: an ASCII letter or
+/// underscore followed by letters, digits or underscores, at most 63 bytes long.
+///
+/// Table names are interpolated into SQL, so anything else is rejected outright rather
+/// than quoted.
+fn is_valid_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    name.len() <= MAX_IDENTIFIER_LEN
+        && (first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Returns the deepest array/object nesting in a JSON document, stopping early once it
+/// exceeds `limit`.
+///
+/// This is a lexical scan that only tracks brackets outside of strings, so it works on
+/// malformed input too and never recurses.
+fn json_nesting_depth(content: &str, limit: usize) -> usize {
+    let mut depth = 0;
+    let mut max_depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for byte in content.bytes() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > max_depth {
+                    max_depth = depth;
+                    if max_depth > limit {
+                        break;
+                    }
+                }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    max_depth
+}
+
+/// Returns the deepest element nesting in an XML document, stopping early once it
+/// exceeds `limit`.
+///
+/// Scanning stops at the first parse error too, leaving `xml_to_json` to report it.
+fn xml_nesting_depth(content: &str, limit: usize) -> usize {
+    let mut reader = Reader::from_str(content);
+    let mut depth = 0;
+    let mut max_depth = 0;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(_)) => {
+                depth += 1;
+                if depth > max_depth {
+                    max_depth = depth;
+                    if max_depth > limit {
+                        break;
+                    }
+                }
+            }
+            Ok(Event::Empty(_)) => max_depth = max_depth.max(depth + 1),
+            Ok(Event::End(_)) => depth = depth.saturating_sub(1),
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    max_depth
+}
+
+/// An XML element whose closing tag has not been read yet
+struct XmlFrame {
+    name: String,
+    fields: Map<String, Value>,
+    text: String,
+}
+
+impl XmlFrame {
+    fn open(start: &BytesStart) -> Result<Self, quick_xml::Error> {
+        let mut fields = Map::new();
+        for attr in start.attributes() {
+            let attr = attr?;
+            let key = format!("@{}", String::from_utf8_lossy(attr.key.as_ref()));
+            fields.insert(key, Value::String(attr.unescape_value()?.into_owned()));
+        }
+        Ok(Self {
+            name: String::from_utf8_lossy(start.name().as_ref()).into_owned(),
+            fields,
+            text: String::new(),
+        })
+    }
+
+    fn into_value(mut self) -> (String, Value) {
+        let value = if self.fields.is_empty() {
+            if self.text.is_empty() {
+                Value::Null
+            } else {
+                Value::String(self.text)
+            }
+        } else {
+            if !self.text.is_empty() {
+                self.fields
+                    .insert("#text".to_string(), Value::String(self.text));
+            }
+            Value::Object(self.fields)
+        };
+        (self.name, value)
+    }
+}
+
+/// Adds a child element to its parent, turning repeated names into arrays.
+fn insert_child(fields: &mut Map<String, Value>, name: String, value: Value) {
+    match fields.get_mut(&name) {
+        Some(Value::Array(items)) => items.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Value::Array(vec![first, value]);
+        }
+        None => {
+            fields.insert(name, value);
+        }
+    }
+}
+
+/// Converts an XML document into a JSON value.
+///
+/// The conversion is deterministic and loss-minimizing:
+/// * The result is an object with a single key, the root element's name.
+/// * Attributes become keys prefixed with `@` (`<item id="1">` → `{"@id": "1"}`).
+/// * Child elements become keys named after the element; repeated siblings with the
+///   same name are collected into an array in document order.
+/// * An element with only text becomes a string; when it also has attributes or child
+///   elements, its text is stored under `#text`.
+/// * An empty element without attributes becomes `null`.
+/// * All text and attribute values are kept as strings; no type inference is done.
+///   Surrounding whitespace in text is trimmed.
+///
+/// # Arguments
+/// * `content` - The XML document
+///
+/// # Returns
+/// * `Result<Value, String>` - The JSON value, or a description of the parse error
+pub fn xml_to_json(content: &str) -> Result<Value, String> {
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+
+    let mut stack: Vec<XmlFrame> = Vec::new();
+    let mut root: Option<(String, Value)> = None;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("at position {}: {}", reader.buffer_position(), e))?;
+        match event {
+            Event::Start(start) => {
+                stack.push(XmlFrame::open(&start).map_err(|e| e.to_string())?);
+            }
+            Event::Empty(start) => {
+                let (name, value) = XmlFrame::open(&start)
+                    .map_err(|e| e.to_string())?
+                    .into_value();
+                match stack.last_mut() {
+                    Some(parent) => insert_child(&mut parent.fields, name, value),
+                    None => root = Some((name, value)),
+                }
+            }
+            Event::Text(text) => {
+                if let Some(frame) = stack.last_mut() {
+                    frame
+                        .text
+                        .push_str(&text.unescape().map_err(|e| e.to_string())?);
+                }
+            }
+            Event::CData(data) => {
+                if let Some(frame) = stack.last_mut() {
+                    frame.text.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            Event::End(_) => {
+                let frame = stack
+                    .pop()
+                    .ok_or_else(|| "unexpected closing tag".to_string())?;
+                let (name, value) = frame.into_value();
+                match stack.last_mut() {
+                    Some(parent) => insert_child(&mut parent.fields, name, value),
+                    None => root = Some((name, value)),
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !stack.is_empty() {
+        return Err("unexpected end of document".to_string());
+    }
+
+    let (name, value) = root.ok_or_else(|| "document has no root element".to_string())?;
+    let mut document = Map::new();
+    document.insert(name, value);
+    Ok(Value::Object(document))
+}
 
 /// Error types that can occur during ETL pipeline operations.
 ///
 /// This enum represents various errors that can occur during the Extract, Transform, Load process.
 #[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum ETLPipelineError {
     /// Error occurred while reading a file
     #[error("Failed to read file: {0}")]
@@ -19,6 +281,10 @@ pub enum ETLPipelineError {
     #[error("Failed to parse JSON: {0}")]
     JsonParseError(String),
 
+    /// Error occurred while parsing XML data
+    #[error("Failed to parse XML: {0}")]
+    XmlParseError(String),
+
     /// Error occurred during database operations
     #[error("Database error: {0}")]
     DatabaseError(#[from] sqlx::Error),
@@ -26,14 +292,150 @@ pub enum ETLPipelineError {
     /// Error occurred while processing a directory
     #[error("Directory error: {0}")]
     DirectoryError(String),
+
+    /// The file is larger than the configured maximum size
+    #[error("File too large: {0}")]
+    FileTooLarge(String),
+
+    /// The document is nested more deeply than the configured maximum depth
+    #[error("Nesting too deep: {0}")]
+    NestingTooDeep(String),
+
+    /// The configured target table is not a valid SQL identifier
+    #[error("Invalid table name: {0}")]
+    InvalidTableName(String),
+
+    /// A configured ignore pattern is not a valid glob
+    #[error("Invalid ignore pattern: {0}")]
+    InvalidIgnorePattern(String),
+}
+
+impl ETLPipelineError {
+    /// Returns the variant name, used as the `error_kind` of dead-letter records.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ETLPipelineError::FileReadError(_) => "FileReadError",
+            ETLPipelineError::JsonParseError(_) => "JsonParseError",
+            ETLPipelineError::XmlParseError(_) => "XmlParseError",
+            ETLPipelineError::DatabaseError(_) => "DatabaseError",
+            ETLPipelineError::DirectoryError(_) => "DirectoryError",
+            ETLPipelineError::FileTooLarge(_) => "FileTooLarge",
+            ETLPipelineError::NestingTooDeep(_) => "NestingTooDeep",
+            ETLPipelineError::InvalidTableName(_) => "InvalidTableName",
+            ETLPipelineError::InvalidIgnorePattern(_) => "InvalidIgnorePattern",
+        }
+    }
+
+    /// Returns how the underlying database error is classified, for `DatabaseError`s.
+    pub fn db_error_kind(&self) -> Option<DbErrorKind> {
+        match self {
+            ETLPipelineError::DatabaseError(e) => Some(classify_error(e)),
+            _ => None,
+        }
+    }
+}
+
+/// What happened to a file loaded into `json_data`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadOutcome {
+    /// A new row was inserted
+    Inserted,
+    /// An existing row with the same file name was overwritten (reprocess mode)
+    Updated,
+    /// A row with the same file name already existed and was left alone
+    Skipped,
+    /// The file was empty or only whitespace, so there was nothing to load
+    EmptyFile,
+    /// The file name matched one of the pipeline's ignore patterns, so it wasn't read
+    Ignored,
+}
+
+/// The result of one file in a `process_directory_stream` run.
+#[derive(Debug)]
+pub struct FileResult {
+    /// Name of the file within the directory
+    pub file_name: String,
+    /// How the file was loaded, or why it failed
+    pub outcome: Result<LoadOutcome, ETLPipelineError>,
+}
+
+/// Why a `process_directory` run stopped before reaching the end of the directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EarlyStop {
+    /// The `max_files` limit was reached
+    MaxFiles,
+    /// `abort_after_consecutive_failures` files in a row failed
+    ConsecutiveFailures,
+    /// Another run over the same directory holds its lock, so no file was processed
+    Locked,
+}
+
+/// Per-outcome file counts for a `process_directory` run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProcessSummary {
+    /// Files inserted as new rows
+    pub inserted: usize,
+    /// Files that overwrote an existing row
+    pub updated: usize,
+    /// Files skipped because they were already loaded
+    pub skipped: usize,
+    /// Files skipped because they were empty or only whitespace
+    pub empty: usize,
+    /// Files that failed to load
+    pub failed: usize,
+    /// Files not read because their name matched an ignore pattern
+    pub ignored: usize,
+    /// Set when the run stopped early, leaving the remaining files unprocessed
+    pub stopped: Option<EarlyStop>,
+}
+
+impl ProcessSummary {
+    /// Returns the number of files processed, whatever their outcome.
+    ///
+    /// Ignored files were never processed, so they are not included.
+    pub fn processed(&self) -> usize {
+        self.inserted + self.updated + self.skipped + self.empty + self.failed
+    }
+
+    fn record(&mut self, outcome: LoadOutcome) {
+        match outcome {
+            LoadOutcome::Inserted => self.inserted += 1,
+            LoadOutcome::Updated => self.updated += 1,
+            LoadOutcome::Skipped => self.skipped += 1,
+            LoadOutcome::EmptyFile => self.empty += 1,
+            LoadOutcome::Ignored => self.ignored += 1,
+        }
+    }
 }
 
 /// A pipeline for Extract, Transform, Load (ETL) operations.
 ///
-/// This struct provides functionality to process JSON files and load them into a PostgreSQL database.
+/// This struct provides functionality to process JSON and XML files and load them into a PostgreSQL database.
 pub struct ETLPipeline {
     /// The PostgreSQL connection pool used for database operations
     pool: PgPool,
+    /// Optional sink to which ingestion events are published
+    event_sink: Option<Arc<dyn EventSink>>,
+    /// How long a watched file's size must be stable before it is read
+    watch_debounce: Duration,
+    /// When true, files are read and parsed but nothing is written to the database
+    dry_run: bool,
+    /// When true, files already in `json_data` are overwritten instead of skipped
+    reprocess: bool,
+    /// Files larger than this many bytes are rejected without being read
+    max_file_bytes: u64,
+    /// JSON documents nested deeper than this are rejected without being parsed
+    max_nesting_depth: usize,
+    /// `process_directory` stops after this many files
+    max_files: Option<usize>,
+    /// `process_directory` gives up after this many consecutive failures
+    abort_after_consecutive_failures: Option<usize>,
+    /// Table that documents are loaded into, checked by `is_valid_identifier`
+    target_table: String,
+    /// Glob patterns of file names that are never processed
+    ignore_patterns: Vec<String>,
+    /// `ignore_patterns`, compiled
+    ignore_set: GlobSet,
 }
 
 impl ETLPipeline {
@@ -46,119 +448,863 @@ impl ETLPipeline {
     /// A new `ETLPipeline` instance
     pub fn new(pool: PgPool) -> Self {
         info!("Creating new ETL pipeline instance");
-        Self { pool }
+        Self {
+            pool,
+            event_sink: None,
+            watch_debounce: DEFAULT_WATCH_DEBOUNCE,
+            dry_run: false,
+            reprocess: false,
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            max_files: None,
+            abort_after_consecutive_failures: None,
+            target_table: DEFAULT_TARGET_TABLE.to_string(),
+            ignore_patterns: Vec::new(),
+            ignore_set: GlobSet::empty(),
+        }
+    }
+
+    /// Publishes an `ETLEvent` for every file ingested by `watch_directory`.
+    ///
+    /// # Arguments
+    /// * `event_sink` - The sink events are published to, usually `EventChannels::sink`
+    pub fn with_event_sink(mut self, event_sink: Arc<dyn EventSink>) -> Self {
+        self.event_sink = Some(event_sink);
+        self
+    }
+
+    /// Sets how long a watched file's size must stay unchanged before it is ingested.
+    ///
+    /// # Arguments
+    /// * `debounce` - The stability window used to detect partially written files
+    pub fn with_watch_debounce(mut self, debounce: Duration) -> Self {
+        self.watch_debounce = debounce;
+        self
+    }
+
+    /// Enables dry-run mode, for validating new data sources.
+    ///
+    /// In dry-run mode files are read, parsed and validated as usual, and what would be
+    /// inserted is logged, but no rows are written to `json_data` or `json_data_failures`.
+    ///
+    /// # Arguments
+    /// * `dry_run` - Whether to skip all database writes
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Sets whether files already present in `json_data` are reloaded.
+    ///
+    /// Rows are matched by file name. When `reprocess` is true an existing row's data is
+    /// overwritten; when false (the default) the file is skipped.
+    ///
+    /// # Arguments
+    /// * `reprocess` - Whether to overwrite previously loaded files
+    pub fn with_reprocess(mut self, reprocess: bool) -> Self {
+        self.reprocess = reprocess;
+        self
+    }
+
+    /// Sets the largest file, in bytes, the pipeline will read (default 100 MiB).
+    ///
+    /// # Arguments
+    /// * `max_file_bytes` - Files larger than this fail with `FileTooLarge`
+    pub fn with_max_file_size(mut self, max_file_bytes: u64) -> Self {
+        self.max_file_bytes = max_file_bytes;
+        self
+    }
+
+    /// Sets the deepest array/object nesting allowed in JSON files (default 128).
+    ///
+    /// `serde_json` enforces its own limit of 128 levels, so larger values only relax this
+    /// check, not the parser.
+    ///
+    /// # Arguments
+    /// * `max_nesting_depth` - Documents nested deeper than this fail with `NestingTooDeep`
+    pub fn with_max_nesting_depth(mut self, max_nesting_depth: usize) -> Self {
+        self.max_nesting_depth = max_nesting_depth;
+        self
+    }
+
+    /// Sets how many files a `process_directory` run handles at most (default unlimited).
+    ///
+    /// Unsupported files don't count toward the limit.
+    ///
+    /// # Arguments
+    /// * `max_files` - The limit, or `None` to process the whole directory
+    pub fn with_max_files(mut self, max_files: Option<usize>) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// Makes `process_directory` give up once this many files in a row have failed
+    /// (default never).
+    ///
+    /// # Arguments
+    /// * `abort_after_consecutive_failures` - The failure streak that aborts the run, or `None` to never abort
+    pub fn with_abort_after_consecutive_failures(
+        mut self,
+        abort_after_consecutive_failures: Option<usize>,
+    ) -> Self {
+        self.abort_after_consecutive_failures = abort_after_consecutive_failures;
+        self
+    }
+
+    /// Sets the table that documents are loaded into (default `json_data`).
+    ///
+    /// The table needs the same `file_name` (unique), `data` and `updated_at` columns as
+    /// `json_data`. Failures are still recorded in `json_data_failures`.
+    ///
+    /// # Arguments
+    /// * `target_table` - An unquoted table name: a letter or underscore followed by letters, digits or underscores
+    ///
+    /// # Errors
+    /// * `InvalidTableName` - If `target_table` is not such an identifier
+    pub fn with_target_table(
+        mut self,
+        target_table: impl Into<String>,
+    ) -> Result<Self, ETLPipelineError> {
+        let target_table = target_table.into();
+        if !is_valid_identifier(&target_table) {
+            return Err(ETLPipelineError::InvalidTableName(format!(
+                "{:?} is not a valid table name",
+                target_table
+            )));
+        }
+        self.target_table = target_table;
+        Ok(self)
+    }
+
+    /// Sets glob patterns of file names to skip, such as `*.tmp.json` or `_manifest.json`.
+    ///
+    /// Patterns are matched against the file name only, not its directory. Matching files
+    /// in `process_directory` are not read and are counted as `ignored`; `watch_directory`
+    /// skips them too.
+    ///
+    /// # Arguments
+    /// * `ignore_patterns` - The patterns, in `globset` syntax
+    ///
+    /// # Errors
+    /// * `InvalidIgnorePattern` - If a pattern is not a valid glob
+    pub fn with_ignore_patterns(
+        mut self,
+        ignore_patterns: Vec<String>,
+    ) -> Result<Self, ETLPipelineError> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &ignore_patterns {
+            let glob = Glob::new(pattern).map_err(|e| {
+                ETLPipelineError::InvalidIgnorePattern(format!("{:?}: {}", pattern, e))
+            })?;
+            builder.add(glob);
+        }
+        self.ignore_set = builder
+            .build()
+            .map_err(|e| ETLPipelineError::InvalidIgnorePattern(e.to_string()))?;
+        self.ignore_patterns = ignore_patterns;
+        Ok(self)
+    }
+
+    /// Returns the glob patterns of file names the pipeline skips.
+    pub fn ignore_patterns(&self) -> &[String] {
+        &self.ignore_patterns
+    }
+
+    /// Returns whether `path`'s file name matches one of the ignore patterns.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        path.file_name()
+            .is_some_and(|name| self.ignore_set.is_match(name))
+    }
+
+    /// Reads a file, rejecting it first if it exceeds `max_file_bytes`.
+    fn read_file(&self, file_path: &Path) -> Result<String, ETLPipelineError> {
+        let size = fs::metadata(file_path)
+            .map_err(|e| {
+                error!("Failed to read file {:?}: {}", file_path, e);
+                ETLPipelineError::FileReadError(format!("{:?}: {}", file_path, e))
+            })?
+            .len();
+        if size > self.max_file_bytes {
+            error!(
+                "File {:?} is {} bytes, over the {} byte limit",
+                file_path, size, self.max_file_bytes
+            );
+            return Err(ETLPipelineError::FileTooLarge(format!(
+                "{:?}: {} bytes exceeds the {} byte limit",
+                file_path, size, self.max_file_bytes
+            )));
+        }
+
+        fs::read_to_string(file_path).map_err(|e| {
+            error!("Failed to read file {:?}: {}", file_path, e);
+            ETLPipelineError::FileReadError(format!("{:?}: {}", file_path, e))
+        })
     }
 
     /// Processes a single JSON file and loads it into the database.
     ///
     /// This method reads a JSON file, parses its contents, and stores both the file name
-    /// and the JSON data in the database.
+    /// and the JSON data in the database. A file whose name is already in `json_data` is
+    /// skipped, or overwritten when the pipeline was built `with_reprocess(true)`. Empty or
+    /// whitespace-only files are not parsed and report `LoadOutcome::EmptyFile`.
     ///
     /// # Arguments
     /// * `file_path` - The path to the JSON file to process
     ///
     /// # Returns
-    /// * `Result<(), ETLPipelineError>` - Ok(()) if successful, or an error if processing fails
+    /// * `Result<LoadOutcome, ETLPipelineError>` - Whether the file was inserted, updated or skipped, or an error if processing fails
     ///
     /// # Errors
     /// * `FileReadError` - If the file cannot be read
+    /// * `FileTooLarge` - If the file exceeds the configured maximum size
+    /// * `NestingTooDeep` - If the JSON is nested deeper than the configured maximum depth
     /// * `JsonParseError` - If the JSON content cannot be parsed
     /// * `DatabaseError` - If the database operation fails
-    pub async fn process_file(&self, file_path: &Path) -> Result<(), ETLPipelineError> {
+    pub async fn process_file(&self, file_path: &Path) -> Result<LoadOutcome, ETLPipelineError> {
         debug!("Processing file: {:?}", file_path);
 
-        let content = fs::read_to_string(file_path).map_err(|e| {
-            error!("Failed to read file {:?}: {}", file_path, e);
-            ETLPipelineError::FileReadError(format!("{:?}: {}", file_path, e))
-        })?;
+        let content = self.read_file(file_path)?;
+
+        if content.trim().is_empty() {
+            warn!("Skipping empty file: {:?}", file_path);
+            return Ok(LoadOutcome::EmptyFile);
+        }
+
+        if json_nesting_depth(&content, self.max_nesting_depth) > self.max_nesting_depth {
+            error!(
+                "JSON in file {:?} is nested deeper than {} levels",
+                file_path, self.max_nesting_depth
+            );
+            return Err(ETLPipelineError::NestingTooDeep(format!(
+                "{:?}: exceeds the maximum depth of {}",
+                file_path, self.max_nesting_depth
+            )));
+        }
 
         let json_value: Value = serde_json::from_str(&content).map_err(|e| {
             error!("Failed to parse JSON in file {:?}: {}", file_path, e);
             ETLPipelineError::JsonParseError(format!("{:?}: {}", file_path, e))
         })?;
 
+        self.load_value(file_path, json_value).await
+    }
+
+    /// Processes a single XML file and loads it into the database as JSON.
+    ///
+    /// The document is converted with `xml_to_json` and stored in `json_data` exactly
+    /// like the JSON path.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the XML file to process
+    ///
+    /// # Returns
+    /// * `Result<LoadOutcome, ETLPipelineError>` - Whether the file was inserted, updated or skipped, or an error if processing fails
+    ///
+    /// # Errors
+    /// * `FileReadError` - If the file cannot be read
+    /// * `FileTooLarge` - If the file exceeds the configured maximum size
+    /// * `NestingTooDeep` - If elements are nested deeper than the configured maximum depth
+    /// * `XmlParseError` - If the XML content cannot be parsed
+    /// * `DatabaseError` - If the database operation fails
+    pub async fn process_xml_file(
+        &self,
+        file_path: &Path,
+    ) -> Result<LoadOutcome, ETLPipelineError> {
+        debug!("Processing XML file: {:?}", file_path);
+
+        let content = self.read_file(file_path)?;
+
+        if content.trim().is_empty() {
+            warn!("Skipping empty file: {:?}", file_path);
+            return Ok(LoadOutcome::EmptyFile);
+        }
+
+        if xml_nesting_depth(&content, self.max_nesting_depth) > self.max_nesting_depth {
+            error!(
+                "XML in file {:?} is nested deeper than {} levels",
+                file_path, self.max_nesting_depth
+            );
+            return Err(ETLPipelineError::NestingTooDeep(format!(
+                "{:?}: exceeds the maximum depth of {}",
+                file_path, self.max_nesting_depth
+            )));
+        }
+
+        let json_value = xml_to_json(&content).map_err(|e| {
+            error!("Failed to parse XML in file {:?}: {}", file_path, e);
+            ETLPipelineError::XmlParseError(format!("{:?}: {}", file_path, e))
+        })?;
+
+        self.load_value(file_path, json_value).await
+    }
+
+    /// Inserts a parsed document into the target table under the file's name.
+    async fn load_value(
+        &self,
+        file_path: &Path,
+        json_value: Value,
+    ) -> Result<LoadOutcome, ETLPipelineError> {
         let file_name = file_path
             .file_name()
             .and_then(|name| name.to_str())
             .unwrap_or("unknown")
             .to_string();
 
+        if self.dry_run {
+            let outcome = match self.is_file_processed(&file_name).await? {
+                false => LoadOutcome::Inserted,
+                true if self.reprocess => LoadOutcome::Updated,
+                true => LoadOutcome::Skipped,
+            };
+            info!(
+                "Dry run: would load {} bytes of data from file {} ({:?})",
+                json_value.to_string().len(),
+                file_name,
+                outcome
+            );
+            return Ok(outcome);
+        }
+
         debug!("Inserting data from file: {}", file_name.clone());
 
-        sqlx::query(
+        let mut query = QueryBuilder::<Postgres>::new("INSERT INTO ");
+        query
+            .push(&self.target_table)
+            .push(" (file_name, data) VALUES (")
+            .push_bind(file_name.clone())
+            .push(", ")
+            .push_bind(json_value)
+            .push(")");
+        // `xmax = 0` holds for freshly inserted rows and not for rows updated by the upsert
+        if self.reprocess {
+            query.push(
+                " ON CONFLICT (file_name) DO UPDATE SET data = EXCLUDED.data, updated_at = NOW() \
+                 RETURNING (xmax = 0)",
+            );
+        } else {
+            query.push(" ON CONFLICT (file_name) DO NOTHING RETURNING TRUE");
+        }
+        let inserted: Option<bool> = query
+            .build_query_scalar()
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| {
+                error!(
+                    "Database error ({:?}) while processing file {:?}: {}",
+                    classify_error(&e),
+                    file_path,
+                    e
+                );
+                ETLPipelineError::DatabaseError(e)
+            })?;
+
+        let outcome = match inserted {
+            Some(true) => LoadOutcome::Inserted,
+            Some(false) => LoadOutcome::Updated,
+            None => LoadOutcome::Skipped,
+        };
+        match outcome {
+            LoadOutcome::Skipped => info!("Skipped already loaded file: {}", file_name),
+            _ => info!("Successfully processed file: {} ({:?})", file_name, outcome),
+        }
+        Ok(outcome)
+    }
+
+    /// Processes all JSON and XML files in a directory.
+    ///
+    /// This method scans a directory and processes each `.json` file using `process_file`
+    /// and each `.xml` file using `process_xml_file`. Files that fail are recorded in the
+    /// `json_data_failures` table so they can be inspected or retried with `retry_failures`.
+    /// Processed and failed counts are the same in dry-run mode, where nothing is written.
+    /// Files matching an ignore pattern (see `with_ignore_patterns`) are counted as
+    /// `ignored` without being read.
+    ///
+    /// The run stops early, returning the counts so far with `stopped` set, once
+    /// `max_files` files have been processed while more remain, or once
+    /// `abort_after_consecutive_failures` files in a row have failed.
+    ///
+    /// Runs over the same directory are serialized across processes with a Postgres
+    /// advisory lock keyed by `directory_lock_key`. If another run holds it, nothing is
+    /// processed and the summary's `stopped` is `Locked`. Dry runs write nothing and
+    /// don't take the lock.
+    ///
+    /// This consumes `process_directory_stream`; use that directly to observe each file's
+    /// result as it is produced.
+    ///
+    /// # Arguments
+    /// * `dir_path` - The path to the directory containing JSON and XML files
+    ///
+    /// # Returns
+    /// * `Result<ProcessSummary, ETLPipelineError>` - Counts of inserted, updated, skipped and failed files, or an error if the directory cannot be read
+    ///
+    /// # Errors
+    /// * `DirectoryError` - If the directory cannot be read
+    /// * `DatabaseError` - If the directory's lock cannot be queried
+    pub async fn process_directory(
+        &self,
+        dir_path: &Path,
+    ) -> Result<ProcessSummary, ETLPipelineError> {
+        if self.dry_run {
+            return self.process_directory_locked(dir_path).await;
+        }
+        // Report an unreadable directory as such rather than waiting on its lock
+        fs::read_dir(dir_path)
+            .map_err(|e| ETLPipelineError::DirectoryError(format!("{:?}: {}", dir_path, e)))?;
+
+        let db = DbConnection {
+            pool: self.pool.clone(),
+        };
+        let locked_run = db
+            .with_advisory_lock(directory_lock_key(dir_path), || {
+                self.process_directory_locked(dir_path)
+            })
+            .await?;
+
+        locked_run.unwrap_or_else(|| {
+            warn!(
+                "Skipping directory processing of {:?}: another run holds its lock",
+                dir_path
+            );
+            Ok(ProcessSummary {
+                stopped: Some(EarlyStop::Locked),
+                ..ProcessSummary::default()
+            })
+        })
+    }
+
+    /// The body of `process_directory`, run while holding the directory's lock (except
+    /// in dry runs)
+    async fn process_directory_locked(
+        &self,
+        dir_path: &Path,
+    ) -> Result<ProcessSummary, ETLPipelineError> {
+        let mut summary = ProcessSummary::default();
+        let mut consecutive_failures = 0;
+
+        let results = self.process_directory_stream(dir_path);
+        futures::pin_mut!(results);
+
+        loop {
+            if self.max_files.is_some_and(|max| summary.processed() >= max) {
+                // Reaching the limit on the directory's last file isn't an early stop
+                if self.has_unprocessed_files(dir_path, summary.processed()) {
+                    warn!(
+                        "Stopping directory processing of {:?} after reaching the limit of {} files",
+                        dir_path,
+                        summary.processed()
+                    );
+                    summary.stopped = Some(EarlyStop::MaxFiles);
+                }
+                break;
+            }
+
+            let Some(result) = results.next().await else {
+                break;
+            };
+            match result.outcome {
+                Ok(LoadOutcome::Ignored) => summary.record(LoadOutcome::Ignored),
+                Ok(outcome) => {
+                    summary.record(outcome);
+                    consecutive_failures = 0;
+                }
+                Err(e @ ETLPipelineError::DirectoryError(_)) => return Err(e),
+                Err(_) => {
+                    summary.failed += 1;
+                    consecutive_failures += 1;
+                }
+            }
+
+            if self
+                .abort_after_consecutive_failures
+                .is_some_and(|max| consecutive_failures >= max)
+            {
+                error!(
+                    "Aborting directory processing of {:?} after {} consecutive failures",
+                    dir_path, consecutive_failures
+                );
+                summary.stopped = Some(EarlyStop::ConsecutiveFailures);
+                break;
+            }
+        }
+
+        info!(
+            "Directory processing complete. Inserted: {}, Updated: {}, Skipped: {}, Empty: {}, Failed: {}, Ignored: {}",
+            summary.inserted,
+            summary.updated,
+            summary.skipped,
+            summary.empty,
+            summary.failed,
+            summary.ignored
+        );
+
+        if summary.failed > 0 {
+            warn!("Some files failed to process. Check error logs for details.");
+        }
+
+        Ok(summary)
+    }
+
+    /// Processes the JSON and XML files in a directory, yielding each file's result as
+    /// soon as it has been loaded.
+    ///
+    /// Files are handled exactly as in `process_directory`, including recording failures
+    /// in `json_data_failures`, but `max_files` and `abort_after_consecutive_failures` are
+    /// not applied; drop the stream to stop early. Files are only processed while the
+    /// stream is polled.
+    ///
+    /// If the directory or one of its entries cannot be read, a final result with a
+    /// `DirectoryError` outcome and the directory's path as `file_name` ends the stream.
+    ///
+    /// # Arguments
+    /// * `dir_path` - The path to the directory containing JSON and XML files
+    pub fn process_directory_stream<'a>(
+        &'a self,
+        dir_path: &'a Path,
+    ) -> impl Stream<Item = FileResult> + 'a {
+        async_stream::stream! {
+            info!("Processing directory: {:?}", dir_path);
+
+            let entries = match fs::read_dir(dir_path) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    error!("Failed to read directory {:?}: {}", dir_path, e);
+                    yield FileResult {
+                        file_name: dir_path.display().to_string(),
+                        outcome: Err(ETLPipelineError::DirectoryError(format!(
+                            "{:?}: {}",
+                            dir_path, e
+                        ))),
+                    };
+                    return;
+                }
+            };
+
+            for entry in entries {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        error!("Failed to read directory entry: {}", e);
+                        yield FileResult {
+                            file_name: dir_path.display().to_string(),
+                            outcome: Err(ETLPipelineError::DirectoryError(format!(
+                                "Failed to read entry: {}",
+                                e
+                            ))),
+                        };
+                        return;
+                    }
+                };
+
+                let path = entry.path();
+                if self.is_ignored(&path) {
+                    debug!("Ignoring file matching an ignore pattern: {:?}", path);
+                    yield FileResult {
+                        file_name: entry.file_name().to_string_lossy().into_owned(),
+                        outcome: Ok(LoadOutcome::Ignored),
+                    };
+                    continue;
+                }
+                let Some(outcome) = self.process_supported_file(&path).await else {
+                    continue;
+                };
+                if let Err(e) = &outcome {
+                    error!("Failed to process file {:?}: {}", path, e);
+                    self.record_failure(&path, e).await;
+                }
+                yield FileResult {
+                    file_name: entry.file_name().to_string_lossy().into_owned(),
+                    outcome,
+                };
+            }
+        }
+    }
+
+    /// Re-attempts every file recorded in `json_data_failures`.
+    ///
+    /// Each failure whose file now loads successfully is deleted from the table. Failures
+    /// that still fail have their error details and `failed_at` refreshed. In dry-run mode
+    /// the table is left untouched and the returned count is what would be recovered.
+    ///
+    /// # Returns
+    /// * `Result<usize, ETLPipelineError>` - The number of files recovered, or an error if the failures cannot be read
+    ///
+    /// # Errors
+    /// * `DatabaseError` - If the failures table cannot be read or updated
+    pub async fn retry_failures(&self) -> Result<usize, ETLPipelineError> {
+        let failures: Vec<(i32, String)> =
+            sqlx::query_as("SELECT id, file_path FROM json_data_failures ORDER BY failed_at")
+                .fetch_all(&self.pool)
+                .await?;
+
+        info!("Retrying {} failed files", failures.len());
+        let mut recovered = 0;
+
+        for (id, file_path) in failures {
+            let path = PathBuf::from(&file_path);
+            let result = match self.process_supported_file(&path).await {
+                Some(result) => result,
+                None => self.process_file(&path).await,
+            };
+
+            if self.dry_run {
+                if result.is_ok() {
+                    recovered += 1;
+                }
+                continue;
+            }
+
+            match result {
+                Ok(_) => {
+                    sqlx::query("DELETE FROM json_data_failures WHERE id = $1")
+                        .bind(id)
+                        .execute(&self.pool)
+                        .await?;
+                    recovered += 1;
+                }
+                Err(e) => {
+                    warn!("Retry failed for {:?}: {}", path, e);
+                    sqlx::query(
+                        r#"
+                        UPDATE json_data_failures
+                        SET error_kind = $1, error_detail = $2, failed_at = NOW()
+                        WHERE id = $3
+                        "#,
+                    )
+                    .bind(e.kind())
+                    .bind(e.to_string())
+                    .bind(id)
+                    .execute(&self.pool)
+                    .await?;
+                }
+            }
+        }
+
+        info!("Recovered {} previously failed files", recovered);
+        Ok(recovered)
+    }
+
+    /// Returns whether `dir_path` holds more JSON and XML files outside the ignore
+    /// patterns than the `processed` already loaded from it.
+    fn has_unprocessed_files(&self, dir_path: &Path, processed: usize) -> bool {
+        let Ok(entries) = fs::read_dir(dir_path) else {
+            return false;
+        };
+        entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                matches!(
+                    path.extension().and_then(|s| s.to_str()),
+                    Some("json" | "xml")
+                ) && !self.is_ignored(path)
+            })
+            .nth(processed)
+            .is_some()
+    }
+
+    /// Processes a file according to its extension, or returns None if it is not JSON or XML.
+    async fn process_supported_file(
+        &self,
+        path: &Path,
+    ) -> Option<Result<LoadOutcome, ETLPipelineError>> {
+        match path.extension().and_then(|s| s.to_str()) {
+            Some("json") => Some(self.process_file(path).await),
+            Some("xml") => Some(self.process_xml_file(path).await),
+            _ => None,
+        }
+    }
+
+    /// Records a failed file in `json_data_failures`, logging if the record cannot be written.
+    ///
+    /// A file keeps a single row: failing again replaces its error and failure time.
+    /// Nothing is recorded in dry-run mode, or when the file failed because the database
+    /// was unreachable, since the record couldn't be written either.
+    async fn record_failure(&self, path: &Path, err: &ETLPipelineError) {
+        if self.dry_run {
+            return;
+        }
+        if err.db_error_kind() == Some(DbErrorKind::Connection) {
+            warn!(
+                "Not recording failure of {:?}: the database is unavailable",
+                path
+            );
+            return;
+        }
+
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("unknown")
+            .to_string();
+
+        let result = sqlx::query(
             r#"
-            INSERT INTO json_data (file_name, data)
-            VALUES ($1, $2)
+            INSERT INTO json_data_failures (file_name, file_path, error_kind, error_detail)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (file_path) DO UPDATE
+            SET error_kind = EXCLUDED.error_kind,
+                error_detail = EXCLUDED.error_detail,
+                failed_at = NOW()
             "#,
         )
-        .bind(file_name.clone())
-        .bind(json_value)
+        .bind(file_name)
+        .bind(path.to_string_lossy().into_owned())
+        .bind(err.kind())
+        .bind(err.to_string())
         .execute(&self.pool)
-        .await
-        .map_err(|e| {
-            error!(
-                "Database error while processing file {:?}: {}",
-                file_path, e
-            );
-            ETLPipelineError::DatabaseError(e)
-        })?;
+        .await;
 
-        debug!("Inserted data from file: {}", file_name);
-        info!("Successfully processed file: {}", file_name);
-        Ok(())
+        if let Err(e) = result {
+            error!("Failed to record failure for {:?}: {}", path, e);
+        }
     }
 
-    /// Processes all JSON files in a directory.
+    /// Watches a directory and ingests each new JSON file as it lands.
     ///
-    /// This method scans a directory for JSON files and processes each one using `process_file`.
+    /// Files are only read once their size has been stable for the configured debounce
+    /// window, so partially written exports are not parsed. Files already ingested, either
+    /// during this watch or by an earlier run (matched by file name in `json_data`), are
+    /// skipped. An `ETLEvent` with type `FileIngested` is emitted for each ingested file.
+    ///
+    /// This method runs until the watcher shuts down.
     ///
     /// # Arguments
-    /// * `dir_path` - The path to the directory containing JSON files
+    /// * `dir_path` - The path to the directory to watch
     ///
     /// # Returns
-    /// * `Result<(), ETLPipelineError>` - Ok(()) if successful, or an error if processing fails
+    /// * `Result<(), ETLPipelineError>` - Ok(()) when the watcher stops, or an error if it cannot start
     ///
     /// # Errors
-    /// * `DirectoryError` - If the directory cannot be read
-    /// * Any error from `process_file` if file processing fails
-    pub async fn process_directory(&self, dir_path: &Path) -> Result<(), ETLPipelineError> {
-        info!("Processing directory: {:?}", dir_path);
+    /// * `DirectoryError` - If the directory cannot be watched
+    pub async fn watch_directory(&self, dir_path: &Path) -> Result<(), ETLPipelineError> {
+        info!("Watching directory: {:?}", dir_path);
 
-        let entries = fs::read_dir(dir_path).map_err(|e| {
-            error!("Failed to read directory {:?}: {}", dir_path, e);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |res| {
+            let _ = tx.send(res);
+        })
+        .map_err(|e| {
+            error!("Failed to create watcher for {:?}: {}", dir_path, e);
             ETLPipelineError::DirectoryError(format!("{:?}: {}", dir_path, e))
         })?;
+        watcher
+            .watch(dir_path, RecursiveMode::NonRecursive)
+            .map_err(|e| {
+                error!("Failed to watch directory {:?}: {}", dir_path, e);
+                ETLPipelineError::DirectoryError(format!("{:?}: {}", dir_path, e))
+            })?;
 
-        let mut processed_files = 0;
-        let mut failed_files = 0;
+        let mut seen: HashSet<PathBuf> = HashSet::new();
 
-        for entry in entries {
-            let entry = entry.map_err(|e| {
-                error!("Failed to read directory entry: {}", e);
-                ETLPipelineError::DirectoryError(format!("Failed to read entry: {}", e))
-            })?;
+        while let Some(res) = rx.recv().await {
+            let event: notify::Event = match res {
+                Ok(event) => event,
+                Err(e) => {
+                    error!("Watcher error on {:?}: {}", dir_path, e);
+                    continue;
+                }
+            };
 
-            let path = entry.path();
-            if path.extension().and_then(|s| s.to_str()) == Some("json") {
-                match self.process_file(&path).await {
-                    Ok(_) => processed_files += 1,
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                continue;
+            }
+
+            for path in event.paths {
+                if path.extension().and_then(|s| s.to_str()) != Some("json")
+                    || seen.contains(&path)
+                    || self.is_ignored(&path)
+                {
+                    continue;
+                }
+
+                if !self.wait_for_stable_size(&path).await {
+                    continue;
+                }
+                seen.insert(path.clone());
+
+                let file_name = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or("unknown")
+                    .to_string();
+
+                match self.is_file_processed(&file_name).await {
+                    Ok(true) => {
+                        debug!("Skipping already processed file: {}", file_name);
+                        continue;
+                    }
+                    Ok(false) => {}
                     Err(e) => {
-                        error!("Failed to process file {:?}: {}", path, e);
-                        failed_files += 1;
+                        error!("Failed to check whether {:?} was processed: {}", path, e);
+                        continue;
+                    }
+                }
+
+                match self.process_file(&path).await {
+                    Ok(LoadOutcome::EmptyFile) => {
+                        // Ingest it once content is written
+                        seen.remove(&path);
                     }
+                    Ok(_) => self.emit_file_event(&file_name),
+                    Err(e) => error!("Failed to process watched file {:?}: {}", path, e),
                 }
             }
         }
 
-        info!(
-            "Directory processing complete. Processed: {}, Failed: {}",
-            processed_files, failed_files
-        );
+        info!("Stopped watching directory: {:?}", dir_path);
+        Ok(())
+    }
 
-        if failed_files > 0 {
-            warn!("Some files failed to process. Check error logs for details.");
+    /// Waits until a file's size is unchanged across one debounce window.
+    ///
+    /// Returns `false` if the file disappears or is empty.
+    async fn wait_for_stable_size(&self, path: &Path) -> bool {
+        let mut last_size = None;
+        loop {
+            tokio::time::sleep(self.watch_debounce).await;
+            let size = match fs::metadata(path) {
+                Ok(metadata) => metadata.len(),
+                Err(e) => {
+                    debug!("Watched file {:?} is no longer readable: {}", path, e);
+                    return false;
+                }
+            };
+            if last_size == Some(size) {
+                return size > 0;
+            }
+            last_size = Some(size);
         }
+    }
 
-        Ok(())
+    /// Checks whether a file with the given name has already been loaded into the target
+    /// table.
+    async fn is_file_processed(&self, file_name: &str) -> Result<bool, ETLPipelineError> {
+        let mut query = QueryBuilder::<Postgres>::new("SELECT EXISTS(SELECT 1 FROM ");
+        query
+            .push(&self.target_table)
+            .push(" WHERE file_name = ")
+            .push_bind(file_name)
+            .push(")");
+        let exists: bool = query.build_query_scalar().fetch_one(&self.pool).await?;
+        Ok(exists)
+    }
+
+    /// Publishes a `FileIngested` event, if an event sink is configured.
+    fn emit_file_event(&self, file_name: &str) {
+        if let Some(event_sink) = &self.event_sink {
+            event_sink.publish(&ETLEvent {
+                event_id: None,
+                event_type: "FileIngested".to_string(),
+                entity_id: UuidScalar(Uuid::new_v4()),
+                status: Some(Status::Completed),
+                previous_status: None,
+                data: Some(
+                    serde_json::json!({ "file_name": file_name })
+                        .to_string()
+                        .into(),
+                ),
+                business_group_id: None,
+            });
+        }
     }
 }
@@ -0,0 +1,564 @@
+use crate::db::DbConnection;
+use crate::etl::{
+    directory_lock_key, xml_to_json, ETLPipeline, ETLPipelineError, EarlyStop, LoadOutcome,
+};
+use futures::StreamExt;
+use serde_json::json;
+
+#[test]
+fn test_xml_to_json_converts_fixture() {
+    let content = std::fs::read_to_string(
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("data/xml/sample.xml"),
+    )
+    .unwrap();
+
+    let value = xml_to_json(&content).unwrap();
+
+    assert_eq!(
+        value,
+        json!({
+            "export": {
+                "@source": "legacy-feed",
+                "@generated": "2025-05-01T12:00:00Z",
+                "user": {
+                    "@id": "42",
+                    "name": "John Doe",
+                    "address": { "street": "123 Main St", "city": "Anytown" }
+                },
+                "order": {
+                    "@id": "12345",
+                    "item": [
+                        { "@sku": "W-1", "@quantity": "2", "#text": "Widget" },
+                        { "@sku": "G-7", "@quantity": "1", "#text": "Gadget" }
+                    ],
+                    "notes": null,
+                    "comment": "Deliver after 5pm & ring twice"
+                }
+            }
+        })
+    );
+}
+
+#[test]
+fn test_xml_to_json_rejects_malformed_documents() {
+    assert!(xml_to_json("<root><open></root>").is_err());
+    assert!(xml_to_json("<root>").is_err());
+    assert!(xml_to_json("").is_err());
+}
+
+#[tokio::test]
+async fn test_malformed_file_lands_in_failures_table() {
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .expect("Failed to create test database");
+
+    let dir = std::env::temp_dir().join(format!("dds_etl_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let bad_file = dir.join("broken.json");
+    std::fs::write(&bad_file, "{ \"unterminated\": ").unwrap();
+
+    let pipeline = ETLPipeline::new(pool.clone());
+    pipeline.process_directory(&dir).await.unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let (file_name, error_kind): (String, String) =
+        sqlx::query_as("SELECT file_name, error_kind FROM json_data_failures WHERE file_path = $1")
+            .bind(bad_file.to_string_lossy().into_owned())
+            .fetch_one(&pool)
+            .await
+            .expect("malformed file should be recorded as a failure");
+
+    assert_eq!(file_name, "broken.json");
+    assert_eq!(error_kind, "JsonParseError");
+}
+
+#[tokio::test]
+async fn test_repeated_failure_keeps_one_failures_row() {
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .expect("Failed to create test database");
+
+    let dir = std::env::temp_dir().join(format!("dds_etl_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let bad_file = dir.join("broken.json");
+    std::fs::write(&bad_file, "{ \"unterminated\": ").unwrap();
+
+    let pipeline = ETLPipeline::new(pool.clone());
+    pipeline.process_directory(&dir).await.unwrap();
+    std::fs::write(&bad_file, "<not json>").unwrap();
+    pipeline.process_directory(&dir).await.unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        "SELECT error_kind, error_detail FROM json_data_failures WHERE file_path = $1",
+    )
+    .bind(bad_file.to_string_lossy().into_owned())
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].0, "JsonParseError");
+    assert!(rows[0].1.contains("expected value"), "{}", rows[0].1);
+}
+
+#[tokio::test]
+async fn test_dry_run_writes_no_rows() {
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .expect("Failed to create test database");
+
+    let dir = std::env::temp_dir().join(format!("dds_etl_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let good_file = format!("dry_run_{}.json", uuid::Uuid::new_v4());
+    std::fs::write(dir.join(&good_file), r#"{ "user": { "name": "Jane" } }"#).unwrap();
+    let bad_file = dir.join("dry_run_broken.json");
+    std::fs::write(&bad_file, "{ \"unterminated\": ").unwrap();
+
+    let pipeline = ETLPipeline::new(pool.clone()).with_dry_run(true);
+    pipeline.process_directory(&dir).await.unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let loaded: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM json_data WHERE file_name = $1")
+        .bind(&good_file)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    let failures: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM json_data_failures WHERE file_path = $1")
+            .bind(bad_file.to_string_lossy().into_owned())
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(loaded, 0);
+    assert_eq!(failures, 0);
+}
+
+#[tokio::test]
+async fn test_reprocess_overwrites_and_default_skips_loaded_files() {
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .expect("Failed to create test database");
+
+    let dir = std::env::temp_dir().join(format!("dds_etl_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file_name = format!("reprocess_{}.json", uuid::Uuid::new_v4());
+    let path = dir.join(&file_name);
+
+    std::fs::write(&path, r#"{ "version": 1 }"#).unwrap();
+    let pipeline = ETLPipeline::new(pool.clone());
+    assert_eq!(
+        pipeline.process_file(&path).await.unwrap(),
+        LoadOutcome::Inserted
+    );
+
+    std::fs::write(&path, r#"{ "version": 2 }"#).unwrap();
+    let summary = pipeline.process_directory(&dir).await.unwrap();
+    assert_eq!((summary.inserted, summary.skipped), (0, 1));
+
+    let summary = ETLPipeline::new(pool.clone())
+        .with_reprocess(true)
+        .process_directory(&dir)
+        .await
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!((summary.inserted, summary.updated), (0, 1));
+
+    let data: serde_json::Value =
+        sqlx::query_scalar("SELECT data FROM json_data WHERE file_name = $1")
+            .bind(&file_name)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(data, json!({ "version": 2 }));
+}
+
+#[tokio::test]
+async fn test_empty_files_are_skipped_not_failed() {
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .expect("Failed to create test database");
+
+    let dir = std::env::temp_dir().join(format!("dds_etl_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let zero_byte = dir.join("zero_byte.json");
+    let whitespace = dir.join("whitespace.json");
+    std::fs::write(&zero_byte, "").unwrap();
+    std::fs::write(&whitespace, " \n\t \n").unwrap();
+
+    let pipeline = ETLPipeline::new(pool.clone());
+    assert_eq!(
+        pipeline.process_file(&zero_byte).await.unwrap(),
+        LoadOutcome::EmptyFile
+    );
+    assert_eq!(
+        pipeline.process_file(&whitespace).await.unwrap(),
+        LoadOutcome::EmptyFile
+    );
+
+    let summary = pipeline.process_directory(&dir).await.unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!((summary.empty, summary.failed), (2, 0));
+}
+
+/// A pipeline whose pool never connects, for checks that fail before any database access
+fn offline_pipeline() -> ETLPipeline {
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .connect_lazy("postgres://localhost/unused")
+        .unwrap();
+    ETLPipeline::new(pool)
+}
+
+#[tokio::test]
+async fn test_file_over_size_limit_is_rejected() {
+    let path = std::env::temp_dir().join(format!("dds_large_{}.json", uuid::Uuid::new_v4()));
+    std::fs::write(&path, format!(r#"{{ "padding": "{}" }}"#, "x".repeat(1024))).unwrap();
+
+    let result = offline_pipeline()
+        .with_max_file_size(512)
+        .process_file(&path)
+        .await;
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(result, Err(ETLPipelineError::FileTooLarge(_))));
+}
+
+#[tokio::test]
+async fn test_deeply_nested_json_is_rejected() {
+    let path = std::env::temp_dir().join(format!("dds_deep_{}.json", uuid::Uuid::new_v4()));
+    // Brackets inside strings do not count toward the depth
+    let content = format!(
+        r#"{{ "s": "[[[[" , "v": {}{}}}"#,
+        "[".repeat(200),
+        "]".repeat(200)
+    );
+    std::fs::write(&path, content).unwrap();
+
+    let result = offline_pipeline().process_file(&path).await;
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(result, Err(ETLPipelineError::NestingTooDeep(_))));
+}
+
+#[tokio::test]
+async fn test_deeply_nested_xml_is_rejected() {
+    let path = std::env::temp_dir().join(format!("dds_deep_{}.xml", uuid::Uuid::new_v4()));
+    std::fs::write(
+        &path,
+        format!("{}<leaf/>{}", "<a>".repeat(200), "</a>".repeat(200)),
+    )
+    .unwrap();
+
+    let result = offline_pipeline().process_xml_file(&path).await;
+    let within_limit = offline_pipeline()
+        .with_max_nesting_depth(201)
+        .process_xml_file(&path)
+        .await;
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(result, Err(ETLPipelineError::NestingTooDeep(_))));
+    assert!(
+        !matches!(within_limit, Err(ETLPipelineError::NestingTooDeep(_))),
+        "{:?}",
+        within_limit
+    );
+}
+
+/// Creates a temporary directory holding `count` JSON files with the given content
+fn temp_dir_with_files(count: usize, content: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("dds_etl_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for i in 0..count {
+        std::fs::write(dir.join(format!("file_{}.json", i)), content).unwrap();
+    }
+    dir
+}
+
+#[tokio::test]
+async fn test_process_directory_stops_at_max_files() {
+    let dir = temp_dir_with_files(5, r#"{ "ok": true }"#);
+
+    let summary = offline_pipeline()
+        .with_dry_run(true)
+        .with_max_files(Some(2))
+        .process_directory(&dir)
+        .await
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(summary.processed(), 2);
+    assert_eq!(summary.stopped, Some(EarlyStop::MaxFiles));
+}
+
+#[tokio::test]
+async fn test_process_directory_reaching_max_files_on_the_last_file_is_not_a_stop() {
+    let dir = temp_dir_with_files(2, r#"{ "ok": true }"#);
+    // Neither unsupported nor ignored files count as remaining work
+    std::fs::write(dir.join("notes.txt"), "not loaded").unwrap();
+    std::fs::write(dir.join("partial.tmp.json"), "{").unwrap();
+
+    let summary = offline_pipeline()
+        .with_dry_run(true)
+        .with_ignore_patterns(vec!["*.tmp.json".to_string()])
+        .unwrap()
+        .with_max_files(Some(2))
+        .process_directory(&dir)
+        .await
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(summary.processed(), 2);
+    assert_eq!(summary.stopped, None);
+}
+
+#[tokio::test]
+async fn test_process_directory_aborts_after_consecutive_failures() {
+    let dir = temp_dir_with_files(5, "{ not json");
+
+    let summary = offline_pipeline()
+        .with_dry_run(true)
+        .with_abort_after_consecutive_failures(Some(3))
+        .process_directory(&dir)
+        .await
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(summary.failed, 3);
+    assert_eq!(summary.processed(), 3);
+    assert_eq!(summary.stopped, Some(EarlyStop::ConsecutiveFailures));
+}
+
+#[tokio::test]
+async fn test_process_directory_without_limits_processes_everything() {
+    let dir = temp_dir_with_files(3, "{ not json");
+
+    let summary = offline_pipeline()
+        .with_dry_run(true)
+        .process_directory(&dir)
+        .await
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(summary.failed, 3);
+    assert_eq!(summary.stopped, None);
+}
+
+#[tokio::test]
+async fn test_process_directory_stream_yields_each_file_result() {
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .expect("Failed to create test database");
+
+    let prefix = uuid::Uuid::new_v4();
+    let dir = std::env::temp_dir().join(format!("dds_etl_{}", prefix));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = |name: &str| format!("{}_{}", prefix, name);
+    std::fs::write(dir.join(file("valid.json")), r#"{ "ok": true }"#).unwrap();
+    std::fs::write(dir.join(file("broken.json")), "{ not json").unwrap();
+    std::fs::write(dir.join(file("empty.json")), "").unwrap();
+    std::fs::write(dir.join(file("feed.xml")), "<feed><item>1</item></feed>").unwrap();
+    std::fs::write(dir.join(file("notes.txt")), "ignored").unwrap();
+
+    // Dry run leaves json_data untouched
+    let pipeline = ETLPipeline::new(pool).with_dry_run(true);
+    let mut results: Vec<_> = pipeline
+        .process_directory_stream(&dir)
+        .map(|result| (result.file_name, result.outcome))
+        .collect()
+        .await;
+    std::fs::remove_dir_all(&dir).unwrap();
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let names: Vec<_> = results.iter().map(|(name, _)| name.clone()).collect();
+    assert_eq!(
+        names,
+        [
+            file("broken.json"),
+            file("empty.json"),
+            file("feed.xml"),
+            file("valid.json")
+        ]
+    );
+    assert!(matches!(
+        results[0].1,
+        Err(ETLPipelineError::JsonParseError(_))
+    ));
+    assert!(matches!(results[1].1, Ok(LoadOutcome::EmptyFile)));
+    assert!(matches!(results[2].1, Ok(LoadOutcome::Inserted)));
+    assert!(matches!(results[3].1, Ok(LoadOutcome::Inserted)));
+}
+
+#[tokio::test]
+async fn test_process_directory_stream_reports_unreadable_directory() {
+    let dir = std::env::temp_dir().join(format!("dds_etl_missing_{}", uuid::Uuid::new_v4()));
+
+    let pipeline = offline_pipeline();
+    let results: Vec<_> = pipeline.process_directory_stream(&dir).collect().await;
+
+    assert_eq!(results.len(), 1);
+    assert!(matches!(
+        results[0].outcome,
+        Err(ETLPipelineError::DirectoryError(_))
+    ));
+    assert!(matches!(
+        pipeline.process_directory(&dir).await,
+        Err(ETLPipelineError::DirectoryError(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_with_target_table_loads_into_that_table() {
+    // The table only exists inside the test transaction
+    let db = DbConnection::new_for_test().await.unwrap();
+    sqlx::query("CREATE TABLE json_data_archive (LIKE json_data INCLUDING ALL)")
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+    let dir = std::env::temp_dir().join(format!("dds_etl_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file_name = format!("archive_{}.json", uuid::Uuid::new_v4());
+    let path = dir.join(&file_name);
+    std::fs::write(&path, r#"{ "archived": true }"#).unwrap();
+
+    let pipeline = ETLPipeline::new(db.pool.clone())
+        .with_target_table("json_data_archive")
+        .unwrap();
+    let first = pipeline.process_file(&path).await.unwrap();
+    let second = pipeline.process_file(&path).await.unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(
+        (first, second),
+        (LoadOutcome::Inserted, LoadOutcome::Skipped)
+    );
+
+    let archived: serde_json::Value =
+        sqlx::query_scalar("SELECT data FROM json_data_archive WHERE file_name = $1")
+            .bind(&file_name)
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+    assert_eq!(archived, json!({ "archived": true }));
+    let in_default: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM json_data WHERE file_name = $1)")
+            .bind(&file_name)
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+    assert!(!in_default);
+}
+
+#[tokio::test]
+async fn test_with_target_table_rejects_invalid_identifiers() {
+    for table in [
+        "",
+        "json_data; DROP TABLE users",
+        "json-data",
+        "1json_data",
+        "public.json_data",
+        "\"json_data\"",
+        &"t".repeat(64),
+    ] {
+        let result = offline_pipeline().with_target_table(table);
+        assert!(
+            matches!(result, Err(ETLPipelineError::InvalidTableName(_))),
+            "{:?} was accepted",
+            table
+        );
+    }
+
+    for table in ["json_data", "_staging", "Feed2024"] {
+        assert!(offline_pipeline().with_target_table(table).is_ok());
+    }
+}
+
+#[tokio::test]
+async fn test_process_directory_skips_ignored_files() {
+    let dir = std::env::temp_dir().join(format!("dds_etl_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let good_file = format!("orders_{}.json", uuid::Uuid::new_v4());
+    std::fs::write(dir.join(&good_file), r#"{ "ok": true }"#).unwrap();
+    // Partial and sidecar files that would fail to load if they were read
+    std::fs::write(dir.join("orders.tmp.json"), "{ \"unterminated\": ").unwrap();
+    std::fs::write(dir.join("_manifest.json"), "not json").unwrap();
+
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .expect("Failed to create test database");
+    let summary = ETLPipeline::new(pool)
+        .with_dry_run(true)
+        .with_ignore_patterns(vec!["*.tmp.json".to_string(), "_manifest.json".to_string()])
+        .unwrap()
+        .process_directory(&dir)
+        .await
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(summary.inserted, 1);
+    assert_eq!(summary.ignored, 2);
+    assert_eq!(summary.failed, 0);
+    assert_eq!(summary.processed(), 1);
+}
+
+#[tokio::test]
+async fn test_with_ignore_patterns_rejects_invalid_globs() {
+    let result = offline_pipeline().with_ignore_patterns(vec!["[unclosed".to_string()]);
+    assert!(matches!(
+        result,
+        Err(ETLPipelineError::InvalidIgnorePattern(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_directory_run_is_skipped_while_another_holds_its_lock() {
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(3)
+        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .expect("Failed to create test database");
+
+    let dir = std::env::temp_dir().join(format!("dds_etl_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file_name = format!("locked_{}.json", uuid::Uuid::new_v4());
+    std::fs::write(dir.join(&file_name), r#"{ "locked": true }"#).unwrap();
+
+    // The key doesn't depend on how the directory is spelled
+    assert_eq!(directory_lock_key(&dir), directory_lock_key(&dir.join(".")));
+
+    let db = DbConnection { pool: pool.clone() };
+    let pipeline = ETLPipeline::new(pool.clone());
+    let skipped = db
+        .with_advisory_lock(directory_lock_key(&dir), || {
+            pipeline.process_directory(&dir)
+        })
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let summary = pipeline.process_directory(&dir).await.unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    sqlx::query("DELETE FROM json_data WHERE file_name = $1")
+        .bind(&file_name)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(skipped.stopped, Some(EarlyStop::Locked));
+    assert_eq!(skipped.processed(), 0);
+    assert_eq!(summary.inserted, 1);
+}
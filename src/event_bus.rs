@@ -0,0 +1,432 @@
+//! Delivery of `ETLEvent`s from publishers to GraphQL subscribers.
+//!
+//! By default events travel over an in-process broadcast channel. With
+//! `EVENT_BUS=postgres` they are published with `NOTIFY` and read back with `LISTEN`, so
+//! subscribers connected to any replica see events published on every replica.
+//!
+//! Either way each event is stored in the `etl_events` table before it is delivered, so
+//! subscribers can catch up on what they missed with `replay_events`.
+//!
+//! Publishers don't send on the channels directly but through an `EventSink`. With
+//! `EVENT_SINK=nats` every event is also published to NATS for other services.
+
+use sqlx::postgres::{PgListener, PgPool};
+use std::env;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{error, info, warn};
+
+use crate::graphql::ETLEvent;
+
+/// Postgres channel that events are published on
+pub const EVENT_CHANNEL: &str = "etl_events";
+
+/// Default capacity of the broadcast channels
+pub const DEFAULT_EVENT_CAPACITY: usize = 100;
+
+/// Default NATS subject prefix that events are published under
+pub const DEFAULT_NATS_SUBJECT: &str = "etl_events";
+
+/// Largest `NOTIFY` payload Postgres accepts, in bytes
+const MAX_NOTIFY_PAYLOAD_BYTES: usize = 7999;
+
+/// Destination that mutations, the task runner, the reaper and the ETL pipeline publish
+/// events to.
+///
+/// `publish` must not block: sinks that talk to the network queue the event and send it
+/// in the background.
+pub trait EventSink: Debug + Send + Sync {
+    /// Publishes an event, dropping it if nobody can receive it.
+    fn publish(&self, event: &ETLEvent);
+}
+
+impl EventSink for broadcast::Sender<ETLEvent> {
+    fn publish(&self, event: &ETLEvent) {
+        // Having no subscribers isn't an error
+        let _ = self.send(event.clone());
+    }
+}
+
+/// Sink that publishes events to NATS as well as to the local event bus.
+///
+/// Each event is sent as JSON on `<subject>.<event_type>`. NATS messages are sent by a
+/// background task in publish order; if the connection falls behind by more than the
+/// queue capacity, further events are logged and not sent to NATS. Events reach NATS
+/// before they are stored, so their `event_id` is null.
+#[derive(Clone, Debug)]
+pub struct NatsSink {
+    local: broadcast::Sender<ETLEvent>,
+    queue: mpsc::Sender<ETLEvent>,
+}
+
+impl NatsSink {
+    /// Connects to the NATS server at `url` and starts the background publisher.
+    ///
+    /// # Arguments
+    /// * `url` - The NATS server URL, e.g. `nats://localhost:4222`
+    /// * `subject` - The subject prefix events are published under
+    /// * `local` - The local bus every event is also sent to
+    /// * `capacity` - How many events may wait to be sent to NATS
+    ///
+    /// # Returns
+    /// * `Result<NatsSink, async_nats::ConnectError>` - The sink, or an error if the
+    ///   connection fails
+    pub async fn connect(
+        url: &str,
+        subject: impl Into<String>,
+        local: broadcast::Sender<ETLEvent>,
+        capacity: usize,
+    ) -> Result<Self, async_nats::ConnectError> {
+        let client = async_nats::connect(url).await?;
+        let (queue, receiver) = mpsc::channel(capacity);
+        tokio::spawn(publish_to_nats(client, subject.into(), receiver));
+        Ok(Self { local, queue })
+    }
+}
+
+impl EventSink for NatsSink {
+    fn publish(&self, event: &ETLEvent) {
+        self.local.publish(event);
+        if let Err(e) = self.queue.try_send(event.clone()) {
+            warn!(
+                "Dropping {} event for {} instead of sending it to NATS: {}",
+                event.event_type, event.entity_id.0, e
+            );
+        }
+    }
+}
+
+/// Sends queued events to NATS until every `NatsSink` using the queue is dropped.
+async fn publish_to_nats(
+    client: async_nats::Client,
+    subject: String,
+    mut receiver: mpsc::Receiver<ETLEvent>,
+) {
+    while let Some(event) = receiver.recv().await {
+        let payload = match serde_json::to_vec(&event) {
+            Ok(payload) => payload,
+            Err(e) => {
+                error!("Failed to serialize {} event: {}", event.event_type, e);
+                continue;
+            }
+        };
+        let event_subject = format!("{}.{}", subject, event.event_type);
+        if let Err(e) = client.publish(event_subject, payload.into()).await {
+            error!(
+                "Failed to publish {} event to NATS: {}",
+                event.event_type, e
+            );
+        }
+    }
+    if let Err(e) = client.flush().await {
+        error!("Failed to flush NATS events: {}", e);
+    }
+}
+
+/// The channels events are published to and subscribers listen on.
+///
+/// For the in-process bus both are the same channel. For the Postgres bus events sent to
+/// `publisher` reach `subscriber` only after a round trip through `NOTIFY`/`LISTEN`.
+#[derive(Clone, Debug)]
+pub struct EventChannels {
+    /// The local channel that published events enter the bus through
+    pub publisher: broadcast::Sender<ETLEvent>,
+    /// Where GraphQL subscriptions receive events
+    pub subscriber: broadcast::Sender<ETLEvent>,
+    /// Where mutations, the task runner, the reaper and the ETL pipeline publish events;
+    /// `publisher` itself unless another sink is configured
+    pub sink: Arc<dyn EventSink>,
+}
+
+impl From<broadcast::Sender<ETLEvent>> for EventChannels {
+    fn from(sender: broadcast::Sender<ETLEvent>) -> Self {
+        Self {
+            publisher: sender.clone(),
+            subscriber: sender.clone(),
+            sink: Arc::new(sender),
+        }
+    }
+}
+
+impl EventChannels {
+    /// Creates an in-process bus that only reaches subscribers of this process.
+    ///
+    /// Events are not persisted; use `persisted` for a bus that supports replay.
+    pub fn local(capacity: usize) -> Self {
+        broadcast::channel(capacity).0.into()
+    }
+
+    /// Creates an in-process bus that stores every event in `etl_events` before
+    /// delivering it, with its `event_id` set.
+    ///
+    /// Events that fail to be stored are logged and delivered without an `event_id`.
+    ///
+    /// # Arguments
+    /// * `pool` - A PostgreSQL connection pool
+    /// * `capacity` - Capacity of the broadcast channels
+    pub fn persisted(pool: &PgPool, capacity: usize) -> Self {
+        let (publisher, _) = broadcast::channel(capacity);
+        let (subscriber, _) = broadcast::channel(capacity);
+
+        tokio::spawn(store_events(
+            pool.clone(),
+            publisher.subscribe(),
+            subscriber.clone(),
+        ));
+
+        Self {
+            sink: Arc::new(publisher.clone()),
+            publisher,
+            subscriber,
+        }
+    }
+
+    /// Creates a bus backed by Postgres `LISTEN/NOTIFY` on `EVENT_CHANNEL`.
+    ///
+    /// Spawns a relay that stores every event sent to `publisher` in `etl_events` and
+    /// `NOTIFY`s it, and a listener that forwards notifications from any replica into
+    /// `subscriber`. Events whose JSON is too large for a notification are sent without
+    /// their `data`.
+    ///
+    /// # Arguments
+    /// * `pool` - A PostgreSQL connection pool
+    /// * `capacity` - Capacity of the broadcast channels
+    ///
+    /// # Returns
+    /// * `Result<EventChannels, sqlx::Error>` - The channels, or an error if `LISTEN` fails
+    pub async fn postgres(pool: &PgPool, capacity: usize) -> Result<Self, sqlx::Error> {
+        let (publisher, _) = broadcast::channel(capacity);
+        let (subscriber, _) = broadcast::channel(capacity);
+
+        let mut listener = PgListener::connect_with(pool).await?;
+        listener.listen(EVENT_CHANNEL).await?;
+
+        tokio::spawn(relay_events(pool.clone(), publisher.subscribe()));
+        tokio::spawn(forward_notifications(listener, subscriber.clone()));
+
+        Ok(Self {
+            sink: Arc::new(publisher.clone()),
+            publisher,
+            subscriber,
+        })
+    }
+
+    /// Creates the bus selected by `EVENT_BUS` (`local`, the default, or `postgres`).
+    ///
+    /// Both persist events to `etl_events`. The channels hold `EVENT_CHANNEL_CAPACITY`
+    /// (default 100) events; subscribers further behind than that skip the oldest.
+    pub async fn from_env(pool: &PgPool) -> Result<Self, sqlx::Error> {
+        let capacity = capacity_from_env();
+
+        match env::var("EVENT_BUS").as_deref() {
+            Ok("postgres") => {
+                info!("Using Postgres LISTEN/NOTIFY event bus");
+                Self::postgres(pool, capacity).await
+            }
+            Ok(other) if other != "local" => {
+                warn!(
+                    "Unknown EVENT_BUS {:?}; using the in-process event bus",
+                    other
+                );
+                Ok(Self::persisted(pool, capacity))
+            }
+            _ => Ok(Self::persisted(pool, capacity)),
+        }
+    }
+
+    /// Replaces `sink` with the one selected by `EVENT_SINK`.
+    ///
+    /// `broadcast` (the default) publishes to the local bus only. `nats` connects to
+    /// `NATS_URL` (default `nats://localhost:4222`) and publishes under `NATS_SUBJECT`
+    /// (default `etl_events`) as well as to the local bus.
+    ///
+    /// # Errors
+    /// Returns an error if `EVENT_SINK=nats` and the NATS server can't be reached.
+    pub async fn with_sink_from_env(mut self) -> Result<Self, async_nats::ConnectError> {
+        match env::var("EVENT_SINK").as_deref() {
+            Ok("nats") => {
+                let url =
+                    env::var("NATS_URL").unwrap_or_else(|_| "nats://localhost:4222".to_string());
+                let subject =
+                    env::var("NATS_SUBJECT").unwrap_or_else(|_| DEFAULT_NATS_SUBJECT.to_string());
+                info!("Publishing events to NATS at {} under {}", url, subject);
+                let sink =
+                    NatsSink::connect(&url, subject, self.publisher.clone(), capacity_from_env())
+                        .await?;
+                self.sink = Arc::new(sink);
+            }
+            Ok(other) if other != "broadcast" => {
+                warn!(
+                    "Unknown EVENT_SINK {:?}; publishing to the local event bus only",
+                    other
+                );
+            }
+            _ => {}
+        }
+        Ok(self)
+    }
+}
+
+/// Reads the event channel capacity from `EVENT_CHANNEL_CAPACITY` (default 100).
+fn capacity_from_env() -> usize {
+    env::var("EVENT_CHANNEL_CAPACITY")
+        .ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .filter(|&capacity| capacity > 0)
+        .unwrap_or(DEFAULT_EVENT_CAPACITY)
+}
+
+/// Stores an event in `etl_events` and sets its `event_id`.
+async fn persist_event(pool: &PgPool, event: &mut ETLEvent) {
+    let result = sqlx::query_scalar(
+        r#"
+        INSERT INTO etl_events
+            (event_type, entity_id, status, previous_status, data, business_group_id)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id
+        "#,
+    )
+    .bind(&event.event_type)
+    .bind(event.entity_id)
+    .bind(event.status)
+    .bind(event.previous_status)
+    .bind(event.data.as_deref())
+    .bind(event.business_group_id)
+    .fetch_one(pool)
+    .await;
+
+    match result {
+        Ok(id) => event.event_id = Some(id),
+        Err(e) => error!("Failed to store {} event: {}", event.event_type, e),
+    }
+}
+
+/// Loads a tenant's persisted events for replay, oldest first.
+///
+/// # Arguments
+/// * `pool` - A PostgreSQL connection pool
+/// * `tenant` - Only events of this tenant are returned (global events when `None`)
+/// * `since_event_id` - Only events with a greater `event_id` are returned, if set
+/// * `limit` - How many of the most recent matching events to return
+///
+/// # Returns
+/// * `Result<Vec<ETLEvent>, sqlx::Error>` - The events, or an error if they cannot be read
+pub async fn replay_events(
+    pool: &PgPool,
+    tenant: Option<i64>,
+    since_event_id: Option<i64>,
+    limit: i64,
+) -> Result<Vec<ETLEvent>, sqlx::Error> {
+    sqlx::query_as::<_, ETLEvent>(
+        r#"
+        SELECT * FROM (
+            SELECT id AS event_id, event_type, entity_id, status, previous_status, data,
+                   business_group_id
+            FROM etl_events
+            WHERE ($1::bigint IS NULL OR id > $1)
+              AND business_group_id IS NOT DISTINCT FROM $3
+            ORDER BY id DESC
+            LIMIT $2
+        ) recent
+        ORDER BY event_id
+        "#,
+    )
+    .bind(since_event_id)
+    .bind(limit)
+    .bind(tenant)
+    .fetch_all(pool)
+    .await
+}
+
+/// Stores every event sent on the publisher channel, then delivers it to subscribers.
+async fn store_events(
+    pool: PgPool,
+    mut receiver: broadcast::Receiver<ETLEvent>,
+    subscriber: broadcast::Sender<ETLEvent>,
+) {
+    loop {
+        let mut event = match receiver.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!("Event store lagged; {} events were not delivered", skipped);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        persist_event(&pool, &mut event).await;
+        let _ = subscriber.send(event);
+    }
+}
+
+/// Serializes an event as a `NOTIFY` payload, dropping `data` if it would not fit.
+fn notify_payload(event: &ETLEvent) -> Result<String, serde_json::Error> {
+    let payload = serde_json::to_string(event)?;
+    if payload.len() <= MAX_NOTIFY_PAYLOAD_BYTES {
+        return Ok(payload);
+    }
+
+    warn!(
+        "{} event for {} is too large to NOTIFY ({} bytes); sending it without data",
+        event.event_type,
+        event.entity_id.0,
+        payload.len()
+    );
+    serde_json::to_string(&ETLEvent {
+        data: None,
+        ..event.clone()
+    })
+}
+
+/// Stores every event sent on the local channel and publishes it with `NOTIFY`.
+async fn relay_events(pool: PgPool, mut receiver: broadcast::Receiver<ETLEvent>) {
+    loop {
+        let mut event = match receiver.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!("Event relay lagged; {} events were not published", skipped);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        persist_event(&pool, &mut event).await;
+        let payload = match notify_payload(&event) {
+            Ok(payload) => payload,
+            Err(e) => {
+                error!("Failed to serialize {} event: {}", event.event_type, e);
+                continue;
+            }
+        };
+        if let Err(e) = sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(EVENT_CHANNEL)
+            .bind(payload)
+            .execute(&pool)
+            .await
+        {
+            error!("Failed to publish {} event: {}", event.event_type, e);
+        }
+    }
+}
+
+/// Forwards notifications on `EVENT_CHANNEL` to local subscribers.
+///
+/// `PgListener` reconnects and re-issues `LISTEN` on its own after a dropped connection;
+/// notifications sent while it was disconnected are lost.
+async fn forward_notifications(mut listener: PgListener, subscriber: broadcast::Sender<ETLEvent>) {
+    loop {
+        match listener.recv().await {
+            Ok(notification) => match serde_json::from_str::<ETLEvent>(notification.payload()) {
+                Ok(event) => {
+                    let _ = subscriber.send(event);
+                }
+                Err(e) => warn!("Ignoring malformed {} notification: {}", EVENT_CHANNEL, e),
+            },
+            Err(e) => {
+                error!("Event listener error: {}", e);
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::event_bus::{replay_events, EventChannels};
+use crate::graphql::ETLEvent;
+use crate::models::etl::{Status, UuidScalar};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+async fn test_pool() -> PgPool {
+    PgPoolOptions::new()
+        .max_connections(4)
+        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .expect("Failed to create test database")
+}
+
+fn event(entity_id: Uuid, data: Option<String>) -> ETLEvent {
+    ETLEvent {
+        event_id: None,
+        event_type: "JobStatusUpdated".to_string(),
+        entity_id: UuidScalar(entity_id),
+        status: Some(Status::Completed),
+        previous_status: Some(Status::Running),
+        data: data.map(Into::into),
+        business_group_id: None,
+    }
+}
+
+/// Waits for the event about `entity_id`, skipping events published by other tests.
+async fn recv_for(receiver: &mut broadcast::Receiver<ETLEvent>, entity_id: Uuid) -> ETLEvent {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let event = receiver.recv().await.expect("event channel closed");
+            if event.entity_id.0 == entity_id {
+                return event;
+            }
+        }
+    })
+    .await
+    .expect("timed out waiting for event")
+}
+
+#[tokio::test]
+async fn test_local_bus_shares_one_channel() {
+    let events = EventChannels::local(8);
+    let mut receiver = events.subscriber.subscribe();
+    let entity_id = Uuid::new_v4();
+
+    events.publisher.send(event(entity_id, None)).unwrap();
+
+    assert_eq!(
+        recv_for(&mut receiver, entity_id).await.entity_id.0,
+        entity_id
+    );
+}
+
+#[tokio::test]
+async fn test_postgres_bus_delivers_events_across_replicas() {
+    let pool = test_pool().await;
+    let replica_a = EventChannels::postgres(&pool, 16).await.unwrap();
+    let replica_b = EventChannels::postgres(&pool, 16).await.unwrap();
+    let mut receiver_a = replica_a.subscriber.subscribe();
+    let mut receiver_b = replica_b.subscriber.subscribe();
+
+    let entity_id = Uuid::new_v4();
+    replica_a
+        .publisher
+        .send(event(entity_id, Some("{\"id\":1}".to_string())))
+        .unwrap();
+
+    for receiver in [&mut receiver_a, &mut receiver_b] {
+        let received = recv_for(receiver, entity_id).await;
+        assert_eq!(received.event_type, "JobStatusUpdated");
+        assert_eq!(received.status, Some(Status::Completed));
+        assert_eq!(received.previous_status, Some(Status::Running));
+        assert_eq!(received.data.as_deref(), Some("{\"id\":1}"));
+    }
+}
+
+#[tokio::test]
+async fn test_postgres_bus_drops_data_too_large_to_notify() {
+    let pool = test_pool().await;
+    let events = EventChannels::postgres(&pool, 16).await.unwrap();
+    let mut receiver = events.subscriber.subscribe();
+
+    let entity_id = Uuid::new_v4();
+    events
+        .publisher
+        .send(event(entity_id, Some("x".repeat(10_000))))
+        .unwrap();
+
+    let received = recv_for(&mut receiver, entity_id).await;
+    assert_eq!(received.data, None);
+    assert_eq!(received.status, Some(Status::Completed));
+}
+
+#[tokio::test]
+async fn test_persisted_bus_stores_events_for_replay() {
+    let pool = test_pool().await;
+    let events = EventChannels::persisted(&pool, 16);
+    let mut receiver = events.subscriber.subscribe();
+
+    let entity_id = Uuid::new_v4();
+    events.publisher.send(event(entity_id, None)).unwrap();
+
+    let received = recv_for(&mut receiver, entity_id).await;
+    let event_id = received.event_id.expect("delivered events carry their ID");
+
+    let replayed = replay_events(&pool, None, Some(event_id - 1), 1)
+        .await
+        .unwrap();
+    assert_eq!(replayed.len(), 1);
+    assert_eq!(replayed[0].event_id, Some(event_id));
+    assert_eq!(replayed[0].entity_id.0, entity_id);
+    assert_eq!(replayed[0].status, Some(Status::Completed));
+}
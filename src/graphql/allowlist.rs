@@ -0,0 +1,126 @@
+//! Safe-listing of the operations a public endpoint will run.
+
+use async_graphql::{Data, ErrorExtensions, Executor, Pos, Request, Response};
+use futures::stream::{self, BoxStream, StreamExt};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Errors loading the `GRAPHQL_ALLOWLIST` file
+#[derive(Error, Debug)]
+pub enum AllowlistError {
+    /// The file could not be read
+    #[error("Failed to read operation allowlist {0}: {1}")]
+    Read(PathBuf, #[source] std::io::Error),
+
+    /// The file is not a JSON array of hash strings
+    #[error("Invalid operation allowlist {0}: {1}")]
+    Parse(PathBuf, #[source] serde_json::Error),
+}
+
+/// Hex SHA-256 of an operation's document with runs of whitespace collapsed to one space,
+/// so reformatting a query doesn't change its hash
+pub fn operation_hash(query: &str) -> String {
+    let normalized = query.split_whitespace().collect::<Vec<_>>().join(" ");
+    format!("{:x}", Sha256::digest(normalized.as_bytes()))
+}
+
+/// The operations `/graphql` may run, identified by `operation_hash`
+#[derive(Clone, Debug, Default)]
+pub struct OperationAllowlist {
+    hashes: Arc<HashSet<String>>,
+}
+
+impl OperationAllowlist {
+    /// Allows exactly the given operation hashes (compared case-insensitively)
+    pub fn new(hashes: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            hashes: Arc::new(
+                hashes
+                    .into_iter()
+                    .map(|hash| hash.trim().to_ascii_lowercase())
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Reads a JSON array of operation hashes from `path`
+    pub fn from_file(path: &Path) -> Result<Self, AllowlistError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| AllowlistError::Read(path.to_path_buf(), e))?;
+        let hashes: Vec<String> = serde_json::from_str(&contents)
+            .map_err(|e| AllowlistError::Parse(path.to_path_buf(), e))?;
+        Ok(Self::new(hashes))
+    }
+
+    /// Loads the file named by `GRAPHQL_ALLOWLIST`, or returns None when it is unset
+    pub fn from_env() -> Result<Option<Self>, AllowlistError> {
+        match std::env::var("GRAPHQL_ALLOWLIST") {
+            Ok(path) if !path.is_empty() => Self::from_file(Path::new(&path)).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Whether `query` is one of the allowed operations
+    pub fn allows(&self, query: &str) -> bool {
+        self.hashes.contains(&operation_hash(query))
+    }
+
+    /// Number of allowed operations
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Whether no operation is allowed
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+}
+
+/// The response to an operation outside the allowlist, logging its hash
+pub(crate) fn not_allowed_response(query: &str) -> Response {
+    tracing::warn!(
+        "Rejected GraphQL operation {} outside the allowlist",
+        operation_hash(query)
+    );
+    let error = async_graphql::Error::new("operation not allowed")
+        .extend_with(|_, e| e.set("code", "OPERATION_NOT_ALLOWED"));
+    Response::from_errors(vec![error.into_server_error(Pos::default())])
+}
+
+/// An executor that answers operations outside `allowlist` with `operation not allowed`
+/// instead of running them, for transports such as `/graphql/ws` that bypass
+/// `graphql_handler`
+#[derive(Clone)]
+pub struct AllowlistedExecutor<E> {
+    inner: E,
+    allowlist: OperationAllowlist,
+}
+
+impl<E> AllowlistedExecutor<E> {
+    pub fn new(inner: E, allowlist: OperationAllowlist) -> Self {
+        Self { inner, allowlist }
+    }
+}
+
+impl<E: Executor> Executor for AllowlistedExecutor<E> {
+    async fn execute(&self, request: Request) -> Response {
+        if !self.allowlist.allows(&request.query) {
+            return not_allowed_response(&request.query);
+        }
+        self.inner.execute(request).await
+    }
+
+    fn execute_stream(
+        &self,
+        request: Request,
+        session_data: Option<Arc<Data>>,
+    ) -> BoxStream<'static, Response> {
+        if !self.allowlist.allows(&request.query) {
+            return stream::once(async move { not_allowed_response(&request.query) }).boxed();
+        }
+        self.inner.execute_stream(request, session_data)
+    }
+}
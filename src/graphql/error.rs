@@ -0,0 +1,78 @@
+//! Typed errors returned by resolvers.
+
+use async_graphql::ErrorExtensions;
+
+/// An error returned to GraphQL clients.
+///
+/// Converting it into an `async_graphql::Error` sets the `code` extension, so clients can
+/// branch on the kind of failure instead of parsing messages:
+///
+/// | Variant        | `code`             |
+/// |----------------|--------------------|
+/// | `NotFound`     | `NOT_FOUND`        |
+/// | `Unauthorized` | `UNAUTHENTICATED`  |
+/// | `Forbidden`    | `FORBIDDEN`        |
+/// | `Validation`   | `BAD_USER_INPUT`   |
+/// | `Conflict`     | `CONFLICT`         |
+/// | `Internal`     | `INTERNAL_ERROR`   |
+///
+/// Each variant carries the message shown to the client. Further extensions can be added
+/// after conversion with `ErrorExtensions::extend_with`.
+///
+/// # Example
+/// ```
+/// use dds::graphql::GqlError;
+///
+/// let err: async_graphql::Error = GqlError::Validation("limit must be positive".into()).into();
+/// assert_eq!(err.message, "limit must be positive");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GqlError {
+    /// The requested entity doesn't exist
+    NotFound(String),
+    /// The request isn't authenticated
+    Unauthorized(String),
+    /// The authenticated user may not perform the operation
+    Forbidden(String),
+    /// An argument is invalid
+    Validation(String),
+    /// The operation conflicts with the current state of the data
+    Conflict(String),
+    /// Something failed on the server
+    Internal(String),
+}
+
+impl GqlError {
+    /// The `code` extension set for this error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            GqlError::NotFound(_) => "NOT_FOUND",
+            GqlError::Unauthorized(_) => "UNAUTHENTICATED",
+            GqlError::Forbidden(_) => "FORBIDDEN",
+            GqlError::Validation(_) => "BAD_USER_INPUT",
+            GqlError::Conflict(_) => "CONFLICT",
+            GqlError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+
+    /// The message shown to the client.
+    pub fn message(&self) -> &str {
+        match self {
+            GqlError::NotFound(message)
+            | GqlError::Unauthorized(message)
+            | GqlError::Forbidden(message)
+            | GqlError::Validation(message)
+            | GqlError::Conflict(message)
+            | GqlError::Internal(message) => message,
+        }
+    }
+}
+
+// `GqlError` deliberately doesn't implement `Display`: async-graphql converts any
+// `Display` type into an error without a `code`, which would bypass this impl.
+impl From<GqlError> for async_graphql::Error {
+    fn from(err: GqlError) -> Self {
+        let code = err.code();
+        async_graphql::Error::new(err.message()).extend_with(|_, e| e.set("code", code))
+    }
+}
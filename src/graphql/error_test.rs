@@ -0,0 +1,112 @@
+use crate::graphql::{map_db_error, GqlError};
+use async_graphql::Value;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn setup_test_pool() -> PgPool {
+    PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .expect("Failed to create test database")
+}
+
+fn error_code(err: &async_graphql::Error) -> Option<Value> {
+    err.extensions
+        .as_ref()
+        .and_then(|extensions| extensions.get("code"))
+        .cloned()
+}
+
+#[tokio::test]
+async fn test_unique_violation_maps_to_constraint_violation() {
+    let pool = setup_test_pool().await;
+    // Both inserts share a transaction that is rolled back when dropped
+    let mut tx = pool.begin().await.expect("Failed to start transaction");
+    let username = format!("testuser_{}", Uuid::new_v4());
+
+    let insert = "INSERT INTO public.users (id, username, email, created_at, updated_at) VALUES ($1, $2, $3, NOW(), NOW())";
+    sqlx::query(insert)
+        .bind(Uuid::new_v4())
+        .bind(&username)
+        .bind(format!("test_{}@example.com", Uuid::new_v4()))
+        .execute(&mut *tx)
+        .await
+        .expect("Failed to insert user");
+
+    let err = sqlx::query(insert)
+        .bind(Uuid::new_v4())
+        .bind(&username)
+        .bind(format!("test_{}@example.com", Uuid::new_v4()))
+        .execute(&mut *tx)
+        .await
+        .expect_err("duplicate username should violate the unique constraint");
+
+    let gql_err = map_db_error(err);
+    assert_eq!(gql_err.message, "constraint violation");
+    assert_eq!(
+        error_code(&gql_err),
+        Some(Value::from("CONSTRAINT_VIOLATION"))
+    );
+    assert!(!gql_err.message.contains("users"));
+}
+
+#[tokio::test]
+async fn test_closed_pool_maps_to_db_unavailable() {
+    let pool = setup_test_pool().await;
+    pool.close().await;
+
+    let err = sqlx::query("SELECT 1")
+        .execute(&pool)
+        .await
+        .expect_err("closed pool should refuse queries");
+
+    let gql_err = map_db_error(err);
+    assert_eq!(gql_err.message, "database unavailable");
+    assert_eq!(error_code(&gql_err), Some(Value::from("DB_UNAVAILABLE")));
+}
+
+#[test]
+fn test_row_not_found_maps_to_not_found() {
+    let gql_err = map_db_error(sqlx::Error::RowNotFound);
+    assert_eq!(error_code(&gql_err), Some(Value::from("NOT_FOUND")));
+}
+
+#[test]
+fn test_gql_errors_carry_their_code() {
+    let cases = [
+        (GqlError::NotFound("missing".into()), "NOT_FOUND"),
+        (GqlError::Unauthorized("missing".into()), "UNAUTHENTICATED"),
+        (GqlError::Forbidden("missing".into()), "FORBIDDEN"),
+        (GqlError::Validation("missing".into()), "BAD_USER_INPUT"),
+        (GqlError::Conflict("missing".into()), "CONFLICT"),
+        (GqlError::Internal("missing".into()), "INTERNAL_ERROR"),
+    ];
+    for (err, code) in cases {
+        let gql_err = async_graphql::Error::from(err);
+        assert_eq!(gql_err.message, "missing");
+        assert_eq!(error_code(&gql_err), Some(Value::from(code)));
+    }
+}
+
+#[test]
+fn test_missing_etl_directory_is_not_found() {
+    let root = std::env::temp_dir();
+
+    let err =
+        super::resolve_etl_directory(&root, &format!("missing_{}", Uuid::new_v4())).unwrap_err();
+
+    assert_eq!(err.message, "Directory not found");
+    assert_eq!(error_code(&err), Some(Value::from("NOT_FOUND")));
+}
+
+#[tokio::test]
+async fn test_invalid_argument_is_a_validation_error() {
+    let pool = setup_test_pool().await;
+
+    let err = super::purge_json_data(&pool, 0).await.unwrap_err();
+
+    assert_eq!(err.message, "olderThanDays must be at least 1");
+    assert_eq!(error_code(&err), Some(Value::from("BAD_USER_INPUT")));
+}
@@ -0,0 +1,122 @@
+//! CSV exports served next to the GraphQL endpoint.
+
+use axum::body::Body;
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use futures::{Stream, TryStreamExt};
+use serde::Deserialize;
+use sqlx::{PgPool, Postgres};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use crate::auth::{is_admin, AuthProvider};
+use crate::db::DbConnection;
+use crate::models::user::User;
+
+/// Bytes of CSV collected before they are written to the response body
+pub const CSV_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Header row of `/export/users.csv`
+const USER_CSV_HEADER: &str = "id,username,email,created_at\r\n";
+
+/// What the export routes need: where to read rows from and how to authenticate callers
+#[derive(Clone)]
+pub struct ExportState {
+    pub pool: PgPool,
+    pub auth_provider: Arc<dyn AuthProvider>,
+}
+
+/// Query parameters of `/export/users.csv`
+#[derive(Debug, Default, Deserialize)]
+pub(super) struct UserExportParams {
+    /// Export at most this many users
+    limit: Option<i64>,
+}
+
+/// Quotes a CSV field when it contains a comma, quote or line break, doubling any quotes.
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Formats a user as one CSV record, including the trailing line break
+fn user_csv_row(user: &User) -> String {
+    format!(
+        "{},{},{},{}\r\n",
+        user.id.0,
+        csv_field(&user.username),
+        csv_field(&user.email),
+        user.created_at.0.to_rfc3339()
+    )
+}
+
+/// Yields the header row and the users' CSV records in chunks of about
+/// `CSV_CHUNK_BYTES`, as they arrive from the database cursor
+fn user_csv_rows(
+    db: DbConnection<Postgres>,
+    limit: Option<i64>,
+) -> impl Stream<Item = Result<String, sqlx::Error>> + Send + 'static {
+    async_stream::try_stream! {
+        let users = db.stream_users(limit);
+        futures::pin_mut!(users);
+
+        let mut chunk = String::from(USER_CSV_HEADER);
+        while let Some(user) = users.try_next().await? {
+            chunk.push_str(&user_csv_row(&user));
+            if chunk.len() >= CSV_CHUNK_BYTES {
+                yield std::mem::take(&mut chunk);
+            }
+        }
+        if !chunk.is_empty() {
+            yield chunk;
+        }
+    }
+}
+
+/// Streams every user, or the first `?limit=` users, as CSV
+/// (`id,username,email,created_at`) to an admin.
+///
+/// The caller authenticates with an `Authorization: Bearer` token whose subject must be
+/// listed in `ADMIN_USER_IDS`; otherwise the answer is `401 Unauthorized` or
+/// `403 Forbidden`. Rows flow from a database cursor (see `DbConnection::stream_users`)
+/// into the response body as it is written, so memory use doesn't grow with the table.
+/// A database error mid-export aborts the response.
+pub(super) async fn export_users_csv(
+    State(state): State<ExportState>,
+    Query(params): Query<UserExportParams>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(token) = super::bearer_token(&headers) else {
+        return (StatusCode::UNAUTHORIZED, "Authentication required").into_response();
+    };
+    let claims = match state.auth_provider.validate_token(token).await {
+        Ok(claims) => claims,
+        Err(e) => {
+            tracing::debug!("Rejected user export token: {}", e.message);
+            return (StatusCode::UNAUTHORIZED, "Invalid token").into_response();
+        }
+    };
+    if !is_admin(&claims.sub) {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    tracing::info!("User {} exported the user list", claims.sub);
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"users.csv\"",
+            ),
+        ],
+        Body::from_stream(user_csv_rows(
+            DbConnection { pool: state.pool },
+            params.limit,
+        )),
+    )
+        .into_response()
+}
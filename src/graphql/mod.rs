@@ -1,136 +1,838 @@
-use async_graphql::{Context, Object, Schema, SimpleObject, Subscription};
-use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use async_graphql::dataloader::{DataLoader, Loader};
+use async_graphql::extensions::{
+    Extension as SchemaExtension, ExtensionContext, ExtensionFactory, NextExecute,
+};
+use async_graphql::http::ALL_WEBSOCKET_PROTOCOLS;
+use async_graphql::{
+    BatchRequest, BatchResponse, ComplexObject, Context, Data, ErrorExtensions, Guard, Object, Pos,
+    Response, Schema, SimpleObject, Subscription, Union,
+};
+use async_graphql_axum::{GraphQLBatchRequest, GraphQLProtocol, GraphQLResponse, GraphQLWebSocket};
 use axum::{
-    extract::Extension,
+    extract::{ws::WebSocketUpgrade, Extension, FromRequest, Request},
+    http::{header, HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::IntoResponse,
     routing::{get, post},
-    Router,
+    Json, Router,
 };
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use moka::future::Cache;
+use opentelemetry::propagation::Extractor;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::{PgConnection, PgPool, Postgres, QueryBuilder, Row};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::broadcast;
+use tower_http::compression::CompressionLayer;
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::timeout::TimeoutLayer;
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 use uuid::Uuid;
 
-use crate::auth::{Auth0Okta, AuthProvider, AuthResponse};
-use crate::models::etl::{Job, PipelineRun, Status, Task, UuidScalar};
+use crate::auth::hash_password;
+use crate::auth::{
+    get_current_tenant_id, get_current_user_id, is_admin, AdminGuard, Auth0Okta, AuthProvider,
+    AuthResponse, AuthenticatedGuard, BearerToken, CachedAuthProvider, CurrentTenant, CurrentUser,
+    LocalAuthProvider, TokenClaims,
+};
+use crate::db::{classify_error, DbConnection, DbErrorKind};
+use crate::etl::ETLPipeline;
+use crate::event_bus::{replay_events, EventChannels, EventSink};
+use crate::logging::otel_enabled;
+use crate::models::audit::AuditLogEntry;
+use crate::models::etl::{
+    CreateJob, CreateTaskInput, DateTimeScalar, Job, JobError, NonEmptyString, PipelineRun, Status,
+    Task, UpdateJob, UpdateTask, UuidScalar,
+};
 use crate::models::user::User;
+use crate::notifier::validate_callback_url;
+use crate::task_handlers::{HandlerRegistry, SchemaViolation};
+use crate::task_runner;
+
+mod allowlist;
+mod error;
+mod export;
+mod operation_log;
+mod pagination;
+use allowlist::{not_allowed_response, AllowlistedExecutor};
+pub use allowlist::{operation_hash, AllowlistError, OperationAllowlist};
+pub use error::GqlError;
+pub use export::{ExportState, CSV_CHUNK_BYTES};
+use operation_log::OperationLogExtension;
+use pagination::Counted;
+pub use pagination::{
+    clamp_limit, page_complexity, PaginationConfig, DEFAULT_PAGE_LIMIT, DEFAULT_PAGE_MAX_LIMIT,
+};
+
+/// Default time-to-live for cached `etl_metrics` results, in seconds
+pub const DEFAULT_METRICS_CACHE_TTL_SECS: u64 = 10;
+
+/// Newest-first ordering of `jobs`, matching the `idx_jobs_created_id` index
+const JOBS_ORDER: &str = "created_at DESC, id DESC";
+
+/// A job's pipeline runs, newest first, matching the `idx_pipeline_runs_job_created_id` index
+const PIPELINE_RUNS_BY_JOB_SQL: &str =
+    "SELECT * FROM pipeline_runs WHERE job_id = $1 ORDER BY created_at DESC, id DESC";
+
+/// Maximum number of persisted events replayed when subscribing to `etlEvents`
+const MAX_EVENT_REPLAY: i64 = 1000;
+
+/// Default idle time after which `etlEvents` sends a `Ping` event, in seconds
+pub const DEFAULT_SUBSCRIPTION_KEEPALIVE_SECS: u64 = 30;
+
+/// Maximum number of results returned by `search`, below the global page limit
+const MAX_SEARCH_LIMIT: i32 = 50;
+
+/// Text of a job or task that `search` matches against, matching the `idx_*_search` indexes
+const SEARCH_TEXT: &str = "name || COALESCE(' ' || description, '')";
+
+/// Default directory that `triggerEtlDirectory` may read from
+pub const DEFAULT_ETL_ALLOWED_ROOT: &str = "data";
+
+/// Default maximum size of a `/graphql` request body, in bytes
+pub const DEFAULT_GRAPHQL_MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Default maximum number of operations in one batched `/graphql` request
+pub const DEFAULT_GRAPHQL_MAX_BATCH_SIZE: usize = 10;
+
+/// Default maximum query complexity; more costly operations are rejected before running
+pub const DEFAULT_GRAPHQL_MAX_COMPLEXITY: usize = 1000;
+
+/// Complexity of `etl_metrics`, which runs two aggregate scans on a cache miss
+const ETL_METRICS_COMPLEXITY: usize = 50;
+
+/// Assumed number of items in lists that aren't paginated, for query complexity
+const UNBOUNDED_LIST_COMPLEXITY: usize = 10;
+
+/// Default database ping latency above which `health` reports `degraded`, in milliseconds
+pub const DEFAULT_HEALTH_DEGRADED_LATENCY_MS: u64 = 500;
+
+/// Default wall-clock deadline of an HTTP request, in seconds
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+
+/// Maximum number of operations accepted in one batched request
+#[derive(Clone, Copy)]
+struct MaxBatchSize(usize);
+
+/// Request data marking a request that should be parsed and validated but not executed
+struct ValidateOnly;
+
+/// Schema extension that stops `ValidateOnly` requests after validation, so no resolver
+/// (and therefore no database query) runs
+struct ValidateOnlyExtension;
+
+impl ExtensionFactory for ValidateOnlyExtension {
+    fn create(&self) -> Arc<dyn SchemaExtension> {
+        Arc::new(ValidateOnlyExtension)
+    }
+}
 
-/// GraphQL context that holds the database pool and event sender
+#[async_trait::async_trait]
+impl SchemaExtension for ValidateOnlyExtension {
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        if ctx.data_opt::<ValidateOnly>().is_some() {
+            return Response::new(async_graphql::Value::Null);
+        }
+        next.run(ctx, operation_name).await
+    }
+}
+
+/// GraphQL context that holds the database pool and event sink
 pub struct GraphQLContext {
     pub pool: PgPool,
-    pub event_sender: broadcast::Sender<ETLEvent>,
+    /// Where mutations publish events (see `crate::event_bus::EventSink`)
+    pub event_sender: Arc<dyn EventSink>,
+    /// Channel that subscriptions listen on (see `crate::event_bus`)
+    pub subscription_sender: broadcast::Sender<ETLEvent>,
     pub auth_provider: Arc<dyn AuthProvider>,
     pub current_user_id: Option<UuidScalar>,
+    /// Tenant (business group) whose jobs, tasks and pipeline runs are visible; `None`
+    /// limits reads to globally-scoped rows
+    pub tenant_id: Option<i64>,
+    /// Cached `etl_metrics` results keyed by tenant and the `since` argument (microseconds
+    /// since epoch)
+    pub metrics_cache: Cache<(Option<i64>, Option<i64>), ETLMetrics>,
+    /// When the schema was created, reported by `serverInfo`
+    pub started_at: DateTime<Utc>,
+    /// Batches `Job.progress` lookups into one query per request tick
+    pub task_progress_loader: DataLoader<TaskProgressLoader>,
+    /// Batches `createdBy`/`updatedBy` user lookups into one query per request tick
+    pub user_loader: DataLoader<UserLoader>,
+    /// Batches `Task.job` lookups into one query per request tick
+    pub job_loader: DataLoader<JobLoader>,
+    /// Task handlers, whose schemas task `input_data`/`output_data` are validated against
+    pub task_handlers: HandlerRegistry,
+    /// Default and maximum page sizes for resolvers taking a `limit`
+    pub pagination: PaginationConfig,
+    /// Idle time after which `etlEvents` sends a `Ping`; zero disables pings
+    pub subscription_keepalive: Duration,
 }
 
 /// Events that can be emitted during ETL operations
-#[derive(Clone, Debug, SimpleObject)]
+///
+/// Events are cloned for every subscriber of the broadcast channel, so `data` is
+/// serialized once by the publisher and shared rather than copied.
+#[derive(Clone, Debug, Serialize, Deserialize, SimpleObject)]
 pub struct ETLEvent {
+    /// ID of the event in the `etl_events` table, usable as `sinceEventId` when
+    /// resubscribing (null for events that were not persisted)
+    #[serde(default)]
+    pub event_id: Option<i64>,
     /// The type of event
     pub event_type: String,
     /// The ID of the entity involved
     pub entity_id: UuidScalar,
     /// The status of the entity (if applicable)
     pub status: Option<Status>,
-    /// The entity data (if applicable)
-    pub data: Option<String>,
+    /// The status of the entity before this event (for status updates)
+    pub previous_status: Option<Status>,
+    /// The entity data as JSON (if applicable)
+    pub data: Option<Arc<str>>,
+    /// Tenant of the job the event is about (`None` for global events); subscribers only
+    /// receive events of their own tenant
+    #[serde(default)]
+    #[graphql(skip)]
+    pub business_group_id: Option<i64>,
+}
+
+impl<'r> sqlx::FromRow<'r, PgRow> for ETLEvent {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            event_id: row.try_get("event_id")?,
+            event_type: row.try_get("event_type")?,
+            entity_id: row.try_get("entity_id")?,
+            status: row.try_get("status")?,
+            previous_status: row.try_get("previous_status")?,
+            data: row.try_get::<Option<String>, _>("data")?.map(Arc::from),
+            business_group_id: row.try_get("business_group_id")?,
+        })
+    }
+}
+
+/// A job status transition, emitted by `jobStatusChanged`
+#[derive(Clone, Debug, SimpleObject)]
+pub struct JobStatusEvent {
+    /// The job whose status changed
+    pub job_id: UuidScalar,
+    /// The status before the transition
+    pub old_status: Status,
+    /// The status after the transition
+    pub new_status: Status,
+}
+
+/// Converts a database error into a GraphQL error with a `code` extension.
+///
+/// The returned message is safe to show to clients: raw SQL, database messages and
+/// connection details are only written to the logs. The `code` extension is one of
+/// `NOT_FOUND`, `CONSTRAINT_VIOLATION`, `QUERY_TIMEOUT`, `DB_UNAVAILABLE` or
+/// `INTERNAL_ERROR`, following `classify_error`.
+pub(crate) fn map_db_error(err: sqlx::Error) -> async_graphql::Error {
+    let (code, message) = match (&err, classify_error(&err)) {
+        (sqlx::Error::RowNotFound, _) => ("NOT_FOUND", "record not found"),
+        (_, DbErrorKind::Timeout) => ("QUERY_TIMEOUT", "query timed out"),
+        (_, DbErrorKind::Conflict | DbErrorKind::ForeignKey | DbErrorKind::NotNull) => {
+            ("CONSTRAINT_VIOLATION", "constraint violation")
+        }
+        (_, DbErrorKind::Connection) => ("DB_UNAVAILABLE", "database unavailable"),
+        (_, DbErrorKind::Other) => ("INTERNAL_ERROR", "internal database error"),
+    };
+
+    tracing::error!("Database error ({}): {}", code, err);
+
+    let constraint = match &err {
+        sqlx::Error::Database(db_err) => db_err.constraint().map(str::to_string),
+        _ => None,
+    };
+
+    async_graphql::Error::new(message).extend_with(|_, e| {
+        e.set("code", code);
+        if let Some(constraint) = constraint {
+            e.set("constraint", constraint);
+        }
+    })
+}
+
+/// Appends a condition limiting `jobs` rows to `tenant`'s (or, for `None`, the global ones)
+fn push_tenant_filter(query: &mut QueryBuilder<'_, Postgres>, tenant: Option<i64>) {
+    match tenant {
+        Some(tenant) => query.push("business_group_id = ").push_bind(tenant),
+        None => query.push("business_group_id IS NULL"),
+    };
+}
+
+/// Appends a condition limiting rows with a `job_id` to jobs visible to `tenant`
+fn push_job_tenant_filter(query: &mut QueryBuilder<'_, Postgres>, tenant: Option<i64>) {
+    query.push("job_id IN (SELECT id FROM jobs WHERE ");
+    push_tenant_filter(query, tenant);
+    query.push(")");
+}
+
+/// Fetches a job if it exists and is visible to `tenant`
+async fn fetch_tenant_job(
+    pool: &PgPool,
+    id: Uuid,
+    tenant: Option<i64>,
+) -> Result<Option<Job>, sqlx::Error> {
+    let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM jobs WHERE id = ");
+    query.push_bind(id).push(" AND ");
+    push_tenant_filter(&mut query, tenant);
+    query.build_query_as::<Job>().fetch_optional(pool).await
+}
+
+/// Locks a job for the rest of the transaction if it exists and is visible to `tenant`
+async fn lock_tenant_job(
+    conn: &mut PgConnection,
+    id: Uuid,
+    tenant: Option<i64>,
+) -> Result<Option<Job>, sqlx::Error> {
+    let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM jobs WHERE id = ");
+    query.push_bind(id).push(" AND ");
+    push_tenant_filter(&mut query, tenant);
+    query.push(" FOR UPDATE");
+    query.build_query_as::<Job>().fetch_optional(conn).await
+}
+
+/// Locks a task for the rest of the transaction if it exists and its job is visible to
+/// `tenant`
+async fn lock_tenant_task(
+    conn: &mut PgConnection,
+    id: Uuid,
+    tenant: Option<i64>,
+) -> Result<Option<Task>, sqlx::Error> {
+    let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM tasks WHERE id = ");
+    query.push_bind(id).push(" AND ");
+    push_job_tenant_filter(&mut query, tenant);
+    query.push(" FOR UPDATE");
+    query.build_query_as::<Task>().fetch_optional(conn).await
 }
 
 /// Root query type for GraphQL
+///
+/// Job, task and pipeline-run reads only return rows of the request's tenant (see
+/// `get_current_tenant_id`).
 pub struct Query;
 
 #[Object]
 impl Query {
+    /// Check whether this server can reach the database, and how quickly
+    ///
+    /// `status` is `"ok"`, `"degraded"` when the database ping takes longer than
+    /// `HEALTH_DEGRADED_LATENCY_MS` (default 500), or `"down"` when the ping fails.
+    async fn health(&self, ctx: &Context<'_>) -> async_graphql::Result<HealthStatus> {
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let latency = crate::db::ping_latency(&gql_ctx.pool).await;
+        let degraded_after = std::env::var("HEALTH_DEGRADED_LATENCY_MS")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_HEALTH_DEGRADED_LATENCY_MS);
+
+        let status = match latency {
+            None => "down",
+            Some(latency) if latency > Duration::from_millis(degraded_after) => "degraded",
+            Some(_) => "ok",
+        };
+        Ok(HealthStatus {
+            status: status.to_string(),
+            pool_healthy: latency.is_some(),
+            db_latency_ms: latency.map(|latency| latency.as_millis() as i32),
+            uptime_secs: (Utc::now() - gql_ctx.started_at).num_seconds(),
+        })
+    }
+
+    /// Get the server version and start time, for client compatibility checks
+    async fn server_info(&self, ctx: &Context<'_>) -> async_graphql::Result<ServerInfo> {
+        let started_at = ctx.data::<GraphQLContext>()?.started_at;
+        Ok(ServerInfo {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at: DateTimeScalar(started_at),
+            git_sha: option_env!("GIT_SHA").map(str::to_string),
+        })
+    }
+
+    /// Decode the request's bearer token, for troubleshooting authentication
+    ///
+    /// Returns the claims the auth provider validated, or an `UNAUTHENTICATED` error
+    /// explaining why validation failed. Available in debug builds; release builds only
+    /// answer admins (an authenticated admin, or a token whose subject is in
+    /// `ADMIN_USER_IDS`).
+    async fn debug_token(&self, ctx: &Context<'_>) -> async_graphql::Result<TokenDebug> {
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let Some(BearerToken(token)) = ctx.data_opt::<BearerToken>() else {
+            return Err(
+                GqlError::Unauthorized("No Authorization: Bearer token was sent".into()).into(),
+            );
+        };
+
+        let validated = gql_ctx.auth_provider.validate_token(token).await;
+        let allowed = cfg!(debug_assertions)
+            || AdminGuard.check(ctx).await.is_ok()
+            || validated.as_ref().is_ok_and(|claims| is_admin(&claims.sub));
+        if !allowed {
+            return Err(GqlError::Forbidden("Admin access required".into()).into());
+        }
+
+        let claims = validated.map_err(|e| {
+            GqlError::Unauthorized(format!("Token validation failed: {}", e.message))
+        })?;
+        Ok(TokenDebug::from(claims))
+    }
+
     /// Get a job by ID
     async fn job(&self, ctx: &Context<'_>, id: UuidScalar) -> async_graphql::Result<Option<Job>> {
         let pool = ctx.data::<GraphQLContext>()?.pool.clone();
-        let job = sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE id = $1")
-            .bind(id.0)
-            .fetch_optional(&pool)
-            .await?;
+        let tenant = get_current_tenant_id(ctx)?;
+        let job = fetch_tenant_job(&pool, id.0, tenant)
+            .await
+            .map_err(map_db_error)?;
         Ok(job)
     }
 
-    /// Get all jobs
-    async fn jobs(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Job>> {
-        let pool = ctx.data::<GraphQLContext>()?.pool.clone();
-        let jobs = sqlx::query_as::<_, Job>("SELECT * FROM jobs ORDER BY created_at DESC")
+    /// Get a page of jobs, newest first (ties broken by ID), with the total number of jobs
+    ///
+    /// With `updatedSince`, only jobs updated after that instant are returned, oldest
+    /// update first, so sync clients can checkpoint the latest `updatedAt` they have seen.
+    /// `limit` follows the global page limits and `offset` skips that many jobs.
+    #[graphql(complexity = "page_complexity(limit, child_complexity)")]
+    async fn jobs(
+        &self,
+        ctx: &Context<'_>,
+        updated_since: Option<DateTimeScalar>,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> async_graphql::Result<JobPage> {
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let pool = gql_ctx.pool.clone();
+        let tenant = get_current_tenant_id(ctx)?;
+        let limit = clamp_limit(limit, gql_ctx.pagination);
+        let offset = offset.unwrap_or(0).max(0);
+
+        // Counting over the window gives the total of the same snapshot as the page
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT *, COUNT(*) OVER() AS total_count FROM jobs WHERE ",
+        );
+        push_tenant_filter(&mut query, tenant);
+        match &updated_since {
+            Some(since) => query
+                .push(" AND updated_at > ")
+                .push_bind(since.0)
+                .push(" ORDER BY updated_at ASC, id ASC"),
+            None => query.push(" ORDER BY ").push(JOBS_ORDER),
+        };
+        query
+            .push(" LIMIT ")
+            .push_bind(i64::from(limit))
+            .push(" OFFSET ")
+            .push_bind(i64::from(offset));
+
+        let rows = query
+            .build_query_as::<Counted<Job>>()
             .fetch_all(&pool)
-            .await?;
-        Ok(jobs)
+            .await
+            .map_err(map_db_error)?;
+
+        // A page past the end has no rows to carry the count, so count separately
+        let total_count = match rows.first() {
+            Some(row) => row.total_count,
+            None if offset > 0 => {
+                let mut count = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM jobs WHERE ");
+                push_tenant_filter(&mut count, tenant);
+                if let Some(since) = updated_since {
+                    count.push(" AND updated_at > ").push_bind(since.0);
+                }
+                count
+                    .build_query_scalar()
+                    .fetch_one(&pool)
+                    .await
+                    .map_err(map_db_error)?
+            }
+            None => 0,
+        };
+
+        Ok(JobPage {
+            items: rows.into_iter().map(|row| row.item).collect(),
+            total_count: total_count as i32,
+        })
+    }
+
+    /// Count jobs, optionally filtered by status and/or updated after a checkpoint
+    async fn job_count(
+        &self,
+        ctx: &Context<'_>,
+        status: Option<Status>,
+        updated_since: Option<DateTimeScalar>,
+    ) -> async_graphql::Result<i32> {
+        let pool = ctx.data::<GraphQLContext>()?.pool.clone();
+        let tenant = get_current_tenant_id(ctx)?;
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM jobs WHERE ");
+        push_tenant_filter(&mut query, tenant);
+        if let Some(status) = status {
+            query.push(" AND status = ").push_bind(status);
+        }
+        if let Some(since) = updated_since {
+            query.push(" AND updated_at > ").push_bind(since.0);
+        }
+
+        let count: i64 = query
+            .build_query_scalar()
+            .fetch_one(&pool)
+            .await
+            .map_err(map_db_error)?;
+        Ok(count as i32)
     }
 
     /// Get tasks for a job
+    #[graphql(complexity = "UNBOUNDED_LIST_COMPLEXITY * child_complexity")]
     async fn tasks(
         &self,
         ctx: &Context<'_>,
         job_id: UuidScalar,
     ) -> async_graphql::Result<Vec<Task>> {
         let pool = ctx.data::<GraphQLContext>()?.pool.clone();
-        let tasks =
-            sqlx::query_as::<_, Task>("SELECT * FROM tasks WHERE job_id = $1 ORDER BY created_at")
-                .bind(job_id.0)
+        let tenant = get_current_tenant_id(ctx)?;
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM tasks WHERE job_id = ");
+        query.push_bind(job_id.0).push(" AND ");
+        push_job_tenant_filter(&mut query, tenant);
+        query.push(" ORDER BY created_at");
+
+        let tasks = query
+            .build_query_as::<Task>()
+            .fetch_all(&pool)
+            .await
+            .map_err(map_db_error)?;
+        Ok(tasks)
+    }
+
+    /// Count tasks for a job
+    async fn task_count(
+        &self,
+        ctx: &Context<'_>,
+        job_id: UuidScalar,
+    ) -> async_graphql::Result<i32> {
+        let pool = ctx.data::<GraphQLContext>()?.pool.clone();
+        let tenant = get_current_tenant_id(ctx)?;
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM tasks WHERE job_id = ");
+        query.push_bind(job_id.0).push(" AND ");
+        push_job_tenant_filter(&mut query, tenant);
+
+        let count: i64 = query
+            .build_query_scalar()
+            .fetch_one(&pool)
+            .await
+            .map_err(map_db_error)?;
+        Ok(count as i32)
+    }
+
+    /// Full-text search over job and task names and descriptions
+    ///
+    /// `query` uses web search syntax (quoted phrases, `or`, `-term`). Jobs and tasks
+    /// are interleaved by relevance, most relevant first, and each result's `snippet`
+    /// highlights the matched terms with `<b>` tags. `limit` follows the global page
+    /// limits, but at most 50 results are returned.
+    #[graphql(
+        complexity = "page_complexity(limit.map(|limit| limit.min(MAX_SEARCH_LIMIT)), child_complexity)"
+    )]
+    async fn search(
+        &self,
+        ctx: &Context<'_>,
+        query: String,
+        limit: Option<i32>,
+    ) -> async_graphql::Result<Vec<SearchResult>> {
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let pool = gql_ctx.pool.clone();
+        let tenant = get_current_tenant_id(ctx)?;
+        let limit = i64::from(clamp_limit(limit, gql_ctx.pagination).min(MAX_SEARCH_LIMIT));
+
+        let document = format!("to_tsvector('english', {})", SEARCH_TEXT);
+        let hit = |kind: &str| {
+            format!(
+                "SELECT '{kind}' AS kind, id, ts_rank({document}, q.query)::float8 AS rank, \
+                 ts_headline('english', {SEARCH_TEXT}, q.query) AS snippet \
+                 FROM {kind}s, q WHERE {document} @@ q.query AND "
+            )
+        };
+
+        let mut sql =
+            QueryBuilder::<Postgres>::new("WITH q AS (SELECT websearch_to_tsquery('english', ");
+        sql.push_bind(query).push(") AS query) SELECT * FROM (");
+        sql.push(hit("job"));
+        push_tenant_filter(&mut sql, tenant);
+        sql.push(" UNION ALL ").push(hit("task"));
+        push_job_tenant_filter(&mut sql, tenant);
+        sql.push(") hits ORDER BY rank DESC, id LIMIT ")
+            .push_bind(limit);
+
+        let hits: Vec<(String, Uuid, f64, String)> = sql
+            .build_query_as()
+            .fetch_all(&pool)
+            .await
+            .map_err(map_db_error)?;
+
+        let ids_of = |kind: &str| -> Vec<Uuid> {
+            hits.iter()
+                .filter(|(hit_kind, ..)| hit_kind == kind)
+                .map(|(_, id, ..)| *id)
+                .collect()
+        };
+        let mut jobs: HashMap<Uuid, Job> =
+            sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE id = ANY($1)")
+                .bind(ids_of("job"))
+                .fetch_all(&pool)
+                .await
+                .map_err(map_db_error)?
+                .into_iter()
+                .map(|job| (job.id.0, job))
+                .collect();
+        let mut tasks: HashMap<Uuid, Task> =
+            sqlx::query_as::<_, Task>("SELECT * FROM tasks WHERE id = ANY($1)")
+                .bind(ids_of("task"))
                 .fetch_all(&pool)
-                .await?;
+                .await
+                .map_err(map_db_error)?
+                .into_iter()
+                .map(|task| (task.id.0, task))
+                .collect();
+
+        // A row deleted between the two reads is dropped rather than failing the search
+        let results = hits
+            .into_iter()
+            .filter_map(|(kind, id, rank, snippet)| {
+                let item = match kind.as_str() {
+                    "job" => SearchItem::Job(jobs.remove(&id)?),
+                    _ => SearchItem::Task(tasks.remove(&id)?),
+                };
+                Some(SearchResult {
+                    item,
+                    snippet,
+                    rank,
+                })
+            })
+            .collect();
+        Ok(results)
+    }
+
+    /// Get the most recently updated jobs, tasks and pipeline runs, newest first
+    ///
+    /// Items of all three kinds are interleaved by `updatedAt` (ties broken by ID) and read
+    /// in a single query. `limit` follows the global page limits (default 20, max 100).
+    #[graphql(complexity = "page_complexity(limit, child_complexity)")]
+    async fn recent_activity(
+        &self,
+        ctx: &Context<'_>,
+        limit: Option<i32>,
+    ) -> async_graphql::Result<Vec<ActivityItem>> {
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let tenant = get_current_tenant_id(ctx)?;
+        let limit = i64::from(clamp_limit(limit, gql_ctx.pagination));
+
+        // Each branch is limited on its own so none of the tables is read in full
+        let mut query = QueryBuilder::<Postgres>::new("SELECT kind, item FROM (");
+        for (i, (kind, table)) in [("job", "jobs"), ("task", "tasks"), ("run", "pipeline_runs")]
+            .into_iter()
+            .enumerate()
+        {
+            if i > 0 {
+                query.push(" UNION ALL ");
+            }
+            query.push(format!(
+                "(SELECT '{kind}' AS kind, to_jsonb(t) AS item, t.updated_at, t.id \
+                 FROM {table} t WHERE "
+            ));
+            if kind == "job" {
+                push_tenant_filter(&mut query, tenant);
+            } else {
+                push_job_tenant_filter(&mut query, tenant);
+            }
+            query
+                .push(" ORDER BY t.updated_at DESC, t.id DESC LIMIT ")
+                .push_bind(limit)
+                .push(")");
+        }
+        query
+            .push(") activity ORDER BY updated_at DESC, id DESC LIMIT ")
+            .push_bind(limit);
+
+        let rows: Vec<(String, serde_json::Value)> = query
+            .build_query_as()
+            .fetch_all(&gql_ctx.pool)
+            .await
+            .map_err(map_db_error)?;
+
+        let items = rows
+            .into_iter()
+            .map(|(kind, item)| {
+                Ok(match kind.as_str() {
+                    "job" => ActivityItem::Job(serde_json::from_value(item)?),
+                    "task" => ActivityItem::Task(serde_json::from_value(item)?),
+                    _ => ActivityItem::PipelineRun(serde_json::from_value(item)?),
+                })
+            })
+            .collect::<Result<Vec<_>, serde_json::Error>>()
+            .map_err(|e| {
+                tracing::error!("Failed to decode recent activity: {}", e);
+                GqlError::Internal("Failed to read recent activity".into())
+            })?;
+        Ok(items)
+    }
+
+    /// Get tasks across all jobs, optionally filtered by status
+    ///
+    /// Results are ordered by most recently updated. `limit` defaults to `PAGE_DEFAULT_LIMIT`
+    /// (20) and is capped at `PAGE_MAX_LIMIT` (100).
+    #[graphql(complexity = "page_complexity(limit, child_complexity)")]
+    async fn all_tasks(
+        &self,
+        ctx: &Context<'_>,
+        status: Option<Status>,
+        limit: Option<i32>,
+    ) -> async_graphql::Result<Vec<Task>> {
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let pool = gql_ctx.pool.clone();
+        let limit = i64::from(clamp_limit(limit, gql_ctx.pagination));
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM tasks WHERE ");
+        push_job_tenant_filter(&mut query, get_current_tenant_id(ctx)?);
+        if let Some(status) = status {
+            query.push(" AND status = ").push_bind(status);
+        }
+        query
+            .push(" ORDER BY updated_at DESC LIMIT ")
+            .push_bind(limit);
+
+        let tasks = query
+            .build_query_as::<Task>()
+            .fetch_all(&pool)
+            .await
+            .map_err(map_db_error)?;
         Ok(tasks)
     }
 
-    /// Get pipeline runs for a job
+    /// Get pipeline runs for a job, newest first (ties broken by ID)
+    ///
+    /// `since` (inclusive) and `until` (exclusive) limit the runs to those created in a
+    /// time window; a window with `since` after `until` is rejected with `BAD_USER_INPUT`.
+    #[graphql(complexity = "UNBOUNDED_LIST_COMPLEXITY * child_complexity")]
     async fn pipeline_runs(
         &self,
         ctx: &Context<'_>,
         job_id: UuidScalar,
+        since: Option<DateTimeScalar>,
+        until: Option<DateTimeScalar>,
     ) -> async_graphql::Result<Vec<PipelineRun>> {
+        if let (Some(since), Some(until)) = (&since, &until) {
+            if since.0 > until.0 {
+                return Err(GqlError::Validation("since must not be after until".into()).into());
+            }
+        }
         let pool = ctx.data::<GraphQLContext>()?.pool.clone();
-        let runs = sqlx::query_as::<_, PipelineRun>(
-            "SELECT * FROM pipeline_runs WHERE job_id = $1 ORDER BY created_at DESC",
-        )
-        .bind(job_id.0)
-        .fetch_all(&pool)
-        .await?;
+        let tenant = get_current_tenant_id(ctx)?;
+
+        if fetch_tenant_job(&pool, job_id.0, tenant)
+            .await
+            .map_err(map_db_error)?
+            .is_none()
+        {
+            return Ok(Vec::new());
+        }
+        let mut query =
+            QueryBuilder::<Postgres>::new("SELECT * FROM pipeline_runs WHERE job_id = ");
+        query.push_bind(job_id.0);
+        if let Some(since) = since {
+            query.push(" AND created_at >= ").push_bind(since.0);
+        }
+        if let Some(until) = until {
+            query.push(" AND created_at < ").push_bind(until.0);
+        }
+        query.push(" ORDER BY created_at DESC, id DESC");
+
+        let runs = query
+            .build_query_as::<PipelineRun>()
+            .fetch_all(&pool)
+            .await
+            .map_err(map_db_error)?;
         Ok(runs)
     }
 
-    /// Get ETL metrics and statistics
-    async fn etl_metrics(&self, ctx: &Context<'_>) -> async_graphql::Result<ETLMetrics> {
+    /// Get a job together with its tasks and pipeline runs in a single request
+    #[graphql(complexity = "UNBOUNDED_LIST_COMPLEXITY * child_complexity")]
+    async fn job_detail(
+        &self,
+        ctx: &Context<'_>,
+        id: UuidScalar,
+    ) -> async_graphql::Result<Option<JobDetail>> {
         let pool = ctx.data::<GraphQLContext>()?.pool.clone();
+        let tenant = get_current_tenant_id(ctx)?;
 
-        // Get job statistics
-        let job_stats = sqlx::query!(
-            r#"
-            SELECT 
-                COUNT(*) as total_jobs,
-                COUNT(*) FILTER (WHERE status = 'Completed') as completed_jobs,
-                COUNT(*) FILTER (WHERE status = 'Failed') as failed_jobs,
-                COUNT(*) FILTER (WHERE status = 'Running') as running_jobs
-            FROM jobs
-            "#
+        // Tasks and runs of a job the tenant can't see are discarded with the job
+        let (job, tasks, runs) = tokio::try_join!(
+            fetch_tenant_job(&pool, id.0, tenant),
+            sqlx::query_as::<_, Task>("SELECT * FROM tasks WHERE job_id = $1 ORDER BY created_at")
+                .bind(id.0)
+                .fetch_all(&pool),
+            sqlx::query_as::<_, PipelineRun>(PIPELINE_RUNS_BY_JOB_SQL)
+                .bind(id.0)
+                .fetch_all(&pool),
         )
-        .fetch_one(&pool)
-        .await?;
+        .map_err(map_db_error)?;
 
-        // Get task statistics
-        let task_stats = sqlx::query!(
-            r#"
-            SELECT 
-                COUNT(*) as total_tasks,
-                COUNT(*) FILTER (WHERE status = 'Completed') as completed_tasks,
-                COUNT(*) FILTER (WHERE status = 'Failed') as failed_tasks,
-                COUNT(*) FILTER (WHERE status = 'Running') as running_tasks
-            FROM tasks
-            "#
-        )
-        .fetch_one(&pool)
-        .await?;
+        Ok(job.map(|job| JobDetail { job, tasks, runs }))
+    }
 
-        Ok(ETLMetrics {
-            total_jobs: job_stats.total_jobs.unwrap_or(0) as i32,
-            completed_jobs: job_stats.completed_jobs.unwrap_or(0) as i32,
-            failed_jobs: job_stats.failed_jobs.unwrap_or(0) as i32,
-            running_jobs: job_stats.running_jobs.unwrap_or(0) as i32,
-            total_tasks: task_stats.total_tasks.unwrap_or(0) as i32,
-            completed_tasks: task_stats.completed_tasks.unwrap_or(0) as i32,
-            failed_tasks: task_stats.failed_tasks.unwrap_or(0) as i32,
-            running_tasks: task_stats.running_tasks.unwrap_or(0) as i32,
+    /// Get ETL metrics and statistics
+    ///
+    /// Only the request tenant's jobs and tasks are counted. Results are cached per tenant
+    /// and `since` value for `METRICS_CACHE_TTL_SECS` (default 10s);
+    /// pass `bypassCache: true` to force a fresh read.
+    #[graphql(complexity = "ETL_METRICS_COMPLEXITY + child_complexity")]
+    async fn etl_metrics(
+        &self,
+        ctx: &Context<'_>,
+        since: Option<DateTimeScalar>,
+        bypass_cache: Option<bool>,
+    ) -> async_graphql::Result<ETLMetrics> {
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let pool = gql_ctx.pool.clone();
+        let tenant = get_current_tenant_id(ctx)?;
+        let since = since.map(|since| since.0);
+        let key = (tenant, since.map(|since| since.timestamp_micros()));
+
+        if bypass_cache.unwrap_or(false) {
+            let metrics = compute_etl_metrics(&pool, tenant, since).await?;
+            gql_ctx.metrics_cache.insert(key, metrics.clone()).await;
+            return Ok(metrics);
+        }
+
+        // Concurrent misses for the same key share a single computation
+        gql_ctx
+            .metrics_cache
+            .try_get_with(key, compute_etl_metrics(&pool, tenant, since))
+            .await
+            .map_err(|e| (*e).clone())
+    }
+
+    /// Get live connection-pool statistics for this server process
+    async fn db_pool_stats(&self, ctx: &Context<'_>) -> async_graphql::Result<DbPoolStats> {
+        let pool = &ctx.data::<GraphQLContext>()?.pool;
+        let size = pool.size();
+        let idle = pool.num_idle() as u32;
+
+        Ok(DbPoolStats {
+            size: size as i32,
+            idle: idle as i32,
+            num_connections: size.saturating_sub(idle) as i32,
+            max_connections: pool.options().get_max_connections() as i32,
         })
     }
 
@@ -140,22 +842,722 @@ impl Query {
         let user = sqlx::query_as::<_, User>("SELECT * FROM public.users WHERE id = $1")
             .bind(id.0)
             .fetch_optional(&pool)
-            .await?;
+            .await
+            .map_err(map_db_error)?;
         Ok(user)
     }
 
     /// Get all users
+    #[graphql(complexity = "UNBOUNDED_LIST_COMPLEXITY * child_complexity")]
     async fn users(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<User>> {
         let pool = ctx.data::<GraphQLContext>()?.pool.clone();
         let users = sqlx::query_as::<_, User>("SELECT * FROM public.users")
             .fetch_all(&pool)
-            .await?;
+            .await
+            .map_err(map_db_error)?;
+        Ok(users)
+    }
+
+    /// Find users whose email starts with `emailPrefix` (ignoring case), ordered by email
+    ///
+    /// `limit` follows the global page limits and `offset` skips that many users.
+    /// Requires an admin user.
+    #[graphql(
+        guard = "AdminGuard",
+        complexity = "page_complexity(limit, child_complexity)"
+    )]
+    async fn search_users(
+        &self,
+        ctx: &Context<'_>,
+        email_prefix: String,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> async_graphql::Result<Vec<User>> {
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let db = DbConnection {
+            pool: gql_ctx.pool.clone(),
+        };
+        let limit = clamp_limit(limit, gql_ctx.pagination);
+        let users = db
+            .search_users(
+                &email_prefix,
+                i64::from(limit),
+                i64::from(offset.unwrap_or(0)),
+            )
+            .await
+            .map_err(map_db_error)?;
         Ok(users)
     }
+
+    /// Count users
+    async fn user_count(&self, ctx: &Context<'_>) -> async_graphql::Result<i32> {
+        let pool = ctx.data::<GraphQLContext>()?.pool.clone();
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM public.users")
+            .fetch_one(&pool)
+            .await
+            .map_err(map_db_error)?;
+        Ok(count as i32)
+    }
+
+    /// List files loaded into `json_data`, most recently (re)loaded first
+    ///
+    /// Pass `fileName` to see only that file. `limit` follows the global page limits.
+    #[graphql(complexity = "page_complexity(limit, child_complexity)")]
+    async fn ingest_history(
+        &self,
+        ctx: &Context<'_>,
+        file_name: Option<String>,
+        limit: Option<i32>,
+    ) -> async_graphql::Result<Vec<IngestRecord>> {
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let limit = clamp_limit(limit, gql_ctx.pagination);
+        let records = sqlx::query_as::<_, IngestRecord>(
+            r#"
+            SELECT file_name, updated_at AS ingested_at,
+                   octet_length(data::text)::bigint AS byte_size
+            FROM json_data
+            WHERE $1::text IS NULL OR file_name = $1
+            ORDER BY updated_at DESC, id DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(file_name)
+        .bind(i64::from(limit))
+        .fetch_all(&gql_ctx.pool)
+        .await
+        .map_err(map_db_error)?;
+        Ok(records)
+    }
+
+    /// Get the audit log entries for an entity, oldest first
+    ///
+    /// Requires an authenticated user. Job, task and pipeline-run entries are only
+    /// returned while the entity exists and belongs to the request's tenant; user entries
+    /// only to that user or an admin.
+    #[graphql(
+        guard = "AuthenticatedGuard",
+        complexity = "UNBOUNDED_LIST_COMPLEXITY * child_complexity"
+    )]
+    async fn audit_log(
+        &self,
+        ctx: &Context<'_>,
+        entity_id: UuidScalar,
+    ) -> async_graphql::Result<Vec<AuditLogEntry>> {
+        let pool = ctx.data::<GraphQLContext>()?.pool.clone();
+        let tenant = get_current_tenant_id(ctx)?;
+        let user_id = get_current_user_id(ctx)?;
+        let admin = user_id.is_some_and(|id| is_admin(&id.0.to_string()));
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM audit_log WHERE entity_id = ");
+        query.push_bind(entity_id.0);
+        query.push(" AND ((entity_type = 'Job' AND entity_id IN (SELECT id FROM jobs WHERE ");
+        push_tenant_filter(&mut query, tenant);
+        query.push(")) OR (entity_type = 'Task' AND entity_id IN (SELECT id FROM tasks WHERE ");
+        push_job_tenant_filter(&mut query, tenant);
+        query.push(
+            ")) OR (entity_type = 'PipelineRun' AND entity_id IN (SELECT id FROM pipeline_runs WHERE ",
+        );
+        push_job_tenant_filter(&mut query, tenant);
+        query.push(")) OR (entity_type = 'User' AND (");
+        query.push_bind(admin).push(" OR entity_id = ");
+        query.push_bind(user_id.map(|id| id.0));
+        query.push("))) ORDER BY occurred_at, id");
+
+        let entries = query
+            .build_query_as::<AuditLogEntry>()
+            .fetch_all(&pool)
+            .await
+            .map_err(map_db_error)?;
+        Ok(entries)
+    }
 }
 
-/// ETL metrics and statistics
+/// Result of the `health` check
+#[derive(SimpleObject)]
+pub struct HealthStatus {
+    /// `ok`, `degraded` (slow database) or `down` (database unreachable)
+    pub status: String,
+    /// Whether a `SELECT 1` through the connection pool succeeded within two seconds
+    pub pool_healthy: bool,
+    /// How long the `SELECT 1` took, in milliseconds (null when it failed)
+    pub db_latency_ms: Option<i32>,
+    /// Seconds since the server's schema was created
+    pub uptime_secs: i64,
+}
+
+/// Server build and uptime information
+#[derive(SimpleObject)]
+pub struct ServerInfo {
+    /// The crate version of the running server
+    pub version: String,
+    /// When the server's schema was created
+    pub started_at: DateTimeScalar,
+    /// The git commit the server was built from, if `GIT_SHA` was set at build time
+    pub git_sha: Option<String>,
+}
+
+/// What the server decoded from a bearer token, returned by `debugToken`
 #[derive(SimpleObject)]
+pub struct TokenDebug {
+    /// Subject (user ID) of the token
+    pub sub: String,
+    /// Expiry, in seconds since the Unix epoch
+    pub exp: i64,
+    /// Issuer, if the token names one
+    pub iss: Option<String>,
+    /// Audience, if the token names one
+    pub aud: Option<String>,
+    /// Email address, if the token carries one
+    pub email: Option<String>,
+}
+
+impl From<TokenClaims> for TokenDebug {
+    fn from(claims: TokenClaims) -> Self {
+        Self {
+            sub: claims.sub,
+            exp: i64::try_from(claims.exp).unwrap_or(i64::MAX),
+            iss: claims.iss,
+            aud: claims.aud,
+            email: claims.email,
+        }
+    }
+}
+
+/// One file loaded into `json_data`, returned by `ingestHistory`
+#[derive(SimpleObject, sqlx::FromRow)]
+pub struct IngestRecord {
+    /// Name of the loaded file
+    pub file_name: String,
+    /// When the file was last loaded (or reloaded with `reprocess`)
+    pub ingested_at: DateTimeScalar,
+    /// Size of the stored JSON document, in bytes
+    pub byte_size: i64,
+}
+
+/// Connection-pool statistics
+///
+/// These are per-process values: each server instance has its own pool, so they do not
+/// reflect connections held by other replicas.
+#[derive(SimpleObject)]
+pub struct DbPoolStats {
+    /// Number of open connections, idle or in use
+    pub size: i32,
+    /// Number of open connections waiting to be checked out
+    pub idle: i32,
+    /// Number of connections currently checked out by queries
+    pub num_connections: i32,
+    /// Maximum number of connections the pool may open
+    pub max_connections: i32,
+}
+
+/// Loads the fraction of each job's tasks that are `Completed`
+pub struct TaskProgressLoader {
+    pool: PgPool,
+}
+
+impl Loader<Uuid> for TaskProgressLoader {
+    type Value = f64;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, f64>, Self::Error> {
+        let rows: Vec<(Uuid, f64)> = sqlx::query_as(
+            r#"
+            SELECT job_id,
+                   COUNT(*) FILTER (WHERE status = 'Completed')::float8 / COUNT(*)::float8
+            FROM tasks
+            WHERE job_id = ANY($1)
+            GROUP BY job_id
+            "#,
+        )
+        .bind(keys)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_db_error)?;
+        Ok(rows.into_iter().collect())
+    }
+}
+
+/// Loads users by ID
+pub struct UserLoader {
+    pool: PgPool,
+}
+
+impl Loader<Uuid> for UserLoader {
+    type Value = User;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, User>, Self::Error> {
+        let users = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = ANY($1)")
+            .bind(keys)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(map_db_error)?;
+        Ok(users.into_iter().map(|user| (user.id.0, user)).collect())
+    }
+}
+
+/// Loads jobs by ID
+///
+/// Keys come from rows the request could already read (a task's `job_id`), so jobs are
+/// not filtered by tenant again.
+pub struct JobLoader {
+    pool: PgPool,
+}
+
+impl Loader<Uuid> for JobLoader {
+    type Value = Job;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Job>, Self::Error> {
+        let jobs = sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE id = ANY($1)")
+            .bind(keys)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(map_db_error)?;
+        Ok(jobs.into_iter().map(|job| (job.id.0, job)).collect())
+    }
+}
+
+/// Resolves an attribution column such as `created_by` to its user, if any
+async fn load_user(
+    ctx: &Context<'_>,
+    id: Option<UuidScalar>,
+) -> async_graphql::Result<Option<User>> {
+    match id {
+        Some(id) => {
+            ctx.data::<GraphQLContext>()?
+                .user_loader
+                .load_one(id.0)
+                .await
+        }
+        None => Ok(None),
+    }
+}
+
+#[ComplexObject]
+impl Job {
+    /// The user who created this job (null for anonymous or system-created jobs)
+    async fn created_by(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<User>> {
+        load_user(ctx, self.created_by).await
+    }
+
+    /// The user who last updated this job through the API (null if anonymous)
+    async fn updated_by(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<User>> {
+        load_user(ctx, self.updated_by).await
+    }
+
+    /// Fraction of this job's tasks that are completed, from 0.0 to 1.0 (0.0 when it has no tasks)
+    async fn progress(&self, ctx: &Context<'_>) -> async_graphql::Result<f64> {
+        let progress = ctx
+            .data::<GraphQLContext>()?
+            .task_progress_loader
+            .load_one(self.id.0)
+            .await?;
+        Ok(progress.unwrap_or(0.0))
+    }
+
+    /// This job's recorded failures, newest first
+    ///
+    /// `limit` defaults to `PAGE_DEFAULT_LIMIT` (20) and is capped at `PAGE_MAX_LIMIT` (100).
+    #[graphql(complexity = "page_complexity(limit, child_complexity)")]
+    async fn errors(
+        &self,
+        ctx: &Context<'_>,
+        limit: Option<i32>,
+    ) -> async_graphql::Result<Vec<JobError>> {
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let pool = &gql_ctx.pool;
+        sqlx::query_as::<_, JobError>(
+            r#"
+            SELECT * FROM job_errors
+            WHERE job_id = $1
+            ORDER BY occurred_at DESC, id DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(self.id)
+        .bind(i64::from(clamp_limit(limit, gql_ctx.pagination)))
+        .fetch_all(pool)
+        .await
+        .map_err(map_db_error)
+    }
+
+    /// Message of this job's most recent recorded failure
+    async fn last_error(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<String>> {
+        let pool = &ctx.data::<GraphQLContext>()?.pool;
+        sqlx::query_scalar(
+            r#"
+            SELECT message FROM job_errors
+            WHERE job_id = $1
+            ORDER BY occurred_at DESC, id DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(self.id)
+        .fetch_optional(pool)
+        .await
+        .map_err(map_db_error)
+    }
+}
+
+#[ComplexObject]
+impl Task {
+    /// The user who created this task (null for anonymous or system-created tasks)
+    async fn created_by(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<User>> {
+        load_user(ctx, self.created_by).await
+    }
+
+    /// The user who last updated this task through the API (null if anonymous)
+    async fn updated_by(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<User>> {
+        load_user(ctx, self.updated_by).await
+    }
+
+    /// The job this task belongs to
+    async fn job(&self, ctx: &Context<'_>) -> async_graphql::Result<Job> {
+        ctx.data::<GraphQLContext>()?
+            .job_loader
+            .load_one(self.job_id.0)
+            .await?
+            .ok_or_else(|| {
+                GqlError::Internal(format!(
+                    "Job {} of task {} not found",
+                    self.job_id.0, self.id.0
+                ))
+                .into()
+            })
+    }
+}
+
+/// File counts from a `triggerEtlDirectory` run
+#[derive(Clone, Debug, Serialize, SimpleObject)]
+pub struct EtlRunSummary {
+    /// Files inserted or updated in `json_data`
+    pub processed: i32,
+    /// Files skipped because they were already loaded or empty
+    pub skipped: i32,
+    /// Files that failed to load (see `json_data_failures`)
+    pub failed: i32,
+}
+
+/// Result of an `updateTasksStatus` bulk update
+#[derive(Clone, Debug, Serialize, SimpleObject)]
+pub struct TasksStatusUpdate {
+    /// Number of tasks moved to the new status
+    pub count: i32,
+    /// Tasks left unchanged because moving them to the new status is not allowed
+    pub skipped: Vec<SkippedTask>,
+}
+
+/// A task `updateTasksStatus` left alone, with the status it kept
+#[derive(Clone, Debug, Serialize, SimpleObject)]
+pub struct SkippedTask {
+    /// The task's ID
+    pub id: UuidScalar,
+    /// The task's current, unchanged status
+    pub status: Status,
+}
+
+/// Resolves a requested ETL directory, rejecting paths outside `allowed_root`.
+///
+/// Relative paths are taken relative to `allowed_root`. Both paths are canonicalized, so
+/// `..` components and symlinks cannot escape the root.
+fn resolve_etl_directory(allowed_root: &Path, requested: &str) -> async_graphql::Result<PathBuf> {
+    let forbidden =
+        || GqlError::Forbidden("Directory is outside the allowed ETL root".into()).into();
+
+    let root = allowed_root.canonicalize().map_err(|e| {
+        tracing::error!("ETL root {:?} is not accessible: {}", allowed_root, e);
+        forbidden()
+    })?;
+    let path = root
+        .join(requested)
+        .canonicalize()
+        .map_err(|_| GqlError::NotFound("Directory not found".into()))?;
+
+    if path.starts_with(&root) && path.is_dir() {
+        Ok(path)
+    } else {
+        Err(forbidden())
+    }
+}
+
+/// A job or task matched by `search`
+#[derive(Union)]
+pub enum SearchItem {
+    Job(Job),
+    Task(Task),
+}
+
+/// A recently changed job, task or pipeline run, returned by `recentActivity`
+#[derive(Union)]
+pub enum ActivityItem {
+    Job(Job),
+    Task(Task),
+    PipelineRun(PipelineRun),
+}
+
+/// One `search` result
+#[derive(SimpleObject)]
+pub struct SearchResult {
+    /// The matched job or task
+    pub item: SearchItem,
+    /// The matched text with search terms wrapped in `<b>` tags
+    pub snippet: String,
+    /// Relevance score; results are ordered by it, highest first
+    pub rank: f64,
+}
+
+/// One page of `jobs`
+#[derive(SimpleObject)]
+pub struct JobPage {
+    /// The jobs on this page
+    pub items: Vec<Job>,
+    /// Number of jobs matching the query across all pages
+    pub total_count: i32,
+}
+
+/// A job with its tasks and pipeline runs
+#[derive(SimpleObject)]
+pub struct JobDetail {
+    /// The job itself
+    pub job: Job,
+    /// Tasks belonging to the job, oldest first
+    pub tasks: Vec<Task>,
+    /// Pipeline runs for the job, newest first
+    pub runs: Vec<PipelineRun>,
+}
+
+/// Runs the ETL pipeline over a directory inside `allowed_root` and publishes the summary.
+async fn run_etl_directory(
+    pool: &PgPool,
+    event_sender: &Arc<dyn EventSink>,
+    allowed_root: &Path,
+    path: &str,
+) -> async_graphql::Result<EtlRunSummary> {
+    let dir = resolve_etl_directory(allowed_root, path)?;
+
+    let summary = ETLPipeline::new(pool.clone())
+        .with_event_sink(event_sender.clone())
+        .process_directory(&dir)
+        .await
+        .map_err(|e| {
+            tracing::error!("ETL run over {:?} failed: {}", dir, e);
+            GqlError::Internal(e.to_string())
+        })?;
+    let summary = EtlRunSummary {
+        processed: (summary.inserted + summary.updated) as i32,
+        skipped: (summary.skipped + summary.empty) as i32,
+        failed: summary.failed as i32,
+    };
+
+    event_sender.publish(&ETLEvent {
+        event_id: None,
+        event_type: "EtlDirectoryProcessed".to_string(),
+        entity_id: UuidScalar(Uuid::new_v4()),
+        status: Some(if summary.failed > 0 {
+            Status::Failed
+        } else {
+            Status::Completed
+        }),
+        previous_status: None,
+        data: Some(
+            serde_json::json!({ "path": dir, "summary": summary })
+                .to_string()
+                .into(),
+        ),
+        business_group_id: None,
+    });
+
+    Ok(summary)
+}
+
+/// Deletes `json_data` rows created more than `older_than_days` days ago, returning how many.
+async fn purge_json_data(pool: &PgPool, older_than_days: i32) -> async_graphql::Result<i32> {
+    if older_than_days < 1 {
+        return Err(GqlError::Validation("olderThanDays must be at least 1".into()).into());
+    }
+
+    let result =
+        sqlx::query("DELETE FROM json_data WHERE created_at < NOW() - make_interval(days => $1)")
+            .bind(older_than_days)
+            .execute(pool)
+            .await
+            .map_err(map_db_error)?;
+    Ok(result.rows_affected() as i32)
+}
+
+/// Rejects a job `callback_url` that is not an http(s) URL with a `BAD_USER_INPUT` error
+fn check_callback_url(callback_url: Option<&str>) -> async_graphql::Result<()> {
+    match callback_url.map(validate_callback_url) {
+        Some(Err(message)) => Err(GqlError::Validation(message).into()),
+        _ => Ok(()),
+    }
+}
+
+/// Rejects task data that doesn't match its kind's schema with a `BAD_USER_INPUT` error
+/// naming the failing path
+fn check_task_data(
+    field: &str,
+    kind: &str,
+    result: Result<(), Vec<SchemaViolation>>,
+) -> async_graphql::Result<()> {
+    let Err(violations) = result else {
+        return Ok(());
+    };
+    let details = violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ");
+    let path = violations
+        .first()
+        .map(|violation| violation.path.clone())
+        .unwrap_or_default();
+    let message = format!(
+        "{} does not match the schema for {} tasks: {}",
+        field, kind, details
+    );
+    Err(async_graphql::Error::from(GqlError::Validation(message))
+        .extend_with(|_, e| e.set("path", path)))
+}
+
+/// Rejects a status change that `Status::can_transition_to` doesn't allow with a
+/// `BAD_USER_INPUT` error
+fn check_status_transition(from: Status, to: Status) -> async_graphql::Result<()> {
+    if from.can_transition_to(to) {
+        return Ok(());
+    }
+    let message = format!("Cannot change status from {:?} to {:?}", from, to);
+    Err(
+        async_graphql::Error::from(GqlError::Validation(message)).extend_with(|_, e| {
+            e.set("from", format!("{:?}", from));
+            e.set("to", format!("{:?}", to));
+        }),
+    )
+}
+
+/// Checks that a task of job `job_id` (`task_id`, unless it is being created) may depend
+/// on `depends_on`: another existing task of the same job. Anything else, including
+/// tasks of other tenants, is rejected with `BAD_USER_INPUT`.
+async fn check_task_dependency(
+    conn: &mut PgConnection,
+    task_id: Option<Uuid>,
+    job_id: Uuid,
+    depends_on: Uuid,
+) -> async_graphql::Result<()> {
+    if task_id == Some(depends_on) {
+        return Err(GqlError::Validation("A task cannot depend on itself".to_string()).into());
+    }
+    let dependency_job: Option<Uuid> = sqlx::query_scalar("SELECT job_id FROM tasks WHERE id = $1")
+        .bind(depends_on)
+        .fetch_optional(conn)
+        .await
+        .map_err(map_db_error)?;
+    if dependency_job != Some(job_id) {
+        return Err(GqlError::Validation(format!(
+            "dependsOn must be a task of job {}, got {}",
+            job_id, depends_on
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Writes an `audit_log` entry for a mutation on the mutation's own transaction
+async fn record_audit<T: serde::Serialize>(
+    conn: &mut PgConnection,
+    actor: Option<UuidScalar>,
+    operation: &str,
+    entity_type: &str,
+    entity_id: UuidScalar,
+    payload: &T,
+) -> async_graphql::Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO audit_log (actor_user_id, operation, entity_type, entity_id, payload)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+    )
+    .bind(actor)
+    .bind(operation)
+    .bind(entity_type)
+    .bind(entity_id)
+    .bind(serde_json::to_value(payload)?)
+    .execute(conn)
+    .await
+    .map_err(map_db_error)?;
+    Ok(())
+}
+
+/// Computes `tenant`'s job and task statistics, optionally limited to entities created since a point in time
+async fn compute_etl_metrics(
+    pool: &PgPool,
+    tenant: Option<i64>,
+    since: Option<DateTime<Utc>>,
+) -> async_graphql::Result<ETLMetrics> {
+    const STATUS_COUNTS: &str = r#"
+        SELECT
+            COUNT(*),
+            COUNT(*) FILTER (WHERE status = 'Completed'),
+            COUNT(*) FILTER (WHERE status = 'Failed'),
+            COUNT(*) FILTER (WHERE status = 'Running'),
+            COUNT(*) FILTER (WHERE status = 'Cancelled')
+        "#;
+
+    // Get job statistics
+    let mut query = QueryBuilder::<Postgres>::new(STATUS_COUNTS);
+    query.push(" FROM jobs WHERE ");
+    push_tenant_filter(&mut query, tenant);
+    if let Some(since) = since {
+        query.push(" AND created_at >= ").push_bind(since);
+    }
+    let (total_jobs, completed_jobs, failed_jobs, running_jobs, cancelled_jobs): (
+        i64,
+        i64,
+        i64,
+        i64,
+        i64,
+    ) = query
+        .build_query_as()
+        .fetch_one(pool)
+        .await
+        .map_err(map_db_error)?;
+
+    // Get task statistics
+    let mut query = QueryBuilder::<Postgres>::new(STATUS_COUNTS);
+    query.push(" FROM tasks WHERE ");
+    push_job_tenant_filter(&mut query, tenant);
+    if let Some(since) = since {
+        query.push(" AND created_at >= ").push_bind(since);
+    }
+    let (total_tasks, completed_tasks, failed_tasks, running_tasks, cancelled_tasks): (
+        i64,
+        i64,
+        i64,
+        i64,
+        i64,
+    ) = query
+        .build_query_as()
+        .fetch_one(pool)
+        .await
+        .map_err(map_db_error)?;
+
+    Ok(ETLMetrics {
+        total_jobs: total_jobs as i32,
+        completed_jobs: completed_jobs as i32,
+        failed_jobs: failed_jobs as i32,
+        running_jobs: running_jobs as i32,
+        cancelled_jobs: cancelled_jobs as i32,
+        total_tasks: total_tasks as i32,
+        completed_tasks: completed_tasks as i32,
+        failed_tasks: failed_tasks as i32,
+        running_tasks: running_tasks as i32,
+        cancelled_tasks: cancelled_tasks as i32,
+    })
+}
+
+/// ETL metrics and statistics
+#[derive(Clone, SimpleObject)]
 pub struct ETLMetrics {
     /// Total number of jobs
     pub total_jobs: i32,
@@ -165,6 +1567,8 @@ pub struct ETLMetrics {
     pub failed_jobs: i32,
     /// Number of running jobs
     pub running_jobs: i32,
+    /// Number of cancelled jobs
+    pub cancelled_jobs: i32,
     /// Total number of tasks
     pub total_tasks: i32,
     /// Number of completed tasks
@@ -173,27 +1577,40 @@ pub struct ETLMetrics {
     pub failed_tasks: i32,
     /// Number of running tasks
     pub running_tasks: i32,
+    /// Number of cancelled tasks
+    pub cancelled_tasks: i32,
 }
 
 /// Root mutation type for GraphQL
+///
+/// Mutations of existing jobs, tasks and pipeline runs only see rows of the request's
+/// tenant, like `Query`; another tenant's IDs are treated as not found.
 pub struct Mutation;
 
 #[Object]
 impl Mutation {
     /// Create a new job
+    ///
+    /// `callbackUrl`, if given, must be an http(s) URL; it receives a POST when the job
+    /// reaches a terminal status. The job belongs to the request's tenant.
     async fn create_job(
         &self,
         ctx: &Context<'_>,
         name: String,
         description: Option<String>,
+        callback_url: Option<String>,
     ) -> async_graphql::Result<Job> {
+        check_callback_url(callback_url.as_deref())?;
         let pool = ctx.data::<GraphQLContext>()?.pool.clone();
         let event_sender = ctx.data::<GraphQLContext>()?.event_sender.clone();
+        let actor = get_current_user_id(ctx)?;
+        let tenant = get_current_tenant_id(ctx)?;
 
+        let mut tx = pool.begin().await.map_err(map_db_error)?;
         let job = sqlx::query_as::<_, Job>(
             r#"
-            INSERT INTO jobs (id, name, description, status, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $5)
+            INSERT INTO jobs (id, name, description, status, created_at, updated_at, created_by, updated_by, callback_url, business_group_id)
+            VALUES ($1, $2, $3, $4, $5, $5, $6, $6, $7, $8)
             RETURNING *
             "#,
         )
@@ -202,21 +1619,108 @@ impl Mutation {
         .bind(description)
         .bind(Status::Pending)
         .bind(chrono::Utc::now())
-        .fetch_one(&pool)
-        .await?;
+        .bind(actor)
+        .bind(callback_url)
+        .bind(tenant)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+        record_audit(&mut tx, actor, "createJob", "Job", job.id, &job).await?;
+        tx.commit().await.map_err(map_db_error)?;
 
         // Emit event
-        let _ = event_sender.send(ETLEvent {
+        event_sender.publish(&ETLEvent {
+            event_id: None,
+            event_type: "JobCreated".to_string(),
+            entity_id: job.id,
+            status: Some(job.status),
+            previous_status: None,
+            data: Some(serde_json::to_string(&job)?.into()),
+            business_group_id: job.business_group_id,
+        });
+
+        Ok(job)
+    }
+
+    /// Create a job and its tasks atomically
+    async fn create_job_with_tasks(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+        description: Option<String>,
+        tasks: Vec<CreateTaskInput>,
+        callback_url: Option<String>,
+    ) -> async_graphql::Result<Job> {
+        check_callback_url(callback_url.as_deref())?;
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let actor = get_current_user_id(ctx)?;
+        let tenant = get_current_tenant_id(ctx)?;
+        for task in &tasks {
+            check_task_data(
+                "inputData",
+                &task.name,
+                gql_ctx
+                    .task_handlers
+                    .validate_input(&task.name, task.input_data.as_ref().map(|input| &input.0)),
+            )?;
+        }
+
+        let mut tx = gql_ctx.pool.begin().await.map_err(map_db_error)?;
+        let (job, tasks) = DbConnection::insert_job_with_tasks(
+            &mut tx,
+            CreateJob {
+                name,
+                description,
+                callback_url,
+            },
+            tasks,
+            actor,
+            tenant,
+        )
+        .await
+        .map_err(map_db_error)?;
+        record_audit(
+            &mut tx,
+            actor,
+            "createJobWithTasks",
+            "Job",
+            job.id,
+            &serde_json::json!({ "job": job, "tasks": tasks }),
+        )
+        .await?;
+        tx.commit().await.map_err(map_db_error)?;
+
+        // Emit events
+        gql_ctx.event_sender.publish(&ETLEvent {
+            event_id: None,
             event_type: "JobCreated".to_string(),
             entity_id: job.id,
             status: Some(job.status),
-            data: Some(serde_json::to_string(&job)?),
+            previous_status: None,
+            data: Some(serde_json::to_string(&job)?.into()),
+            business_group_id: job.business_group_id,
+        });
+        gql_ctx.event_sender.publish(&ETLEvent {
+            event_id: None,
+            event_type: "TasksCreated".to_string(),
+            entity_id: job.id,
+            status: Some(job.status),
+            previous_status: None,
+            data: Some(serde_json::to_string(&tasks)?.into()),
+            business_group_id: job.business_group_id,
         });
 
         Ok(job)
     }
 
     /// Update a job's status
+    ///
+    /// Moves that `Status::can_transition_to` doesn't allow (such as `Completed` back to
+    /// `Pending`) are rejected with `BAD_USER_INPUT`.
+    ///
+    /// Deprecated in favour of `updateJob`, which can change the status along with the
+    /// other fields; this mutation keeps working.
+    #[graphql(deprecation = "use updateJob")]
     async fn update_job_status(
         &self,
         ctx: &Context<'_>,
@@ -225,49 +1729,265 @@ impl Mutation {
     ) -> async_graphql::Result<Option<Job>> {
         let pool = ctx.data::<GraphQLContext>()?.pool.clone();
         let event_sender = ctx.data::<GraphQLContext>()?.event_sender.clone();
+        let actor = get_current_user_id(ctx)?;
+        let tenant = get_current_tenant_id(ctx)?;
+
+        let mut tx = pool.begin().await.map_err(map_db_error)?;
+        let Some(current) = lock_tenant_job(&mut tx, id.0, tenant)
+            .await
+            .map_err(map_db_error)?
+        else {
+            return Ok(None);
+        };
+        check_status_transition(current.status, status)?;
+        let previous_status = Some(current.status);
 
         let job = sqlx::query_as::<_, Job>(
             r#"
             UPDATE jobs
-            SET status = $1, updated_at = $2
+            SET status = $1, updated_by = $2
             WHERE id = $3
             RETURNING *
             "#,
         )
         .bind(status)
-        .bind(chrono::Utc::now())
+        .bind(actor)
         .bind(id.0)
-        .fetch_optional(&pool)
-        .await?;
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+        if let Some(ref job) = job {
+            record_audit(&mut tx, actor, "updateJobStatus", "Job", job.id, job).await?;
+            if job.status == Status::Failed && previous_status != Some(Status::Failed) {
+                DbConnection::record_job_error(&mut tx, job.id, "marked Failed by updateJobStatus")
+                    .await
+                    .map_err(map_db_error)?;
+            }
+        }
+        tx.commit().await.map_err(map_db_error)?;
 
         if let Some(ref job) = job {
             // Emit event
-            let _ = event_sender.send(ETLEvent {
+            event_sender.publish(&ETLEvent {
+                event_id: None,
                 event_type: "JobStatusUpdated".to_string(),
                 entity_id: job.id,
                 status: Some(job.status),
-                data: Some(serde_json::to_string(&job)?),
+                previous_status,
+                data: Some(serde_json::to_string(&job)?.into()),
+                business_group_id: job.business_group_id,
+            });
+        }
+
+        Ok(job)
+    }
+
+    /// Update a job's name, description and/or status
+    ///
+    /// Fields left unset keep their current values. Illegal status transitions are
+    /// rejected as in `updateJobStatus`.
+    async fn update_job(
+        &self,
+        ctx: &Context<'_>,
+        id: UuidScalar,
+        input: UpdateJob,
+    ) -> async_graphql::Result<Option<Job>> {
+        let pool = ctx.data::<GraphQLContext>()?.pool.clone();
+        let event_sender = ctx.data::<GraphQLContext>()?.event_sender.clone();
+        let actor = get_current_user_id(ctx)?;
+        let tenant = get_current_tenant_id(ctx)?;
+
+        let mut tx = pool.begin().await.map_err(map_db_error)?;
+        let Some(current) = lock_tenant_job(&mut tx, id.0, tenant)
+            .await
+            .map_err(map_db_error)?
+        else {
+            return Ok(None);
+        };
+        if let Some(status) = input.status {
+            check_status_transition(current.status, status)?;
+        }
+        let previous_status = Some(current.status);
+
+        let job = sqlx::query_as::<_, Job>(
+            r#"
+            UPDATE jobs
+            SET name = COALESCE($1, name),
+                description = COALESCE($2, description),
+                status = COALESCE($3, status),
+                updated_by = $4
+            WHERE id = $5
+            RETURNING *
+            "#,
+        )
+        .bind(input.name)
+        .bind(input.description)
+        .bind(input.status)
+        .bind(actor)
+        .bind(id.0)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+        if let Some(ref job) = job {
+            record_audit(&mut tx, actor, "updateJob", "Job", job.id, job).await?;
+            if job.status == Status::Failed && previous_status != Some(Status::Failed) {
+                DbConnection::record_job_error(&mut tx, job.id, "marked Failed by updateJob")
+                    .await
+                    .map_err(map_db_error)?;
+            }
+        }
+        tx.commit().await.map_err(map_db_error)?;
+
+        if let Some(ref job) = job {
+            // Emit event
+            event_sender.publish(&ETLEvent {
+                event_id: None,
+                event_type: "JobUpdated".to_string(),
+                entity_id: job.id,
+                status: Some(job.status),
+                previous_status,
+                data: Some(serde_json::to_string(&job)?.into()),
+                business_group_id: job.business_group_id,
             });
         }
 
         Ok(job)
     }
 
+    /// Cancel a job and its unfinished tasks
+    ///
+    /// `Pending` and `Running` jobs move to `Cancelled`, along with their `Pending` and
+    /// `Running` tasks. Cancelling a job that already finished is a no-op that returns
+    /// the job unchanged.
+    async fn cancel_job(
+        &self,
+        ctx: &Context<'_>,
+        id: UuidScalar,
+    ) -> async_graphql::Result<Option<Job>> {
+        let pool = ctx.data::<GraphQLContext>()?.pool.clone();
+        let event_sender = ctx.data::<GraphQLContext>()?.event_sender.clone();
+        let actor = get_current_user_id(ctx)?;
+
+        let tenant = get_current_tenant_id(ctx)?;
+
+        let mut tx = pool.begin().await.map_err(map_db_error)?;
+        let Some(job) = lock_tenant_job(&mut tx, id.0, tenant)
+            .await
+            .map_err(map_db_error)?
+        else {
+            return Ok(None);
+        };
+        if job.status.is_terminal() {
+            return Ok(Some(job));
+        }
+        let previous_status = job.status;
+
+        let job = sqlx::query_as::<_, Job>(
+            r#"
+            UPDATE jobs
+            SET status = $1, completed_at = NOW(), updated_by = $2
+            WHERE id = $3
+            RETURNING *
+            "#,
+        )
+        .bind(Status::Cancelled)
+        .bind(actor)
+        .bind(id.0)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+        let tasks = sqlx::query_as::<_, Task>(
+            r#"
+            UPDATE tasks
+            SET status = $1, completed_at = NOW(), updated_by = $2
+            WHERE job_id = $3 AND status IN ($4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(Status::Cancelled)
+        .bind(actor)
+        .bind(id.0)
+        .bind(Status::Pending)
+        .bind(Status::Running)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+        record_audit(
+            &mut tx,
+            actor,
+            "cancelJob",
+            "Job",
+            job.id,
+            &serde_json::json!({ "job": job, "tasks": tasks }),
+        )
+        .await?;
+        tx.commit().await.map_err(map_db_error)?;
+
+        // Emit events
+        event_sender.publish(&ETLEvent {
+            event_id: None,
+            event_type: "JobCancelled".to_string(),
+            entity_id: job.id,
+            status: Some(job.status),
+            previous_status: Some(previous_status),
+            data: Some(serde_json::to_string(&job)?.into()),
+            business_group_id: job.business_group_id,
+        });
+        for task in &tasks {
+            event_sender.publish(&ETLEvent {
+                event_id: None,
+                event_type: "TaskCancelled".to_string(),
+                entity_id: task.id,
+                status: Some(task.status),
+                previous_status: None,
+                data: Some(serde_json::to_string(task)?.into()),
+                business_group_id: job.business_group_id,
+            });
+        }
+
+        Ok(Some(job))
+    }
+
     /// Create a new task
+    ///
+    /// `inputData` must match the input schema of the task's kind (its `name`), if the
+    /// kind has one, and `dependsOn` must be another task of the same job.
     async fn create_task(
         &self,
         ctx: &Context<'_>,
         job_id: UuidScalar,
         name: String,
         input_data: Option<serde_json::Value>,
+        depends_on: Option<UuidScalar>,
     ) -> async_graphql::Result<Task> {
+        check_task_data(
+            "inputData",
+            &name,
+            ctx.data::<GraphQLContext>()?
+                .task_handlers
+                .validate_input(&name, input_data.as_ref()),
+        )?;
         let pool = ctx.data::<GraphQLContext>()?.pool.clone();
         let event_sender = ctx.data::<GraphQLContext>()?.event_sender.clone();
+        let actor = get_current_user_id(ctx)?;
+        let tenant = get_current_tenant_id(ctx)?;
 
+        if fetch_tenant_job(&pool, job_id.0, tenant)
+            .await
+            .map_err(map_db_error)?
+            .is_none()
+        {
+            return Err(GqlError::NotFound(format!("Job {} not found", job_id.0)).into());
+        }
+
+        let mut tx = pool.begin().await.map_err(map_db_error)?;
+        if let Some(depends_on) = depends_on {
+            check_task_dependency(&mut tx, None, job_id.0, depends_on.0).await?;
+        }
         let task = sqlx::query_as::<_, Task>(
             r#"
-            INSERT INTO tasks (id, job_id, name, status, input_data, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $6)
+            INSERT INTO tasks (id, job_id, name, status, input_data, depends_on, created_at, updated_at, created_by, updated_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $7, $8, $8)
             RETURNING *
             "#,
         )
@@ -276,22 +1996,36 @@ impl Mutation {
         .bind(name)
         .bind(Status::Pending)
         .bind(input_data)
+        .bind(depends_on)
         .bind(chrono::Utc::now())
-        .fetch_one(&pool)
-        .await?;
+        .bind(actor)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+        record_audit(&mut tx, actor, "createTask", "Task", task.id, &task).await?;
+        tx.commit().await.map_err(map_db_error)?;
 
         // Emit event
-        let _ = event_sender.send(ETLEvent {
+        event_sender.publish(&ETLEvent {
+            event_id: None,
             event_type: "TaskCreated".to_string(),
             entity_id: task.id,
             status: Some(task.status),
-            data: Some(serde_json::to_string(&task)?),
+            previous_status: None,
+            data: Some(serde_json::to_string(&task)?.into()),
+            business_group_id: tenant,
         });
 
         Ok(task)
     }
 
     /// Update a task's status
+    ///
+    /// Illegal status transitions are rejected as in `updateJobStatus`, and `outputData`
+    /// must match the output schema of the task's kind, if it has one.
+    ///
+    /// Deprecated in favour of `updateTask`; this mutation keeps working.
+    #[graphql(deprecation = "use updateTask")]
     async fn update_task_status(
         &self,
         ctx: &Context<'_>,
@@ -299,37 +2033,424 @@ impl Mutation {
         status: Status,
         output_data: Option<serde_json::Value>,
     ) -> async_graphql::Result<Option<Task>> {
-        let pool = ctx.data::<GraphQLContext>()?.pool.clone();
-        let event_sender = ctx.data::<GraphQLContext>()?.event_sender.clone();
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let pool = gql_ctx.pool.clone();
+        let event_sender = gql_ctx.event_sender.clone();
+        let actor = get_current_user_id(ctx)?;
+        let tenant = get_current_tenant_id(ctx)?;
+
+        let mut tx = pool.begin().await.map_err(map_db_error)?;
+        let Some(current) = lock_tenant_task(&mut tx, id.0, tenant)
+            .await
+            .map_err(map_db_error)?
+        else {
+            return Ok(None);
+        };
+        check_status_transition(current.status, status)?;
+        if let Some(ref output_data) = output_data {
+            check_task_data(
+                "outputData",
+                &current.name,
+                gql_ctx
+                    .task_handlers
+                    .validate_output(&current.name, output_data),
+            )?;
+        }
+        let previous_status = Some(current.status);
 
         let task = sqlx::query_as::<_, Task>(
             r#"
             UPDATE tasks
-            SET status = $1, output_data = $2, updated_at = $3
+            SET status = $1, output_data = $2, updated_by = $3
             WHERE id = $4
             RETURNING *
             "#,
         )
         .bind(status)
         .bind(output_data)
-        .bind(chrono::Utc::now())
+        .bind(actor)
         .bind(id.0)
-        .fetch_optional(&pool)
-        .await?;
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+        if let Some(ref task) = task {
+            record_audit(&mut tx, actor, "updateTaskStatus", "Task", task.id, task).await?;
+            if task.status == Status::Failed && previous_status != Some(Status::Failed) {
+                let message = format!(
+                    "task {} failed: {}",
+                    task.name,
+                    task.error_message
+                        .as_deref()
+                        .unwrap_or("marked Failed by updateTaskStatus")
+                );
+                DbConnection::record_job_error(&mut tx, task.job_id, &message)
+                    .await
+                    .map_err(map_db_error)?;
+            }
+        }
+        tx.commit().await.map_err(map_db_error)?;
 
         if let Some(ref task) = task {
             // Emit event
-            let _ = event_sender.send(ETLEvent {
+            event_sender.publish(&ETLEvent {
+                event_id: None,
                 event_type: "TaskStatusUpdated".to_string(),
                 entity_id: task.id,
                 status: Some(task.status),
-                data: Some(serde_json::to_string(&task)?),
+                previous_status,
+                data: Some(serde_json::to_string(&task)?.into()),
+                business_group_id: tenant,
+            });
+        }
+
+        Ok(task)
+    }
+
+    /// Update any of a task's name, description, status, output, dependency or error message
+    ///
+    /// Fields left unset keep their current values. Illegal status transitions are
+    /// rejected as in `updateJobStatus`, `outputData` must match the output schema of
+    /// the task's (possibly new) kind, if it has one, and `dependsOn` must be another task
+    /// of the same job.
+    async fn update_task(
+        &self,
+        ctx: &Context<'_>,
+        id: UuidScalar,
+        input: UpdateTask,
+    ) -> async_graphql::Result<Option<Task>> {
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let pool = gql_ctx.pool.clone();
+        let event_sender = gql_ctx.event_sender.clone();
+        let actor = get_current_user_id(ctx)?;
+        let tenant = get_current_tenant_id(ctx)?;
+
+        let mut tx = pool.begin().await.map_err(map_db_error)?;
+        let Some(current) = lock_tenant_task(&mut tx, id.0, tenant)
+            .await
+            .map_err(map_db_error)?
+        else {
+            return Ok(None);
+        };
+        if let Some(status) = input.status {
+            check_status_transition(current.status, status)?;
+        }
+        if let Some(depends_on) = input.depends_on {
+            check_task_dependency(&mut tx, Some(id.0), current.job_id.0, depends_on.0).await?;
+        }
+        if let Some(ref output_data) = input.output_data {
+            let kind = input.name.as_deref().unwrap_or(&current.name);
+            check_task_data(
+                "outputData",
+                kind,
+                gql_ctx.task_handlers.validate_output(kind, &output_data.0),
+            )?;
+        }
+        let previous_status = Some(current.status);
+
+        let task = sqlx::query_as::<_, Task>(
+            r#"
+            UPDATE tasks
+            SET name = COALESCE($1, name),
+                description = COALESCE($2, description),
+                status = COALESCE($3, status),
+                output_data = COALESCE($4, output_data),
+                depends_on = COALESCE($5, depends_on),
+                error_message = COALESCE($6, error_message),
+                updated_by = $7
+            WHERE id = $8
+            RETURNING *
+            "#,
+        )
+        .bind(input.name)
+        .bind(input.description)
+        .bind(input.status)
+        .bind(input.output_data)
+        .bind(input.depends_on)
+        .bind(input.error_message)
+        .bind(actor)
+        .bind(id.0)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+        if let Some(ref task) = task {
+            record_audit(&mut tx, actor, "updateTask", "Task", task.id, task).await?;
+            if task.status == Status::Failed && previous_status != Some(Status::Failed) {
+                let message = format!(
+                    "task {} failed: {}",
+                    task.name,
+                    task.error_message
+                        .as_deref()
+                        .unwrap_or("marked Failed by updateTask")
+                );
+                DbConnection::record_job_error(&mut tx, task.job_id, &message)
+                    .await
+                    .map_err(map_db_error)?;
+            }
+        }
+        tx.commit().await.map_err(map_db_error)?;
+
+        if let Some(ref task) = task {
+            // Emit event
+            event_sender.publish(&ETLEvent {
+                event_id: None,
+                event_type: "TaskUpdated".to_string(),
+                entity_id: task.id,
+                status: Some(task.status),
+                previous_status,
+                data: Some(serde_json::to_string(&task)?.into()),
+                business_group_id: tenant,
             });
         }
 
         Ok(task)
     }
 
+    /// Set the status of many tasks at once, such as failing everything an outage broke
+    ///
+    /// All listed tasks are updated in a single statement and one `TasksStatusUpdated`
+    /// event is published for the batch. Tasks whose current status can't move to
+    /// `status` are left unchanged and returned in `skipped`; IDs of tasks that don't
+    /// exist or belong to another tenant's jobs are ignored.
+    async fn update_tasks_status(
+        &self,
+        ctx: &Context<'_>,
+        ids: Vec<UuidScalar>,
+        status: Status,
+    ) -> async_graphql::Result<TasksStatusUpdate> {
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let actor = get_current_user_id(ctx)?;
+        let tenant = get_current_tenant_id(ctx)?;
+        let ids: Vec<Uuid> = ids.into_iter().map(|id| id.0).collect();
+
+        let mut query =
+            QueryBuilder::<Postgres>::new("SELECT id, status FROM tasks WHERE id = ANY(");
+        query.push_bind(&ids).push(") AND ");
+        push_job_tenant_filter(&mut query, tenant);
+        query.push(" FOR UPDATE");
+
+        let mut tx = gql_ctx.pool.begin().await.map_err(map_db_error)?;
+        let current: Vec<(Uuid, Status)> = query
+            .build_query_as()
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(map_db_error)?;
+        let (allowed, skipped): (Vec<_>, Vec<_>) = current
+            .into_iter()
+            .partition(|(_, previous)| previous.can_transition_to(status));
+        let newly_failed: HashSet<Uuid> = allowed
+            .iter()
+            .filter(|&&(_, previous)| status == Status::Failed && previous != Status::Failed)
+            .map(|&(id, _)| id)
+            .collect();
+        let allowed: Vec<Uuid> = allowed.into_iter().map(|(id, _)| id).collect();
+        let skipped: Vec<SkippedTask> = skipped
+            .into_iter()
+            .map(|(id, status)| SkippedTask {
+                id: UuidScalar(id),
+                status,
+            })
+            .collect();
+
+        let tasks = sqlx::query_as::<_, Task>(
+            "UPDATE tasks SET status = $1, updated_by = $2 WHERE id = ANY($3) RETURNING *",
+        )
+        .bind(status)
+        .bind(actor)
+        .bind(&allowed)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+        for task in &tasks {
+            record_audit(&mut tx, actor, "updateTasksStatus", "Task", task.id, task).await?;
+            if newly_failed.contains(&task.id.0) {
+                let message = format!(
+                    "task {} failed: {}",
+                    task.name,
+                    task.error_message
+                        .as_deref()
+                        .unwrap_or("marked Failed by updateTasksStatus")
+                );
+                DbConnection::record_job_error(&mut tx, task.job_id, &message)
+                    .await
+                    .map_err(map_db_error)?;
+            }
+        }
+        tx.commit().await.map_err(map_db_error)?;
+
+        if !skipped.is_empty() {
+            tracing::warn!(
+                "Skipped {} tasks that cannot move to {:?}",
+                skipped.len(),
+                status
+            );
+        }
+        let update = TasksStatusUpdate {
+            count: tasks.len() as i32,
+            skipped,
+        };
+        if !tasks.is_empty() {
+            let task_ids: Vec<UuidScalar> = tasks.iter().map(|task| task.id).collect();
+            gql_ctx.event_sender.publish(&ETLEvent {
+                event_id: None,
+                event_type: "TasksStatusUpdated".to_string(),
+                entity_id: UuidScalar(Uuid::new_v4()),
+                status: Some(status),
+                previous_status: None,
+                data: Some(
+                    serde_json::json!({ "taskIds": task_ids, "skipped": update.skipped })
+                        .to_string()
+                        .into(),
+                ),
+                business_group_id: tenant,
+            });
+        }
+
+        Ok(update)
+    }
+
+    /// Cancel a task
+    ///
+    /// A `Pending` or `Running` task moves to `Cancelled`. Cancelling a task that already
+    /// finished is a no-op that returns the task unchanged.
+    async fn cancel_task(
+        &self,
+        ctx: &Context<'_>,
+        id: UuidScalar,
+    ) -> async_graphql::Result<Option<Task>> {
+        let pool = ctx.data::<GraphQLContext>()?.pool.clone();
+        let event_sender = ctx.data::<GraphQLContext>()?.event_sender.clone();
+        let actor = get_current_user_id(ctx)?;
+
+        let tenant = get_current_tenant_id(ctx)?;
+
+        let mut tx = pool.begin().await.map_err(map_db_error)?;
+        let Some(task) = lock_tenant_task(&mut tx, id.0, tenant)
+            .await
+            .map_err(map_db_error)?
+        else {
+            return Ok(None);
+        };
+        if task.status.is_terminal() {
+            return Ok(Some(task));
+        }
+        let previous_status = task.status;
+
+        let task = sqlx::query_as::<_, Task>(
+            r#"
+            UPDATE tasks
+            SET status = $1, completed_at = NOW(), updated_by = $2
+            WHERE id = $3
+            RETURNING *
+            "#,
+        )
+        .bind(Status::Cancelled)
+        .bind(actor)
+        .bind(id.0)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+        record_audit(&mut tx, actor, "cancelTask", "Task", task.id, &task).await?;
+        tx.commit().await.map_err(map_db_error)?;
+
+        // Emit event
+        event_sender.publish(&ETLEvent {
+            event_id: None,
+            event_type: "TaskCancelled".to_string(),
+            entity_id: task.id,
+            status: Some(task.status),
+            previous_status: Some(previous_status),
+            data: Some(serde_json::to_string(&task)?.into()),
+            business_group_id: tenant,
+        });
+
+        Ok(Some(task))
+    }
+
+    /// Load every JSON and XML file in a directory into `json_data`
+    ///
+    /// `path` must be inside `ETL_ALLOWED_ROOT` (default `data`); relative paths are
+    /// resolved against it. Requires an admin user.
+    #[graphql(guard = "AdminGuard")]
+    async fn trigger_etl_directory(
+        &self,
+        ctx: &Context<'_>,
+        path: String,
+    ) -> async_graphql::Result<EtlRunSummary> {
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let allowed_root = std::env::var("ETL_ALLOWED_ROOT")
+            .unwrap_or_else(|_| DEFAULT_ETL_ALLOWED_ROOT.to_string());
+
+        run_etl_directory(
+            &gql_ctx.pool,
+            &gql_ctx.event_sender,
+            Path::new(&allowed_root),
+            &path,
+        )
+        .await
+    }
+
+    /// Delete `json_data` rows loaded more than `olderThanDays` days ago
+    ///
+    /// Returns the number of deleted rows. `olderThanDays` must be at least 1. Requires an
+    /// admin user.
+    #[graphql(guard = "AdminGuard")]
+    async fn purge_json_data(
+        &self,
+        ctx: &Context<'_>,
+        older_than_days: i32,
+    ) -> async_graphql::Result<i32> {
+        let pool = &ctx.data::<GraphQLContext>()?.pool;
+        let deleted = purge_json_data(pool, older_than_days).await?;
+        tracing::info!(
+            "Purged {} json_data rows older than {} days",
+            deleted,
+            older_than_days
+        );
+        Ok(deleted)
+    }
+
+    /// Start running a job's pending tasks in the background
+    ///
+    /// Returns the job as it was when the run was started, or null if it doesn't exist.
+    /// Progress is reported through `etlEvents`. Only `Pending` jobs can be run: a
+    /// `Running` job is rejected with `CONFLICT`, and other statuses as illegal
+    /// transitions to `Running` (a `Failed` job must be reset to `Pending` first).
+    async fn run_job(
+        &self,
+        ctx: &Context<'_>,
+        id: UuidScalar,
+    ) -> async_graphql::Result<Option<Job>> {
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let pool = gql_ctx.pool.clone();
+        let actor = get_current_user_id(ctx)?;
+
+        let tenant = get_current_tenant_id(ctx)?;
+
+        let mut tx = pool.begin().await.map_err(map_db_error)?;
+        let job = lock_tenant_job(&mut tx, id.0, tenant)
+            .await
+            .map_err(map_db_error)?;
+        if let Some(ref job) = job {
+            if job.status == Status::Running {
+                return Err(GqlError::Conflict(format!("Job {} is already running", id.0)).into());
+            }
+            check_status_transition(job.status, Status::Running)?;
+            record_audit(&mut tx, actor, "runJob", "Job", job.id, job).await?;
+        }
+        tx.commit().await.map_err(map_db_error)?;
+
+        if job.is_some() {
+            let event_sender = gql_ctx.event_sender.clone();
+            tokio::spawn(async move {
+                if let Err(e) = task_runner::run_job(pool, event_sender, id).await {
+                    tracing::error!("Job {} run failed: {}", id.0, e);
+                }
+            });
+        }
+
+        Ok(job)
+    }
+
     /// Create a new pipeline run
     async fn create_pipeline_run(
         &self,
@@ -338,7 +2459,18 @@ impl Mutation {
     ) -> async_graphql::Result<PipelineRun> {
         let pool = ctx.data::<GraphQLContext>()?.pool.clone();
         let event_sender = ctx.data::<GraphQLContext>()?.event_sender.clone();
+        let actor = get_current_user_id(ctx)?;
+        let tenant = get_current_tenant_id(ctx)?;
 
+        if fetch_tenant_job(&pool, job_id.0, tenant)
+            .await
+            .map_err(map_db_error)?
+            .is_none()
+        {
+            return Err(GqlError::NotFound(format!("Job {} not found", job_id.0)).into());
+        }
+
+        let mut tx = pool.begin().await.map_err(map_db_error)?;
         let run = sqlx::query_as::<_, PipelineRun>(
             r#"
             INSERT INTO pipeline_runs (id, job_id, status, created_at, updated_at)
@@ -350,21 +2482,107 @@ impl Mutation {
         .bind(job_id.0)
         .bind(Status::Pending)
         .bind(chrono::Utc::now())
-        .fetch_one(&pool)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+        record_audit(
+            &mut tx,
+            actor,
+            "createPipelineRun",
+            "PipelineRun",
+            run.id,
+            &run,
+        )
         .await?;
+        tx.commit().await.map_err(map_db_error)?;
 
         // Emit event
-        let _ = event_sender.send(ETLEvent {
+        event_sender.publish(&ETLEvent {
+            event_id: None,
+            event_type: "PipelineRunCreated".to_string(),
+            entity_id: run.id,
+            status: Some(run.status),
+            previous_status: None,
+            data: Some(serde_json::to_string(&run)?.into()),
+            business_group_id: tenant,
+        });
+
+        Ok(run)
+    }
+
+    /// Create a pipeline run for a job and execute it in the background
+    ///
+    /// The run is returned in its initial `Running` state while the job's pending tasks
+    /// run; it then becomes `Completed`, `Failed` or (if the job is cancelled meanwhile)
+    /// `Cancelled`, with metrics recorded. Subscribe to
+    /// `etlEvents` for the `PipelineRunStatusUpdated` event announcing the outcome.
+    async fn start_pipeline_run(
+        &self,
+        ctx: &Context<'_>,
+        job_id: UuidScalar,
+    ) -> async_graphql::Result<PipelineRun> {
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let pool = gql_ctx.pool.clone();
+        let event_sender = gql_ctx.event_sender.clone();
+        let actor = get_current_user_id(ctx)?;
+        let tenant = get_current_tenant_id(ctx)?;
+
+        if fetch_tenant_job(&pool, job_id.0, tenant)
+            .await
+            .map_err(map_db_error)?
+            .is_none()
+        {
+            return Err(GqlError::NotFound(format!("Job {} not found", job_id.0)).into());
+        }
+
+        let mut tx = pool.begin().await.map_err(map_db_error)?;
+        let run = sqlx::query_as::<_, PipelineRun>(
+            r#"
+            INSERT INTO pipeline_runs (id, job_id, status, created_at, updated_at)
+            VALUES ($1, $2, $3, NOW(), NOW())
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(job_id.0)
+        .bind(Status::Running)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+        record_audit(
+            &mut tx,
+            actor,
+            "startPipelineRun",
+            "PipelineRun",
+            run.id,
+            &run,
+        )
+        .await?;
+        tx.commit().await.map_err(map_db_error)?;
+
+        event_sender.publish(&ETLEvent {
+            event_id: None,
             event_type: "PipelineRunCreated".to_string(),
             entity_id: run.id,
             status: Some(run.status),
-            data: Some(serde_json::to_string(&run)?),
+            previous_status: None,
+            data: Some(serde_json::to_string(&run)?.into()),
+            business_group_id: tenant,
+        });
+
+        let run_id = run.id;
+        tokio::spawn(async move {
+            if let Err(e) = task_runner::run_pipeline(pool, event_sender, run_id, job_id).await {
+                tracing::error!("Pipeline run {} failed to finish: {}", run_id.0, e);
+            }
         });
 
         Ok(run)
     }
 
     /// Update a pipeline run's status
+    ///
+    /// Illegal status transitions are rejected as in `updateJobStatus`.
     async fn update_pipeline_run_status(
         &self,
         ctx: &Context<'_>,
@@ -374,29 +2592,62 @@ impl Mutation {
     ) -> async_graphql::Result<Option<PipelineRun>> {
         let pool = ctx.data::<GraphQLContext>()?.pool.clone();
         let event_sender = ctx.data::<GraphQLContext>()?.event_sender.clone();
+        let actor = get_current_user_id(ctx)?;
+        let tenant = get_current_tenant_id(ctx)?;
+
+        let mut tx = pool.begin().await.map_err(map_db_error)?;
+        let mut query =
+            QueryBuilder::<Postgres>::new("SELECT status FROM pipeline_runs WHERE id = ");
+        query.push_bind(id.0).push(" AND ");
+        push_job_tenant_filter(&mut query, tenant);
+        query.push(" FOR UPDATE");
+        let Some(previous_status) = query
+            .build_query_scalar::<Status>()
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(map_db_error)?
+        else {
+            return Ok(None);
+        };
+        check_status_transition(previous_status, status)?;
 
         let run = sqlx::query_as::<_, PipelineRun>(
             r#"
             UPDATE pipeline_runs
-            SET status = $1, metrics = $2, updated_at = $3
-            WHERE id = $4
+            SET status = $1, metrics = $2
+            WHERE id = $3
             RETURNING *
             "#,
         )
         .bind(status)
         .bind(metrics)
-        .bind(chrono::Utc::now())
         .bind(id.0)
-        .fetch_optional(&pool)
-        .await?;
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+        if let Some(ref run) = run {
+            record_audit(
+                &mut tx,
+                actor,
+                "updatePipelineRunStatus",
+                "PipelineRun",
+                run.id,
+                run,
+            )
+            .await?;
+        }
+        tx.commit().await.map_err(map_db_error)?;
 
         if let Some(ref run) = run {
             // Emit event
-            let _ = event_sender.send(ETLEvent {
+            event_sender.publish(&ETLEvent {
+                event_id: None,
                 event_type: "PipelineRunStatusUpdated".to_string(),
                 entity_id: run.id,
                 status: Some(run.status),
-                data: Some(serde_json::to_string(&run)?),
+                previous_status: Some(previous_status),
+                data: Some(serde_json::to_string(&run)?.into()),
+                business_group_id: tenant,
             });
         }
 
@@ -404,21 +2655,39 @@ impl Mutation {
     }
 
     /// Create a new user
+    ///
+    /// With a `password` the user can sign in through `login` when `AUTH_PROVIDER=local`;
+    /// only its Argon2 hash is stored.
     async fn create_user(
         &self,
         ctx: &Context<'_>,
-        username: String,
+        username: NonEmptyString,
         email: String,
+        #[graphql(secret)] password: Option<String>,
     ) -> async_graphql::Result<User> {
         let pool = ctx.data::<GraphQLContext>()?.pool.clone();
+        let actor = get_current_user_id(ctx)?;
+        let password_hash = match password {
+            Some(password) => Some(
+                tokio::task::spawn_blocking(move || hash_password(&password))
+                    .await?
+                    .map_err(|e| GqlError::Internal(format!("Failed to hash password: {}", e)))?,
+            ),
+            None => None,
+        };
+
+        let mut tx = pool.begin().await.map_err(map_db_error)?;
         let user = sqlx::query_as::<_, User>(
-            "INSERT INTO public.users (id, username, email, created_at, updated_at) VALUES ($1, $2, $3, NOW(), NOW()) RETURNING *",
+            "INSERT INTO public.users (id, username, email, password_hash, created_at, updated_at) VALUES ($1, $2, $3, $4, NOW(), NOW()) RETURNING *",
         )
         .bind(UuidScalar(uuid::Uuid::new_v4()))
         .bind(username)
         .bind(email)
-        .fetch_one(&pool)
-        .await?;
+        .bind(password_hash)
+        .fetch_one(&mut *tx)
+        .await.map_err(map_db_error)?;
+        record_audit(&mut tx, actor, "createUser", "User", user.id, &user).await?;
+        tx.commit().await.map_err(map_db_error)?;
         Ok(user)
     }
 
@@ -427,37 +2696,54 @@ impl Mutation {
         &self,
         ctx: &Context<'_>,
         id: UuidScalar,
-        username: Option<String>,
+        username: Option<NonEmptyString>,
         email: Option<String>,
     ) -> async_graphql::Result<Option<User>> {
         let pool = ctx.data::<GraphQLContext>()?.pool.clone();
+        let actor = get_current_user_id(ctx)?;
+
+        let mut tx = pool.begin().await.map_err(map_db_error)?;
         let user = sqlx::query_as::<_, User>(
-            "UPDATE public.users SET username = COALESCE($1, username), email = COALESCE($2, email), updated_at = NOW() WHERE id = $3 RETURNING *",
+            "UPDATE public.users SET username = COALESCE($1, username), email = COALESCE($2, email) WHERE id = $3 RETURNING *",
         )
         .bind(username)
         .bind(email)
         .bind(id.0)
-        .fetch_optional(&pool)
-        .await?;
+        .fetch_optional(&mut *tx)
+        .await.map_err(map_db_error)?;
+        if let Some(ref user) = user {
+            record_audit(&mut tx, actor, "updateUser", "User", user.id, user).await?;
+        }
+        tx.commit().await.map_err(map_db_error)?;
         Ok(user)
     }
 
     /// Delete a user
     async fn delete_user(&self, ctx: &Context<'_>, id: UuidScalar) -> async_graphql::Result<bool> {
         let pool = ctx.data::<GraphQLContext>()?.pool.clone();
-        let result = sqlx::query("DELETE FROM public.users WHERE id = $1")
-            .bind(id.0)
-            .execute(&pool)
-            .await?;
-        Ok(result.rows_affected() > 0)
+        let actor = get_current_user_id(ctx)?;
+
+        let mut tx = pool.begin().await.map_err(map_db_error)?;
+        let deleted =
+            sqlx::query_as::<_, User>("DELETE FROM public.users WHERE id = $1 RETURNING *")
+                .bind(id.0)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(map_db_error)?;
+        if let Some(ref user) = deleted {
+            record_audit(&mut tx, actor, "deleteUser", "User", user.id, user).await?;
+        }
+        tx.commit().await.map_err(map_db_error)?;
+        Ok(deleted.is_some())
     }
 
-    /// Login with Auth0/Okta credentials
+    /// Login with Auth0/Okta credentials, or a local account's password when
+    /// `AUTH_PROVIDER=local`
     async fn login(
         &self,
         ctx: &Context<'_>,
         email: String,
-        password: String,
+        #[graphql(secret)] password: String,
     ) -> async_graphql::Result<AuthResponse> {
         let auth_provider = &ctx.data::<GraphQLContext>()?.auth_provider;
         auth_provider.login(email, password).await
@@ -470,65 +2756,551 @@ pub struct Subscription;
 #[Subscription]
 impl Subscription {
     /// Subscribe to ETL events
+    ///
+    /// Only events of the subscriber's tenant are delivered, or global events for
+    /// subscribers without one (see `graphql_ws_handler` for authenticating the connection).
+    ///
+    /// With `sinceEventId` and/or `replayLast`, persisted events are replayed first: those
+    /// after `sinceEventId`, limited to the most recent `replayLast` (at most 1000), oldest
+    /// first. Live events follow, skipping any that were already replayed.
+    ///
+    /// Live events are buffered in a bounded channel. A subscriber that falls further
+    /// behind than the buffer skips the oldest events instead of holding them in memory;
+    /// it can catch up by resubscribing with `sinceEventId`.
+    ///
+    /// When no event arrives for `SUBSCRIPTION_KEEPALIVE_SECS` (default 30), a `Ping`
+    /// event with a nil `entityId` is sent so proxies don't close the idle connection.
+    /// Clients can ignore pings.
     async fn etl_events(
         &self,
         ctx: &Context<'_>,
+        since_event_id: Option<i64>,
+        replay_last: Option<i32>,
     ) -> async_graphql::Result<impl futures::Stream<Item = ETLEvent>> {
-        let event_sender = ctx.data::<GraphQLContext>()?.event_sender.clone();
-        let mut receiver = event_sender.subscribe();
+        let gql_ctx = ctx.data::<GraphQLContext>()?;
+        let tenant = get_current_tenant_id(ctx)?;
+        // Subscribe before reading the backlog so nothing published in between is missed
+        let mut receiver = gql_ctx.subscription_sender.subscribe();
+        let keepalive = gql_ctx.subscription_keepalive;
+
+        let replayed = if since_event_id.is_some() || replay_last.is_some() {
+            let limit = replay_last
+                .map_or(MAX_EVENT_REPLAY, i64::from)
+                .clamp(0, MAX_EVENT_REPLAY);
+            replay_events(&gql_ctx.pool, tenant, since_event_id, limit)
+                .await
+                .map_err(map_db_error)?
+        } else {
+            Vec::new()
+        };
+        let replayed_up_to = replayed
+            .last()
+            .and_then(|event| event.event_id)
+            .or(since_event_id);
 
         Ok(async_stream::stream! {
-            while let Ok(event) = receiver.recv().await {
+            for event in replayed {
+                yield event;
+            }
+            let mut pings = (!keepalive.is_zero()).then(|| {
+                let mut interval =
+                    tokio::time::interval_at(tokio::time::Instant::now() + keepalive, keepalive);
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                interval
+            });
+            loop {
+                let event = tokio::select! {
+                    event = next_event(&mut receiver) => match event {
+                        Some(event) => event,
+                        None => break,
+                    },
+                    _ = async { pings.as_mut().unwrap().tick().await }, if pings.is_some() => {
+                        yield ping_event();
+                        continue;
+                    }
+                };
+                if event.business_group_id != tenant {
+                    continue;
+                }
+                if let (Some(id), Some(up_to)) = (event.event_id, replayed_up_to) {
+                    if id <= up_to {
+                        continue;
+                    }
+                }
+                // Pings only fill idle periods
+                if let Some(pings) = pings.as_mut() {
+                    pings.reset();
+                }
                 yield event;
             }
         })
     }
+    /// Subscribe to job status transitions, optionally for a single job
+    ///
+    /// Only yields when a job's status actually changes; updates that set the same
+    /// status again are skipped. Like `etlEvents`, only the subscriber's tenant's jobs
+    /// are reported.
+    async fn job_status_changed(
+        &self,
+        ctx: &Context<'_>,
+        job_id: Option<UuidScalar>,
+    ) -> async_graphql::Result<impl futures::Stream<Item = JobStatusEvent>> {
+        let tenant = get_current_tenant_id(ctx)?;
+        let mut receiver = ctx
+            .data::<GraphQLContext>()?
+            .subscription_sender
+            .subscribe();
+
+        Ok(async_stream::stream! {
+            while let Some(event) = next_event(&mut receiver).await {
+                if !matches!(
+                    event.event_type.as_str(),
+                    "JobStatusUpdated" | "JobUpdated" | "JobCancelled"
+                ) {
+                    continue;
+                }
+                if event.business_group_id != tenant
+                    || job_id.is_some_and(|id| id.0 != event.entity_id.0)
+                {
+                    continue;
+                }
+                if let (Some(old_status), Some(new_status)) = (event.previous_status, event.status) {
+                    if old_status != new_status {
+                        yield JobStatusEvent {
+                            job_id: event.entity_id,
+                            old_status,
+                            new_status,
+                        };
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Returns the keepalive event `etlEvents` sends on idle subscriptions.
+fn ping_event() -> ETLEvent {
+    ETLEvent {
+        event_id: None,
+        event_type: "Ping".to_string(),
+        entity_id: UuidScalar(Uuid::nil()),
+        status: None,
+        previous_status: None,
+        data: None,
+        business_group_id: None,
+    }
+}
+
+/// Receives the next event for a subscription, or `None` once the channel is closed.
+///
+/// A subscriber that lagged behind the channel's capacity skips the events it missed
+/// and carries on, rather than ending the subscription.
+async fn next_event(receiver: &mut broadcast::Receiver<ETLEvent>) -> Option<ETLEvent> {
+    loop {
+        match receiver.recv().await {
+            Ok(event) => return Some(event),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!("Subscriber lagged; {} events were skipped", skipped);
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
 }
 
 /// Create a new GraphQL schema
+///
+/// `events` is either a single broadcast channel or the `EventChannels` of an event bus.
 pub fn create_schema(
     pool: PgPool,
-    event_sender: broadcast::Sender<ETLEvent>,
+    events: impl Into<EventChannels>,
+) -> Schema<Query, Mutation, Subscription> {
+    let auth_provider = auth_provider_from_env(pool.clone());
+    create_schema_with_auth(pool, events, auth_provider)
+}
+
+/// Returns the provider selected by `AUTH_PROVIDER` (Auth0/Okta unless `local`), which
+/// skips re-verification of recently seen tokens
+pub fn auth_provider_from_env(pool: PgPool) -> Arc<dyn AuthProvider> {
+    match std::env::var("AUTH_PROVIDER").as_deref() {
+        Ok("local") => Arc::new(CachedAuthProvider::new(LocalAuthProvider::from_env(pool))),
+        _ => Arc::new(CachedAuthProvider::new(Auth0Okta::new())),
+    }
+}
+
+/// Create a new GraphQL schema using the given authentication provider
+///
+/// Operations costlier than `GRAPHQL_MAX_COMPLEXITY` (default 1000) are rejected before
+/// they run. Paginated fields cost their requested `limit` times the cost of one item.
+///
+/// Introspection is disabled when `GRAPHQL_INTROSPECTION=off`.
+pub fn create_schema_with_auth(
+    pool: PgPool,
+    events: impl Into<EventChannels>,
+    auth_provider: Arc<dyn AuthProvider>,
 ) -> Schema<Query, Mutation, Subscription> {
-    // Initialize Auth0/Okta provider
-    let auth_provider = Arc::new(Auth0Okta::new()) as Arc<dyn AuthProvider>;
+    let events = events.into();
+    let metrics_cache_ttl = std::env::var("METRICS_CACHE_TTL_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_METRICS_CACHE_TTL_SECS);
+    let subscription_keepalive = Duration::from_secs(
+        std::env::var("SUBSCRIPTION_KEEPALIVE_SECS")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_SUBSCRIPTION_KEEPALIVE_SECS),
+    );
+    let max_complexity = std::env::var("GRAPHQL_MAX_COMPLEXITY")
+        .ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_GRAPHQL_MAX_COMPLEXITY);
+    let metrics_cache = Cache::builder()
+        .time_to_live(Duration::from_secs(metrics_cache_ttl))
+        .build();
 
-    Schema::build(Query, Mutation, Subscription)
+    let task_progress_loader =
+        DataLoader::new(TaskProgressLoader { pool: pool.clone() }, tokio::spawn);
+    let user_loader = DataLoader::new(UserLoader { pool: pool.clone() }, tokio::spawn);
+    let job_loader = DataLoader::new(JobLoader { pool: pool.clone() }, tokio::spawn);
+
+    let builder = Schema::build(Query, Mutation, Subscription);
+    let builder = if introspection_enabled() {
+        builder
+    } else {
+        builder.disable_introspection()
+    };
+    builder
         .data(GraphQLContext {
             pool,
-            event_sender,
+            event_sender: events.sink,
+            subscription_sender: events.subscriber,
             auth_provider,
             current_user_id: None,
+            tenant_id: None,
+            metrics_cache,
+            started_at: Utc::now(),
+            task_progress_loader,
+            user_loader,
+            job_loader,
+            task_handlers: HandlerRegistry::with_builtins(),
+            pagination: PaginationConfig::from_env(),
+            subscription_keepalive,
         })
+        .extension(ValidateOnlyExtension)
+        .extension(OperationLogExtension)
+        .limit_complexity(max_complexity)
         .finish()
 }
 
 /// Create a new GraphQL router
-pub fn create_router(schema: Schema<Query, Mutation, Subscription>) -> Router {
-    Router::new()
-        .route("/graphql", post(graphql_handler))
+///
+/// Request bodies sent to `/graphql` are limited to `GRAPHQL_MAX_BODY_BYTES` (default
+/// 1 MiB); larger requests are rejected with `413 Payload Too Large`. The limit is applied
+/// per route so other endpoints can set their own.
+///
+/// `/graphql` also accepts a JSON array of operations, answered with an array of responses
+/// in the same order. Batches of more than `GRAPHQL_MAX_BATCH_SIZE` (default 10)
+/// operations are rejected with a single `BATCH_TOO_LARGE` error.
+///
+/// `/graphql/validate` parses and validates a single operation against the schema without
+/// executing it, answering `{ "valid": true }` or `{ "valid": false, "errors": [...] }`.
+///
+/// Responses are gzip/brotli-compressed when the client sends `Accept-Encoding`, unless
+/// `HTTP_COMPRESSION=off`.
+///
+/// `/schema.graphql` serves the schema as SDL, for tooling and CI schema checks. Like
+/// introspection it is disabled by `GRAPHQL_INTROSPECTION=off`, answering `404 Not Found`.
+///
+/// `/export/users.csv` streams every user as CSV to admins authenticated with a bearer
+/// token, reading `exports.pool` through a cursor and checking tokens with `exports.auth_provider`.
+///
+/// HTTP requests that take longer than `REQUEST_TIMEOUT_SECS` (default 30) are aborted
+/// with `408 Request Timeout`. Subscriptions over WebSocket at `/graphql/ws` are exempt,
+/// so they stay open for as long as the client keeps them.
+///
+/// When `GRAPHQL_ALLOWLIST` names a JSON file of operation hashes (see `operation_hash`),
+/// `/graphql` and `/graphql/ws` only run those operations. Fails if that file can't be
+/// loaded.
+pub fn create_router(
+    schema: Schema<Query, Mutation, Subscription>,
+    exports: ExportState,
+) -> Result<Router, AllowlistError> {
+    let request_timeout = std::env::var("REQUEST_TIMEOUT_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_REQUEST_TIMEOUT_SECS);
+    let allowlist = OperationAllowlist::from_env()?;
+    if let Some(allowlist) = &allowlist {
+        tracing::info!(
+            "GraphQL operation allowlist enabled with {} operations",
+            allowlist.len()
+        );
+    }
+
+    Ok(build_router(
+        schema,
+        exports,
+        Duration::from_secs(request_timeout),
+        allowlist,
+    ))
+}
+
+/// Builds the router of `create_router` with an explicit request deadline and allowlist
+fn build_router(
+    schema: Schema<Query, Mutation, Subscription>,
+    exports: ExportState,
+    request_timeout: Duration,
+    allowlist: Option<OperationAllowlist>,
+) -> Router {
+    let max_body_bytes = std::env::var("GRAPHQL_MAX_BODY_BYTES")
+        .ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_GRAPHQL_MAX_BODY_BYTES);
+    let max_batch_size = std::env::var("GRAPHQL_MAX_BATCH_SIZE")
+        .ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_GRAPHQL_MAX_BATCH_SIZE);
+
+    let auth_provider = exports.auth_provider.clone();
+    let ws_auth_provider = auth_provider.clone();
+    let ws_allowlist = allowlist.clone();
+    let router = Router::new()
+        .route(
+            "/graphql",
+            post(graphql_handler)
+                .layer(RequestBodyLimitLayer::new(max_body_bytes))
+                .layer(middleware::from_fn(require_graphql_content_type)),
+        )
+        .route(
+            "/graphql/validate",
+            post(graphql_validate_handler)
+                .layer(RequestBodyLimitLayer::new(max_body_bytes))
+                .layer(middleware::from_fn(require_graphql_content_type)),
+        )
         .route("/graphiql", get(graphql_playground))
-        .layer(Extension(schema))
+        .route("/schema.graphql", get(graphql_sdl))
+        .route(
+            "/export/users.csv",
+            get(export::export_users_csv).with_state(exports),
+        )
+        .layer(Extension(schema.clone()))
+        .layer(Extension(MaxBatchSize(max_batch_size)))
+        .layer(Extension(allowlist))
+        .layer(Extension(auth_provider))
+        .layer(TimeoutLayer::new(request_timeout));
+    let router = if compression_enabled() {
+        router.layer(CompressionLayer::new())
+    } else {
+        router
+    };
+
+    // Added after the layers above so long-lived subscriptions aren't timed out
+    router.merge(
+        Router::new()
+            .route("/graphql/ws", get(graphql_ws_handler))
+            .layer(Extension(schema))
+            .layer(Extension(ws_allowlist))
+            .layer(Extension(ws_auth_provider)),
+    )
+}
+
+/// Rejects GraphQL POSTs whose `Content-Type` isn't `application/json` or
+/// `application/graphql` with `415 Unsupported Media Type`, before the body is parsed
+async fn require_graphql_content_type(req: Request, next: Next) -> axum::response::Response {
+    if matches!(
+        content_mime(req.headers()).as_str(),
+        "application/json" | "application/graphql"
+    ) {
+        return next.run(req).await;
+    }
+
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    tracing::debug!(
+        "Rejected GraphQL request with Content-Type {:?}",
+        content_type
+    );
+    let body = serde_json::json!({
+        "errors": [{
+            "message": format!(
+                "Unsupported Content-Type {:?}; use application/json or application/graphql",
+                content_type
+            ),
+            "extensions": { "code": "UNSUPPORTED_MEDIA_TYPE" },
+        }]
+    });
+    (StatusCode::UNSUPPORTED_MEDIA_TYPE, Json(body)).into_response()
+}
+
+/// Returns the lowercased media type of a request's `Content-Type`, without parameters
+/// such as `charset` (empty when the header is missing)
+fn content_mime(headers: &HeaderMap) -> String {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// The body of a GraphQL POST: a JSON operation or batch, or a raw document sent as
+/// `application/graphql`, which becomes a single operation without variables
+struct GraphQLBody(BatchRequest);
+
+impl<S: Send + Sync> FromRequest<S> for GraphQLBody {
+    type Rejection = axum::response::Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if content_mime(req.headers()) == "application/graphql" {
+            let query = String::from_request(req, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            return Ok(Self(BatchRequest::Single(async_graphql::Request::new(
+                query,
+            ))));
+        }
+
+        let req = <GraphQLBatchRequest>::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        Ok(Self(req.into_inner()))
+    }
+}
+
+/// Returns false when `GRAPHQL_INTROSPECTION` is set to `off`
+fn introspection_enabled() -> bool {
+    !std::env::var("GRAPHQL_INTROSPECTION").is_ok_and(|value| value.eq_ignore_ascii_case("off"))
+}
+
+/// Returns false when `HTTP_COMPRESSION` is set to `off`
+fn compression_enabled() -> bool {
+    !std::env::var("HTTP_COMPRESSION").is_ok_and(|value| value.eq_ignore_ascii_case("off"))
+}
+
+/// Reads W3C trace-context headers (e.g. `traceparent`) from an HTTP request
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+/// Returns the token of an `Authorization: Bearer` header, if the request has one
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
 }
 
 /// GraphQL request handler
+///
+/// Handles a single operation or a batch of them; each operation in a batch succeeds or
+/// fails on its own. Bodies sent as `application/graphql` are a single raw document.
+///
+/// With an operation allowlist, a request containing any operation outside it is
+/// rejected as a whole with `operation not allowed`.
+///
+/// An `Authorization: Bearer` token is checked with the auth provider before anything
+/// runs. A valid token makes its `sub` the `CurrentUser` (when it is a UUID) and its
+/// `business_group_id` claim the `CurrentTenant`; an invalid one fails the whole request
+/// with `UNAUTHENTICATED`.
 async fn graphql_handler(
     Extension(schema): Extension<Schema<Query, Mutation, Subscription>>,
-    req: GraphQLRequest,
+    Extension(MaxBatchSize(max_batch_size)): Extension<MaxBatchSize>,
+    Extension(allowlist): Extension<Option<OperationAllowlist>>,
+    Extension(auth_provider): Extension<Arc<dyn AuthProvider>>,
+    headers: HeaderMap,
+    GraphQLBody(mut graphql_req): GraphQLBody,
 ) -> GraphQLResponse {
-    // Convert the request to an async-graphql request
-    let graphql_req = req.into_inner();
+    // Validate any bearer token once, attaching its user for guards and audit columns and
+    // its tenant for scoping, and keeping the raw token for `debugToken`
+    if let Some(token) = bearer_token(&headers) {
+        let claims = match auth_provider.validate_token(token).await {
+            Ok(claims) => claims,
+            Err(e) => {
+                tracing::debug!(
+                    "Rejected GraphQL request with an invalid token: {}",
+                    e.message
+                );
+                let error: async_graphql::Error =
+                    GqlError::Unauthorized(format!("Token validation failed: {}", e.message))
+                        .into();
+                return Response::from_errors(vec![error.into_server_error(Pos::default())]).into();
+            }
+        };
+        if let Ok(user_id) = Uuid::parse_str(&claims.sub) {
+            graphql_req = graphql_req.data(CurrentUser(UuidScalar(user_id)));
+        }
+        if let Some(tenant_id) = claims.business_group_id {
+            graphql_req = graphql_req.data(CurrentTenant(tenant_id));
+        }
+        graphql_req = graphql_req.data(BearerToken(token.to_string()));
+    }
+
+    // Log the incoming request, leaving out its variables
+    for request in graphql_req.iter() {
+        tracing::debug!("Received GraphQL request: {}", request.query);
+    }
 
-    // Log the incoming request
-    if let Ok(request_json) = serde_json::to_string(&graphql_req) {
-        tracing::debug!("Received GraphQL request: {}", request_json);
+    if let Some(allowlist) = &allowlist {
+        if let Some(request) = graphql_req
+            .iter()
+            .find(|request| !allowlist.allows(&request.query))
+        {
+            return not_allowed_response(&request.query).into();
+        }
     }
 
-    // Execute the request
-    let response = schema.execute(graphql_req).await;
+    let operation_name = match &graphql_req {
+        BatchRequest::Single(request) => request.operation_name.clone().unwrap_or_default(),
+        BatchRequest::Batch(requests) if requests.len() > max_batch_size => {
+            tracing::warn!(
+                "Rejected GraphQL batch of {} operations (limit {})",
+                requests.len(),
+                max_batch_size
+            );
+            let error = async_graphql::Error::new(format!(
+                "Batch of {} operations exceeds the limit of {}",
+                requests.len(),
+                max_batch_size
+            ))
+            .extend_with(|_, e| e.set("code", "BATCH_TOO_LARGE"));
+            return Response::from_errors(vec![error.into_server_error(Pos::default())]).into();
+        }
+        BatchRequest::Batch(requests) => format!("batch({})", requests.len()),
+    };
+
+    // Execute the request, inside a span continuing the caller's trace when exporting
+    let response = if otel_enabled() {
+        let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(&headers))
+        });
+        let span = tracing::info_span!("graphql_request", operation_name = operation_name);
+        span.set_parent(parent);
+        schema.execute_batch(graphql_req).instrument(span).await
+    } else {
+        schema.execute_batch(graphql_req).await
+    };
 
     // Log any errors
-    if !response.errors.is_empty() {
+    let responses = match &response {
+        BatchResponse::Single(response) => std::slice::from_ref(response),
+        BatchResponse::Batch(responses) => responses.as_slice(),
+    };
+    for response in responses.iter().filter(|response| response.is_err()) {
         tracing::error!("GraphQL errors: {:?}", response.errors);
     }
 
@@ -536,11 +3308,134 @@ async fn graphql_handler(
     GraphQLResponse::from(response)
 }
 
+/// GraphQL validation handler
+///
+/// Runs the parse and validation phases only; resolvers never run, so no database
+/// access occurs. Takes a single operation, as JSON or a raw `application/graphql`
+/// document; batches are rejected with `400 Bad Request`.
+async fn graphql_validate_handler(
+    Extension(schema): Extension<Schema<Query, Mutation, Subscription>>,
+    GraphQLBody(req): GraphQLBody,
+) -> axum::response::Response {
+    let req = match req.into_single() {
+        Ok(req) => req,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+    let response = schema.execute(req.data(ValidateOnly)).await;
+
+    if response.errors.is_empty() {
+        Json(serde_json::json!({ "valid": true })).into_response()
+    } else {
+        Json(serde_json::json!({ "valid": false, "errors": response.errors })).into_response()
+    }
+}
+
+/// Serves the schema's SDL as plain text, unless introspection is disabled
+async fn graphql_sdl(
+    Extension(schema): Extension<Schema<Query, Mutation, Subscription>>,
+) -> axum::response::Response {
+    if !introspection_enabled() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    (
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        schema.sdl(),
+    )
+        .into_response()
+}
+
+/// GraphQL subscription handler for `/graphql/ws`
+///
+/// Speaks both the `graphql-transport-ws` and the legacy `graphql-ws` protocol. With an
+/// operation allowlist, subscriptions outside it get `operation not allowed`. See
+/// `ws_connection_data` for how connections authenticate.
+async fn graphql_ws_handler(
+    Extension(schema): Extension<Schema<Query, Mutation, Subscription>>,
+    Extension(allowlist): Extension<Option<OperationAllowlist>>,
+    Extension(auth_provider): Extension<Arc<dyn AuthProvider>>,
+    protocol: GraphQLProtocol,
+    upgrade: WebSocketUpgrade,
+) -> axum::response::Response {
+    upgrade
+        .protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |stream| async move {
+            let on_init = move |payload| ws_connection_data(auth_provider, payload);
+            match allowlist {
+                Some(allowlist) => {
+                    let executor = AllowlistedExecutor::new(schema, allowlist);
+                    GraphQLWebSocket::new(stream, executor, protocol)
+                        .on_connection_init(on_init)
+                        .serve()
+                        .await
+                }
+                None => {
+                    GraphQLWebSocket::new(stream, schema, protocol)
+                        .on_connection_init(on_init)
+                        .serve()
+                        .await
+                }
+            }
+        })
+}
+
+/// Checks the bearer token of a subscription connection's `connection_init` payload
+///
+/// The token is read from the payload's `Authorization` (or `authorization`) field as
+/// `Bearer <token>` and treated like the header of `/graphql`: a valid token attaches its
+/// `CurrentUser` and `CurrentTenant` to every subscription on the connection, while an
+/// invalid one is rejected and the connection closed. Connections without a token are
+/// anonymous and only see global events.
+async fn ws_connection_data(
+    auth_provider: Arc<dyn AuthProvider>,
+    payload: serde_json::Value,
+) -> async_graphql::Result<Data> {
+    let mut data = Data::default();
+    let token = ["Authorization", "authorization"]
+        .iter()
+        .find_map(|key| payload.get(key)?.as_str())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim);
+    let Some(token) = token else {
+        return Ok(data);
+    };
+
+    let claims = auth_provider.validate_token(token).await.map_err(|e| {
+        tracing::debug!(
+            "Rejected subscription connection with an invalid token: {}",
+            e.message
+        );
+        async_graphql::Error::from(GqlError::Unauthorized(format!(
+            "Token validation failed: {}",
+            e.message
+        )))
+    })?;
+    if let Ok(user_id) = Uuid::parse_str(&claims.sub) {
+        data.insert(CurrentUser(UuidScalar(user_id)));
+    }
+    if let Some(tenant_id) = claims.business_group_id {
+        data.insert(CurrentTenant(tenant_id));
+    }
+    data.insert(BearerToken(token.to_string()));
+    Ok(data)
+}
+
 /// GraphQL playground handler
 async fn graphql_playground() -> impl axum::response::IntoResponse {
     axum::response::Html(
         async_graphql::http::GraphiQLSource::build()
             .endpoint("/graphql")
+            .subscription_endpoint("/graphql/ws")
             .finish(),
     )
 }
+
+#[cfg(test)]
+mod error_test;
+#[cfg(test)]
+mod operation_log_test;
+#[cfg(test)]
+mod pagination_test;
+#[cfg(test)]
+mod resolver_test;
+#[cfg(test)]
+mod router_test;
@@ -0,0 +1,125 @@
+//! Logging of every executed GraphQL operation with its timing.
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextParseQuery, NextPrepareRequest, NextRequest,
+};
+use async_graphql::parser::types::{DocumentOperations, ExecutableDocument, OperationType};
+use async_graphql::{Request, Response, ServerResult, Variables};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::field::Empty;
+use tracing::Instrument;
+
+use super::ValidateOnly;
+
+/// Schema extension that runs each operation in a `graphql_operation` span and logs its
+/// name, type, duration and whether it failed at info level once it completes.
+///
+/// Only the operation name and type are recorded, never the query's variables, which may
+/// carry personal data. Requests to `/graphql/validate` are not logged.
+pub(crate) struct OperationLogExtension;
+
+impl ExtensionFactory for OperationLogExtension {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(OperationLog::default())
+    }
+}
+
+/// Per-request state of `OperationLogExtension`, filled in as the request is prepared
+#[derive(Default)]
+struct OperationLog {
+    operation: Mutex<Operation>,
+}
+
+/// What is known about the operation being executed
+#[derive(Default, Clone)]
+struct Operation {
+    name: Option<String>,
+    ty: Option<OperationType>,
+    validate_only: bool,
+}
+
+/// Returns the type of the operation a request runs, if the document defines it.
+fn operation_type(document: &ExecutableDocument, name: Option<&str>) -> Option<OperationType> {
+    match (&document.operations, name) {
+        (DocumentOperations::Single(operation), _) => Some(operation.node.ty),
+        (DocumentOperations::Multiple(operations), Some(name)) => operations
+            .iter()
+            .find(|(operation_name, _)| operation_name.as_str() == name)
+            .map(|(_, operation)| operation.node.ty),
+        (DocumentOperations::Multiple(_), None) => None,
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for OperationLog {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let span = tracing::info_span!(
+            "graphql_operation",
+            operation_name = Empty,
+            operation_type = Empty
+        );
+        let started = Instant::now();
+        let response = next.run(ctx).instrument(span.clone()).await;
+        let elapsed = started.elapsed();
+
+        let operation = self.operation.lock().unwrap().clone();
+        if operation.validate_only {
+            return response;
+        }
+        let name = operation.name.as_deref().unwrap_or("anonymous");
+        let ty = operation
+            .ty
+            .map_or_else(|| "unknown".to_string(), |ty| ty.to_string());
+        span.record("operation_name", name);
+        span.record("operation_type", ty.as_str());
+
+        let _entered = span.enter();
+        tracing::info!(
+            operation_name = name,
+            operation_type = ty.as_str(),
+            duration_ms = elapsed.as_millis() as u64,
+            errored = response.is_err(),
+            "GraphQL {} {} finished in {}ms{}",
+            ty,
+            name,
+            elapsed.as_millis(),
+            if response.is_err() {
+                " with errors"
+            } else {
+                ""
+            }
+        );
+        response
+    }
+
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        self.operation.lock().unwrap().name = request.operation_name.clone();
+        next.run(ctx, request).await
+    }
+
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await;
+
+        let mut operation = self.operation.lock().unwrap();
+        // Request data is only attached once the request has been prepared
+        operation.validate_only = ctx.data_opt::<ValidateOnly>().is_some();
+        if let Ok(document) = &document {
+            operation.ty = operation_type(document, operation.name.as_deref());
+        }
+        drop(operation);
+
+        document
+    }
+}
@@ -0,0 +1,143 @@
+use super::operation_log::OperationLogExtension;
+use super::ValidateOnly;
+use async_graphql::{EmptyMutation, EmptySubscription, Object, Request, Schema, Variables};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+struct TestQuery;
+
+#[Object]
+impl TestQuery {
+    async fn echo(&self, value: String) -> String {
+        value
+    }
+
+    async fn fail(&self) -> async_graphql::Result<bool> {
+        Err("failed".into())
+    }
+}
+
+/// An event recorded by `CaptureLayer`, with the name of the span it was emitted in
+#[derive(Debug, Clone)]
+struct CapturedEvent {
+    span: Option<String>,
+    fields: HashMap<String, String>,
+}
+
+/// Layer that records every span name and event
+#[derive(Clone, Default)]
+struct CaptureLayer {
+    spans: Arc<Mutex<Vec<String>>>,
+    events: Arc<Mutex<Vec<CapturedEvent>>>,
+}
+
+struct FieldVisitor<'a>(&'a mut HashMap<String, String>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+impl<S> Layer<S> for CaptureLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+        self.spans
+            .lock()
+            .unwrap()
+            .push(attrs.metadata().name().to_string());
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut fields = HashMap::new();
+        event.record(&mut FieldVisitor(&mut fields));
+        let span = ctx.event_span(event).map(|span| span.name().to_string());
+        self.events
+            .lock()
+            .unwrap()
+            .push(CapturedEvent { span, fields });
+    }
+}
+
+fn schema() -> Schema<TestQuery, EmptyMutation, EmptySubscription> {
+    Schema::build(TestQuery, EmptyMutation, EmptySubscription)
+        .extension(OperationLogExtension)
+        .finish()
+}
+
+/// Runs `request` with a capturing subscriber and returns the operation log events
+async fn operation_events(request: Request) -> (Vec<String>, Vec<CapturedEvent>) {
+    let layer = CaptureLayer::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(layer.clone()));
+
+    schema().execute(request).await;
+
+    let spans = layer.spans.lock().unwrap().clone();
+    let events = layer
+        .events
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|event| event.span.as_deref() == Some("graphql_operation"))
+        .cloned()
+        .collect();
+    (spans, events)
+}
+
+#[tokio::test]
+async fn test_operation_is_logged_in_span_without_variables() {
+    let request = Request::new("query Lookup($value: String!) { echo(value: $value) }")
+        .operation_name("Lookup")
+        .variables(Variables::from_json(
+            json!({ "value": "secret@example.com" }),
+        ));
+
+    let (spans, events) = operation_events(request).await;
+
+    assert!(spans.iter().any(|span| span == "graphql_operation"));
+    assert_eq!(events.len(), 1, "{:?}", events);
+    let fields = &events[0].fields;
+    assert_eq!(fields["operation_name"], "Lookup");
+    assert_eq!(fields["operation_type"], "query");
+    assert_eq!(fields["errored"], "false");
+    assert!(fields.contains_key("duration_ms"));
+    assert!(
+        fields.values().all(|value| !value.contains("secret")),
+        "{:?}",
+        fields
+    );
+}
+
+#[tokio::test]
+async fn test_failed_operation_is_logged_as_errored() {
+    let (_, events) = operation_events(Request::new("{ fail }")).await;
+
+    assert_eq!(events.len(), 1, "{:?}", events);
+    assert_eq!(events[0].fields["operation_name"], "anonymous");
+    assert_eq!(events[0].fields["errored"], "true");
+}
+
+#[tokio::test]
+async fn test_validate_only_request_is_not_logged() {
+    let request = Request::new("{ echo(value: \"x\") }").data(ValidateOnly);
+
+    let (_, events) = operation_events(request).await;
+
+    assert!(events.is_empty(), "{:?}", events);
+}
@@ -0,0 +1,101 @@
+//! Page-size limits shared by paginated resolvers.
+
+use std::env;
+
+use sqlx::FromRow;
+
+/// Default number of items returned when a resolver's `limit` argument is omitted
+pub const DEFAULT_PAGE_LIMIT: i32 = 20;
+
+/// Default largest number of items any paginated resolver returns
+pub const DEFAULT_PAGE_MAX_LIMIT: i32 = 100;
+
+/// Page sizes applied by every resolver that takes a `limit` argument.
+///
+/// Stored in the GraphQL context, so all resolvers clamp limits the same way through
+/// `clamp_limit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaginationConfig {
+    /// Page size used when no `limit` is given
+    pub default_limit: i32,
+    /// Largest page size returned, whatever `limit` is given
+    pub max_limit: i32,
+}
+
+impl Default for PaginationConfig {
+    fn default() -> Self {
+        Self {
+            default_limit: DEFAULT_PAGE_LIMIT,
+            max_limit: DEFAULT_PAGE_MAX_LIMIT,
+        }
+    }
+}
+
+impl PaginationConfig {
+    /// Reads the limits from `PAGE_DEFAULT_LIMIT` and `PAGE_MAX_LIMIT`.
+    ///
+    /// Missing, unparsable or negative values fall back to the defaults (20 and 100).
+    pub fn from_env() -> Self {
+        let read = |name: &str, default: i32| {
+            env::var(name)
+                .ok()
+                .and_then(|value| value.parse::<i32>().ok())
+                .filter(|&limit| limit >= 0)
+                .unwrap_or(default)
+        };
+        Self {
+            default_limit: read("PAGE_DEFAULT_LIMIT", DEFAULT_PAGE_LIMIT),
+            max_limit: read("PAGE_MAX_LIMIT", DEFAULT_PAGE_MAX_LIMIT),
+        }
+    }
+}
+
+/// A row of a paginated query along with the size of the whole, unpaginated result.
+///
+/// Select `COUNT(*) OVER() AS total_count` next to the row's columns, so the page and its
+/// total come from one snapshot instead of two racing queries.
+#[derive(FromRow)]
+pub(crate) struct Counted<T> {
+    #[sqlx(flatten)]
+    pub item: T,
+    pub total_count: i64,
+}
+
+/// Returns the query-complexity cost of a paginated field.
+///
+/// The cost is the requested `limit` (or `DEFAULT_PAGE_LIMIT` when omitted) times the cost
+/// of one item. It uses the limit as requested rather than as clamped, so asking for a huge
+/// page is rejected by the complexity limit instead of silently shrinking.
+///
+/// # Example
+/// ```
+/// use dds::graphql::page_complexity;
+///
+/// assert_eq!(page_complexity(None, 3), 60);
+/// assert_eq!(page_complexity(Some(5), 3), 15);
+/// assert_eq!(page_complexity(Some(-1), 3), 0);
+/// ```
+pub fn page_complexity(limit: Option<i32>, child_complexity: usize) -> usize {
+    let items = limit.unwrap_or(DEFAULT_PAGE_LIMIT).max(0) as usize;
+    items.saturating_mul(child_complexity)
+}
+
+/// Returns the page size for a requested `limit`.
+///
+/// `None` uses `cfg.default_limit`. The result is capped at `cfg.max_limit`, and negative
+/// limits return an empty page.
+///
+/// # Example
+/// ```
+/// use dds::graphql::{clamp_limit, PaginationConfig};
+///
+/// let cfg = PaginationConfig { default_limit: 20, max_limit: 100 };
+/// assert_eq!(clamp_limit(None, cfg), 20);
+/// assert_eq!(clamp_limit(Some(500), cfg), 100);
+/// ```
+pub fn clamp_limit(requested: Option<i32>, cfg: PaginationConfig) -> i32 {
+    requested
+        .unwrap_or(cfg.default_limit)
+        .min(cfg.max_limit)
+        .max(0)
+}
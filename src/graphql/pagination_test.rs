@@ -0,0 +1,49 @@
+use crate::graphql::{clamp_limit, PaginationConfig};
+
+const CFG: PaginationConfig = PaginationConfig {
+    default_limit: 20,
+    max_limit: 100,
+};
+
+#[test]
+fn test_missing_limit_uses_default() {
+    assert_eq!(clamp_limit(None, CFG), 20);
+}
+
+#[test]
+fn test_limit_within_range_is_kept() {
+    assert_eq!(clamp_limit(Some(1), CFG), 1);
+    assert_eq!(clamp_limit(Some(100), CFG), 100);
+}
+
+#[test]
+fn test_limit_above_max_is_capped() {
+    assert_eq!(clamp_limit(Some(101), CFG), 100);
+    assert_eq!(clamp_limit(Some(i32::MAX), CFG), 100);
+}
+
+#[test]
+fn test_zero_and_negative_limits_return_empty_pages() {
+    assert_eq!(clamp_limit(Some(0), CFG), 0);
+    assert_eq!(clamp_limit(Some(-1), CFG), 0);
+    assert_eq!(clamp_limit(Some(i32::MIN), CFG), 0);
+}
+
+#[test]
+fn test_default_above_max_is_capped() {
+    let cfg = PaginationConfig {
+        default_limit: 500,
+        max_limit: 50,
+    };
+    assert_eq!(clamp_limit(None, cfg), 50);
+}
+
+#[test]
+fn test_invalid_config_never_yields_negative_limit() {
+    let cfg = PaginationConfig {
+        default_limit: -5,
+        max_limit: -1,
+    };
+    assert_eq!(clamp_limit(None, cfg), 0);
+    assert_eq!(clamp_limit(Some(10), cfg), 0);
+}
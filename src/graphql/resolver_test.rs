@@ -0,0 +1,2540 @@
+use crate::auth::{
+    AuthProvider, AuthResponse, BearerToken, CurrentTenant, CurrentUser, TokenClaims,
+};
+use crate::db::DbConnection;
+use crate::event_bus::{EventChannels, EventSink};
+use crate::graphql::{create_schema_with_auth, ETLEvent, Mutation, Query, Subscription};
+use crate::models::etl::{Status, UuidScalar};
+use async_graphql::{Request, Schema, Variables};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use futures::StreamExt;
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Auth provider for tests that never authenticates anyone
+pub(super) struct NoAuth;
+
+#[async_trait]
+impl AuthProvider for NoAuth {
+    async fn login(
+        &self,
+        _email: String,
+        _password: String,
+    ) -> async_graphql::Result<AuthResponse> {
+        Err(async_graphql::Error::new("login disabled in tests"))
+    }
+
+    async fn validate_token(&self, _token: &str) -> async_graphql::Result<TokenClaims> {
+        Err(async_graphql::Error::new("tokens disabled in tests"))
+    }
+}
+
+async fn setup_test_schema() -> (Schema<Query, Mutation, Subscription>, PgPool) {
+    let (schema, pool, _) = setup_test_schema_with_events().await;
+    (schema, pool)
+}
+
+async fn setup_test_schema_with_events() -> (
+    Schema<Query, Mutation, Subscription>,
+    PgPool,
+    broadcast::Sender<ETLEvent>,
+) {
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .expect("Failed to create test database");
+    let (event_sender, _) = broadcast::channel(100);
+
+    let schema = create_schema_with_auth(pool.clone(), event_sender.clone(), Arc::new(NoAuth));
+    (schema, pool, event_sender)
+}
+
+async fn execute(
+    schema: &Schema<Query, Mutation, Subscription>,
+    query: &str,
+    variables: serde_json::Value,
+) -> serde_json::Value {
+    let response = schema
+        .execute(Request::new(query).variables(Variables::from_json(variables)))
+        .await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    response.data.into_json().unwrap()
+}
+
+#[tokio::test]
+async fn test_jobs_updated_since_returns_only_newer_jobs() {
+    let (schema, pool) = setup_test_schema().await;
+
+    // Checkpoint in the future so jobs written by other tests are excluded
+    let checkpoint = Utc::now() + Duration::days(1);
+    let before_id = Uuid::new_v4();
+    let after_id = Uuid::new_v4();
+    for (id, updated_at) in [
+        (before_id, checkpoint - Duration::hours(1)),
+        (after_id, checkpoint + Duration::hours(1)),
+    ] {
+        sqlx::query(
+            "INSERT INTO jobs (id, name, status, created_at, updated_at) VALUES ($1, $2, 'Pending', NOW(), $3)",
+        )
+        .bind(id)
+        .bind(format!("testjob_{}", id))
+        .bind(updated_at)
+        .execute(&pool)
+        .await
+        .expect("Failed to insert job");
+    }
+
+    let data = execute(
+        &schema,
+        "query($since: DateTimeScalar) { jobs(updatedSince: $since) { items { id } } }",
+        json!({ "since": checkpoint.to_rfc3339() }),
+    )
+    .await;
+
+    let ids: Vec<&str> = data["jobs"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|job| job["id"].as_str().unwrap())
+        .collect();
+    assert!(ids.contains(&after_id.to_string().as_str()));
+    assert!(!ids.contains(&before_id.to_string().as_str()));
+}
+
+#[tokio::test]
+async fn test_all_tasks_filters_by_status() {
+    let (schema, pool) = setup_test_schema().await;
+
+    let job_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, 'Running')")
+        .bind(job_id)
+        .bind(format!("testjob_{}", job_id))
+        .execute(&pool)
+        .await
+        .expect("Failed to insert job");
+    for status in ["Pending", "Running", "Completed"] {
+        sqlx::query("INSERT INTO tasks (id, job_id, name, status) VALUES ($1, $2, $3, $4::status)")
+            .bind(Uuid::new_v4())
+            .bind(job_id)
+            .bind(format!("task_{}", status))
+            .bind(status)
+            .execute(&pool)
+            .await
+            .expect("Failed to insert task");
+    }
+
+    let data = execute(
+        &schema,
+        "{ allTasks(status: RUNNING, limit: 50) { status jobId } }",
+        json!({}),
+    )
+    .await;
+
+    let tasks = data["allTasks"].as_array().unwrap();
+    assert!(!tasks.is_empty());
+    assert!(tasks.iter().all(|task| task["status"] == "RUNNING"));
+    assert!(tasks.iter().any(|task| task["jobId"] == job_id.to_string()));
+}
+
+#[tokio::test]
+async fn test_large_limit_exceeds_query_complexity() {
+    let (schema, _) = setup_test_schema().await;
+
+    execute(
+        &schema,
+        "{ allTasks(limit: 100) { id status jobId } }",
+        json!({}),
+    )
+    .await;
+
+    // The page would be clamped to 100 items, but the cost follows the requested size
+    let response = schema
+        .execute("{ allTasks(limit: 100000) { id status jobId } }")
+        .await;
+    assert_eq!(response.errors.len(), 1);
+    assert!(
+        response.errors[0].message.contains("too complex"),
+        "{:?}",
+        response.errors
+    );
+    assert_eq!(response.data, async_graphql::Value::Null);
+}
+
+#[tokio::test]
+async fn test_job_status_changed_yields_transition() {
+    let (schema, pool, event_sender) = setup_test_schema_with_events().await;
+
+    let job_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, 'Pending')")
+        .bind(job_id)
+        .bind(format!("testjob_{}", job_id))
+        .execute(&pool)
+        .await
+        .expect("Failed to insert job");
+
+    let mut stream = schema.execute_stream(
+        Request::new(
+            "subscription($id: UuidScalar) { jobStatusChanged(jobId: $id) { jobId oldStatus newStatus } }",
+        )
+        .variables(Variables::from_json(json!({ "id": job_id.to_string() }))),
+    );
+    let next = tokio::spawn(async move { stream.next().await });
+    while event_sender.receiver_count() == 0 {
+        tokio::task::yield_now().await;
+    }
+
+    // Setting the same status again is not a transition and must not be yielded
+    for status in ["PENDING", "RUNNING"] {
+        execute(
+            &schema,
+            "mutation($id: UuidScalar!, $status: Status!) { updateJobStatus(id: $id, status: $status) { id } }",
+            json!({ "id": job_id.to_string(), "status": status }),
+        )
+        .await;
+    }
+
+    let response = tokio::time::timeout(std::time::Duration::from_secs(5), next)
+        .await
+        .expect("no status change received")
+        .unwrap()
+        .expect("subscription ended");
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    let event = &response.data.into_json().unwrap()["jobStatusChanged"];
+    assert_eq!(event["jobId"], job_id.to_string());
+    assert_eq!(event["oldStatus"], "PENDING");
+    assert_eq!(event["newStatus"], "RUNNING");
+}
+
+#[tokio::test]
+async fn test_idle_etl_events_subscription_receives_pings() {
+    async fn next_event_type(
+        stream: &mut (impl futures::Stream<Item = async_graphql::Response> + Unpin),
+    ) -> String {
+        let response = stream.next().await.expect("subscription ended");
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        response.data.into_json().unwrap()["etlEvents"]["eventType"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    // No query runs, so the pool never has to connect
+    let pool = PgPoolOptions::new()
+        .connect_lazy(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .unwrap();
+    let (event_sender, _) = broadcast::channel(100);
+    let schema = create_schema_with_auth(pool, event_sender.clone(), Arc::new(NoAuth));
+
+    // With the clock paused, waiting for the next ping skips straight to it
+    tokio::time::pause();
+    let started = tokio::time::Instant::now();
+    let mut stream =
+        schema.execute_stream(Request::new("subscription { etlEvents { eventType } }"));
+
+    assert_eq!(next_event_type(&mut stream).await, "Ping");
+    assert_eq!(started.elapsed().as_secs(), 30);
+    assert_eq!(next_event_type(&mut stream).await, "Ping");
+    assert_eq!(started.elapsed().as_secs(), 60);
+
+    event_sender
+        .send(ETLEvent {
+            event_id: None,
+            event_type: "JobCreated".to_string(),
+            entity_id: UuidScalar(Uuid::new_v4()),
+            status: Some(Status::Pending),
+            previous_status: None,
+            data: None,
+            business_group_id: None,
+        })
+        .unwrap();
+    assert_eq!(next_event_type(&mut stream).await, "JobCreated");
+    assert_eq!(next_event_type(&mut stream).await, "Ping");
+    assert_eq!(started.elapsed().as_secs(), 90);
+}
+
+#[tokio::test]
+async fn test_job_count_honors_filters() {
+    let (schema, pool) = setup_test_schema().await;
+
+    // Checkpoint far enough ahead that only the jobs inserted here match
+    let checkpoint = Utc::now() + Duration::days(3650);
+    let mut ids = Vec::new();
+    for status in ["Pending", "Failed", "Failed"] {
+        let id = Uuid::new_v4();
+        ids.push(id);
+        sqlx::query(
+            "INSERT INTO jobs (id, name, status, created_at, updated_at) VALUES ($1, $2, $3::status, NOW(), $4)",
+        )
+        .bind(id)
+        .bind(format!("testjob_{}", id))
+        .bind(status)
+        .bind(checkpoint + Duration::hours(1))
+        .execute(&pool)
+        .await
+        .expect("Failed to insert job");
+    }
+
+    let data = execute(
+        &schema,
+        "query($since: DateTimeScalar) { all: jobCount(updatedSince: $since) failed: jobCount(status: FAILED, updatedSince: $since) }",
+        json!({ "since": checkpoint.to_rfc3339() }),
+    )
+    .await;
+
+    sqlx::query("DELETE FROM jobs WHERE id = ANY($1)")
+        .bind(&ids)
+        .execute(&pool)
+        .await
+        .expect("Failed to clean up jobs");
+
+    assert_eq!(data["all"], 3);
+    assert_eq!(data["failed"], 2);
+}
+
+#[tokio::test]
+async fn test_task_count_matches_inserted_tasks() {
+    let (schema, pool) = setup_test_schema().await;
+
+    let job_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, 'Pending')")
+        .bind(job_id)
+        .bind(format!("testjob_{}", job_id))
+        .execute(&pool)
+        .await
+        .expect("Failed to insert job");
+    for i in 0..4 {
+        sqlx::query("INSERT INTO tasks (id, job_id, name, status) VALUES ($1, $2, $3, 'Pending')")
+            .bind(Uuid::new_v4())
+            .bind(job_id)
+            .bind(format!("task_{}", i))
+            .execute(&pool)
+            .await
+            .expect("Failed to insert task");
+    }
+
+    let data = execute(
+        &schema,
+        "query($id: UuidScalar!) { taskCount(jobId: $id) }",
+        json!({ "id": job_id.to_string() }),
+    )
+    .await;
+
+    assert_eq!(data["taskCount"], 4);
+}
+
+#[tokio::test]
+async fn test_create_job_writes_audit_log_entry() {
+    let (schema, pool) = setup_test_schema().await;
+
+    let data = execute(
+        &schema,
+        "mutation { createJob(name: \"audited job\") { id } }",
+        json!({}),
+    )
+    .await;
+    let job_id = Uuid::parse_str(data["createJob"]["id"].as_str().unwrap()).unwrap();
+
+    let (operation, entity_type, payload): (String, String, serde_json::Value) = sqlx::query_as(
+        "SELECT operation, entity_type, payload FROM audit_log WHERE entity_id = $1",
+    )
+    .bind(job_id)
+    .fetch_one(&pool)
+    .await
+    .expect("createJob should be audited");
+
+    assert_eq!(operation, "createJob");
+    assert_eq!(entity_type, "Job");
+    assert_eq!(payload["name"], "audited job");
+}
+
+#[tokio::test]
+async fn test_audit_log_requires_authentication() {
+    let (schema, _) = setup_test_schema().await;
+
+    let response = schema
+        .execute(format!(
+            "{{ auditLog(entityId: \"{}\") {{ id }} }}",
+            Uuid::new_v4()
+        ))
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+    let code = response.errors[0]
+        .extensions
+        .as_ref()
+        .and_then(|extensions| extensions.get("code"))
+        .cloned();
+    assert_eq!(code, Some(async_graphql::Value::from("UNAUTHENTICATED")));
+}
+
+#[tokio::test]
+async fn test_audit_log_is_scoped_to_the_entitys_tenant() {
+    let (schema, pool) = setup_test_schema().await;
+    let tenant = (Uuid::new_v4().as_u128() >> 65) as i64;
+    let user_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO users (id, username, email, created_at, updated_at) VALUES ($1, $2, $3, NOW(), NOW())",
+    )
+    .bind(user_id)
+    .bind(format!("auditor-{}", user_id.simple()))
+    .bind(format!("auditor-{}@example.com", user_id.simple()))
+    .execute(&pool)
+    .await
+    .expect("Failed to insert user");
+    let as_caller = |tenant: Option<i64>, user: Uuid, query: &str, variables: serde_json::Value| {
+        let request = Request::new(query.to_string())
+            .variables(Variables::from_json(variables))
+            .data(CurrentUser(UuidScalar(user)));
+        match tenant {
+            Some(tenant) => request.data(CurrentTenant(tenant)),
+            None => request,
+        }
+    };
+
+    let create = |query: &str, variables: serde_json::Value| {
+        let request = as_caller(Some(tenant), user_id, query, variables);
+        let schema = &schema;
+        async move {
+            let response = schema.execute(request).await;
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+            response.data.into_json().unwrap()
+        }
+    };
+    let data = create(
+        "mutation { createJob(name: \"audited tenant job\") { id } }",
+        json!({}),
+    )
+    .await;
+    let job_id = data["createJob"]["id"].as_str().unwrap().to_string();
+    let data = create(
+        "mutation($job: UuidScalar!) { createTask(jobId: $job, name: \"noop\") { id } }",
+        json!({ "job": job_id }),
+    )
+    .await;
+    let task_id = data["createTask"]["id"].as_str().unwrap().to_string();
+
+    let read = "query($id: UuidScalar!) { auditLog(entityId: $id) { operation actorUserId } }";
+    for id in [&job_id, &task_id] {
+        let response = schema
+            .execute(as_caller(Some(tenant), user_id, read, json!({ "id": id })))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        assert_eq!(data["auditLog"][0]["actorUserId"], user_id.to_string());
+
+        // Other tenants and tenant-less callers see nothing, even when authenticated
+        for other in [Some(tenant + 1), None] {
+            let response = schema
+                .execute(as_caller(other, Uuid::new_v4(), read, json!({ "id": id })))
+                .await;
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+            let data = response.data.into_json().unwrap();
+            assert_eq!(data["auditLog"], json!([]));
+        }
+    }
+
+    // User entries are only shown to the user themselves
+    let created = execute(
+        &schema,
+        "mutation($email: String!) { createUser(username: \"audited\", email: $email) { id } }",
+        json!({ "email": format!("audited-{}@example.com", user_id.simple()) }),
+    )
+    .await;
+    let created_id = Uuid::parse_str(created["createUser"]["id"].as_str().unwrap()).unwrap();
+    let user_entries =
+        |caller: Uuid| schema.execute(as_caller(None, caller, read, json!({ "id": created_id })));
+    let own = user_entries(created_id).await.data.into_json().unwrap();
+    let others = user_entries(user_id).await.data.into_json().unwrap();
+    sqlx::query("DELETE FROM users WHERE id = ANY($1)")
+        .bind(vec![created_id, user_id])
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(own["auditLog"][0]["operation"], "createUser");
+    assert_eq!(others["auditLog"], json!([]));
+}
+
+#[tokio::test]
+async fn test_update_job_changes_only_description() {
+    let (schema, pool) = setup_test_schema().await;
+
+    let job_id = Uuid::new_v4();
+    let name = format!("testjob_{}", job_id);
+    sqlx::query(
+        "INSERT INTO jobs (id, name, description, status) VALUES ($1, $2, 'before', 'Running')",
+    )
+    .bind(job_id)
+    .bind(&name)
+    .execute(&pool)
+    .await
+    .expect("Failed to insert job");
+
+    let data = execute(
+        &schema,
+        "mutation($id: UuidScalar!) { updateJob(id: $id, input: { description: \"after\" }) { name description status } }",
+        json!({ "id": job_id.to_string() }),
+    )
+    .await;
+
+    let job = &data["updateJob"];
+    assert_eq!(job["description"], "after");
+    assert_eq!(job["name"], name);
+    assert_eq!(job["status"], "RUNNING");
+}
+
+#[tokio::test]
+async fn test_update_task_changes_name_and_description_only() {
+    let (schema, pool) = setup_test_schema().await;
+
+    let job_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, 'Running')")
+        .bind(job_id)
+        .bind(format!("testjob_{}", job_id))
+        .execute(&pool)
+        .await
+        .expect("Failed to insert job");
+    let task_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO tasks (id, job_id, name, status, error_message) VALUES ($1, $2, 'before', 'Failed', 'boom')",
+    )
+    .bind(task_id)
+    .bind(job_id)
+    .execute(&pool)
+    .await
+    .expect("Failed to insert task");
+
+    let data = execute(
+        &schema,
+        "mutation($id: UuidScalar!) { updateTask(id: $id, input: { name: \"after\", description: \"renamed\" }) { name description status errorMessage } }",
+        json!({ "id": task_id.to_string() }),
+    )
+    .await;
+
+    let task = &data["updateTask"];
+    assert_eq!(task["name"], "after");
+    assert_eq!(task["description"], "renamed");
+    assert_eq!(task["status"], "FAILED");
+    assert_eq!(task["errorMessage"], "boom");
+}
+
+#[tokio::test]
+async fn test_job_progress_is_fraction_of_completed_tasks() {
+    let (schema, pool) = setup_test_schema().await;
+
+    let job_id = Uuid::new_v4();
+    let empty_job_id = Uuid::new_v4();
+    for id in [job_id, empty_job_id] {
+        sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, 'Running')")
+            .bind(id)
+            .bind(format!("testjob_{}", id))
+            .execute(&pool)
+            .await
+            .expect("Failed to insert job");
+    }
+    for status in ["Completed", "Running", "Pending", "Failed"] {
+        sqlx::query("INSERT INTO tasks (id, job_id, name, status) VALUES ($1, $2, $3, $4::status)")
+            .bind(Uuid::new_v4())
+            .bind(job_id)
+            .bind(format!("task_{}", status))
+            .bind(status)
+            .execute(&pool)
+            .await
+            .expect("Failed to insert task");
+    }
+
+    let data = execute(
+        &schema,
+        "query($a: UuidScalar!, $b: UuidScalar!) { a: job(id: $a) { progress } b: job(id: $b) { progress } }",
+        json!({ "a": job_id.to_string(), "b": empty_job_id.to_string() }),
+    )
+    .await;
+
+    assert_eq!(data["a"]["progress"], 0.25);
+    assert_eq!(data["b"]["progress"], 0.0);
+}
+
+#[tokio::test]
+async fn test_cancel_job_cancels_unfinished_tasks_and_is_noop_when_finished() {
+    let (schema, pool) = setup_test_schema().await;
+
+    let running_job = Uuid::new_v4();
+    let completed_job = Uuid::new_v4();
+    for (id, status) in [(running_job, "Running"), (completed_job, "Completed")] {
+        sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, $3::status)")
+            .bind(id)
+            .bind(format!("testjob_{}", id))
+            .bind(status)
+            .execute(&pool)
+            .await
+            .expect("Failed to insert job");
+    }
+    for status in ["Pending", "Completed"] {
+        sqlx::query("INSERT INTO tasks (id, job_id, name, status) VALUES ($1, $2, $3, $4::status)")
+            .bind(Uuid::new_v4())
+            .bind(running_job)
+            .bind(format!("task_{}", status))
+            .bind(status)
+            .execute(&pool)
+            .await
+            .expect("Failed to insert task");
+    }
+
+    let data = execute(
+        &schema,
+        "mutation($a: UuidScalar!, $b: UuidScalar!) { a: cancelJob(id: $a) { status } b: cancelJob(id: $b) { status } }",
+        json!({ "a": running_job.to_string(), "b": completed_job.to_string() }),
+    )
+    .await;
+    assert_eq!(data["a"]["status"], "CANCELLED");
+    assert_eq!(data["b"]["status"], "COMPLETED");
+
+    let data = execute(
+        &schema,
+        "query($id: UuidScalar!) { tasks(jobId: $id) { name status } }",
+        json!({ "id": running_job.to_string() }),
+    )
+    .await;
+    for task in data["tasks"].as_array().unwrap() {
+        let expected = if task["name"] == "task_Pending" {
+            "CANCELLED"
+        } else {
+            "COMPLETED"
+        };
+        assert_eq!(task["status"], expected);
+    }
+}
+
+#[tokio::test]
+async fn test_trigger_etl_directory_requires_admin() {
+    let (schema, _) = setup_test_schema().await;
+
+    let response = schema
+        .execute("mutation { triggerEtlDirectory(path: \"json\") { processed } }")
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+    let code = response.errors[0]
+        .extensions
+        .as_ref()
+        .and_then(|extensions| extensions.get("code"))
+        .cloned();
+    assert_eq!(code, Some(async_graphql::Value::from("UNAUTHENTICATED")));
+}
+
+/// Sink that records published events instead of delivering them
+#[derive(Debug, Default)]
+struct RecordingSink {
+    events: Mutex<Vec<ETLEvent>>,
+}
+
+impl EventSink for RecordingSink {
+    fn publish(&self, event: &ETLEvent) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
+
+#[tokio::test]
+async fn test_mutations_publish_through_configured_sink() {
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .expect("Failed to create test database");
+    let (sender, _) = broadcast::channel(100);
+    let sink = Arc::new(RecordingSink::default());
+    let events = EventChannels {
+        publisher: sender.clone(),
+        subscriber: sender,
+        sink: sink.clone(),
+    };
+    let schema = create_schema_with_auth(pool.clone(), events, Arc::new(NoAuth));
+
+    let data = execute(
+        &schema,
+        "mutation { createJob(name: \"sunk job\") { id } }",
+        json!({}),
+    )
+    .await;
+    let job_id = data["createJob"]["id"].as_str().unwrap().to_string();
+
+    let published = sink.events.lock().unwrap().clone();
+    assert_eq!(published.len(), 1);
+    assert_eq!(published[0].event_type, "JobCreated");
+    assert_eq!(published[0].entity_id.0.to_string(), job_id);
+
+    sqlx::query("DELETE FROM jobs WHERE id = $1")
+        .bind(Uuid::parse_str(&job_id).unwrap())
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_run_etl_directory_loads_fixture_and_rejects_traversal() {
+    let (_, pool, event_sender) = setup_test_schema_with_events().await;
+    let mut events = event_sender.subscribe();
+    let event_sink: Arc<dyn EventSink> = Arc::new(event_sender);
+
+    let root = std::env::temp_dir().join(format!("dds_etl_root_{}", Uuid::new_v4()));
+    let dir = root.join("incoming");
+    std::fs::create_dir_all(&dir).unwrap();
+    let good = format!("{}.json", Uuid::new_v4());
+    std::fs::write(dir.join(&good), r#"{"ok": true}"#).unwrap();
+    std::fs::write(dir.join(format!("{}.json", Uuid::new_v4())), "{ broken").unwrap();
+
+    let summary = super::run_etl_directory(&pool, &event_sink, &root, "incoming")
+        .await
+        .unwrap();
+    assert_eq!(summary.processed, 1);
+    assert_eq!(summary.failed, 1);
+
+    let event = loop {
+        let event = events.recv().await.unwrap();
+        if event.event_type == "EtlDirectoryProcessed" {
+            break event;
+        }
+    };
+    assert!(event.data.unwrap().contains("\"processed\":1"));
+
+    for path in ["..", "incoming/../..", "/tmp"] {
+        assert!(
+            super::run_etl_directory(&pool, &event_sink, &root, path)
+                .await
+                .is_err(),
+            "{} should be rejected",
+            path
+        );
+    }
+
+    sqlx::query("DELETE FROM json_data WHERE file_name = $1")
+        .bind(&good)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM json_data_failures WHERE file_path LIKE $1")
+        .bind(format!("{}%", root.display()))
+        .execute(&pool)
+        .await
+        .unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_job_errors_are_listed_newest_first() {
+    let (schema, pool) = setup_test_schema().await;
+
+    let job_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, 'Failed')")
+        .bind(job_id)
+        .bind(format!("testjob_{}", job_id))
+        .execute(&pool)
+        .await
+        .expect("Failed to insert job");
+    let mut conn = pool.acquire().await.unwrap();
+    for message in ["first failure", "second failure"] {
+        DbConnection::record_job_error(&mut conn, UuidScalar(job_id), message)
+            .await
+            .expect("Failed to record job error");
+    }
+
+    let data = execute(
+        &schema,
+        "query($id: UuidScalar!) { job(id: $id) { lastError all: errors { message } one: errors(limit: 1) { message } } }",
+        json!({ "id": job_id.to_string() }),
+    )
+    .await;
+
+    assert_eq!(data["job"]["lastError"], "second failure");
+    assert_eq!(
+        data["job"]["all"],
+        json!([{ "message": "second failure" }, { "message": "first failure" }])
+    );
+    assert_eq!(data["job"]["one"], json!([{ "message": "second failure" }]));
+}
+
+/// Inserts a `Running` job with one `Running` task named `flaky`, returning their IDs
+async fn insert_running_task(pool: &PgPool) -> (Uuid, Uuid) {
+    let job_id = Uuid::new_v4();
+    let task_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, 'Running')")
+        .bind(job_id)
+        .bind(format!("testjob_{}", job_id))
+        .execute(pool)
+        .await
+        .expect("Failed to insert job");
+    sqlx::query("INSERT INTO tasks (id, job_id, name, status) VALUES ($1, $2, 'flaky', 'Running')")
+        .bind(task_id)
+        .bind(job_id)
+        .execute(pool)
+        .await
+        .expect("Failed to insert task");
+    (job_id, task_id)
+}
+
+async fn job_error_messages(pool: &PgPool, job_id: Uuid) -> Vec<String> {
+    sqlx::query_scalar("SELECT message FROM job_errors WHERE job_id = $1 ORDER BY id")
+        .bind(job_id)
+        .fetch_all(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_update_task_status_records_a_job_error_once_per_failure() {
+    let (schema, pool) = setup_test_schema().await;
+    let (job_id, task_id) = insert_running_task(&pool).await;
+
+    for _ in 0..2 {
+        execute(
+            &schema,
+            "mutation($id: UuidScalar!) { updateTaskStatus(id: $id, status: FAILED) { id } }",
+            json!({ "id": task_id.to_string() }),
+        )
+        .await;
+    }
+
+    assert_eq!(
+        job_error_messages(&pool, job_id).await,
+        vec!["task flaky failed: marked Failed by updateTaskStatus"]
+    );
+}
+
+#[tokio::test]
+async fn test_update_task_records_a_job_error_once_per_failure() {
+    let (schema, pool) = setup_test_schema().await;
+    let (job_id, task_id) = insert_running_task(&pool).await;
+
+    for _ in 0..2 {
+        execute(
+            &schema,
+            r#"mutation($id: UuidScalar!) { updateTask(id: $id, input: { status: FAILED, errorMessage: "disk full" }) { id } }"#,
+            json!({ "id": task_id.to_string() }),
+        )
+        .await;
+    }
+
+    assert_eq!(
+        job_error_messages(&pool, job_id).await,
+        vec!["task flaky failed: disk full"]
+    );
+}
+
+#[tokio::test]
+async fn test_update_tasks_status_records_a_job_error_once_per_failure() {
+    let (schema, pool) = setup_test_schema().await;
+    let (job_id, task_id) = insert_running_task(&pool).await;
+
+    for _ in 0..2 {
+        execute(
+            &schema,
+            "mutation($ids: [UuidScalar!]!) { updateTasksStatus(ids: $ids, status: FAILED) { count } }",
+            json!({ "ids": [task_id.to_string()] }),
+        )
+        .await;
+    }
+
+    assert_eq!(
+        job_error_messages(&pool, job_id).await,
+        vec!["task flaky failed: marked Failed by updateTasksStatus"]
+    );
+}
+
+/// Returns the `code` extension of a response's only error
+fn only_error_code(response: &async_graphql::Response) -> Option<async_graphql::Value> {
+    assert_eq!(response.errors.len(), 1, "{:?}", response.errors);
+    response.errors[0]
+        .extensions
+        .as_ref()
+        .and_then(|extensions| extensions.get("code"))
+        .cloned()
+}
+
+#[tokio::test]
+async fn test_create_task_rejects_dependencies_outside_its_job() {
+    let (schema, pool) = setup_test_schema().await;
+    let fixture = TenantFixture::insert(&pool).await;
+    let (job_id, sibling_id) = insert_running_task(&pool).await;
+    let (_, other_job_task_id) = insert_running_task(&pool).await;
+    let create_task = "mutation($job: UuidScalar!, $dep: UuidScalar!) { createTask(jobId: $job, name: \"noop\", dependsOn: $dep) { id dependsOn } }";
+
+    for dependency in [fixture.task_id, other_job_task_id, Uuid::new_v4()] {
+        let response = schema
+            .execute(Request::new(create_task).variables(Variables::from_json(
+                json!({ "job": job_id.to_string(), "dep": dependency.to_string() }),
+            )))
+            .await;
+        assert_eq!(
+            only_error_code(&response),
+            Some(async_graphql::Value::from("BAD_USER_INPUT"))
+        );
+    }
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tasks WHERE job_id = $1")
+        .bind(job_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+
+    let data = execute(
+        &schema,
+        create_task,
+        json!({ "job": job_id.to_string(), "dep": sibling_id.to_string() }),
+    )
+    .await;
+    assert_eq!(data["createTask"]["dependsOn"], sibling_id.to_string());
+}
+
+#[tokio::test]
+async fn test_update_task_rejects_dependencies_outside_its_job() {
+    let (schema, pool) = setup_test_schema().await;
+    let fixture = TenantFixture::insert(&pool).await;
+    let (job_id, task_id) = insert_running_task(&pool).await;
+    let (_, other_job_task_id) = insert_running_task(&pool).await;
+    let sibling_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO tasks (id, job_id, name, status) VALUES ($1, $2, 'noop', 'Pending')")
+        .bind(sibling_id)
+        .bind(job_id)
+        .execute(&pool)
+        .await
+        .expect("Failed to insert task");
+    let update_task = "mutation($id: UuidScalar!, $dep: UuidScalar!) { updateTask(id: $id, input: { dependsOn: $dep }) { dependsOn } }";
+
+    for dependency in [task_id, fixture.task_id, other_job_task_id, Uuid::new_v4()] {
+        let response = schema
+            .execute(Request::new(update_task).variables(Variables::from_json(
+                json!({ "id": task_id.to_string(), "dep": dependency.to_string() }),
+            )))
+            .await;
+        assert_eq!(
+            only_error_code(&response),
+            Some(async_graphql::Value::from("BAD_USER_INPUT"))
+        );
+    }
+    let depends_on: Option<Uuid> = sqlx::query_scalar("SELECT depends_on FROM tasks WHERE id = $1")
+        .bind(task_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(depends_on, None);
+
+    let data = execute(
+        &schema,
+        update_task,
+        json!({ "id": task_id.to_string(), "dep": sibling_id.to_string() }),
+    )
+    .await;
+    assert_eq!(data["updateTask"]["dependsOn"], sibling_id.to_string());
+}
+
+#[tokio::test]
+async fn test_create_and_update_record_user_attribution() {
+    let (schema, pool) = setup_test_schema().await;
+
+    let user_id = Uuid::new_v4();
+    let username = format!("attribution_{}", user_id);
+    sqlx::query(
+        "INSERT INTO users (id, username, email, created_at, updated_at) VALUES ($1, $2, $3, NOW(), NOW())",
+    )
+    .bind(user_id)
+    .bind(&username)
+    .bind(format!("{}@example.com", user_id))
+    .execute(&pool)
+    .await
+    .expect("Failed to insert user");
+    let as_user =
+        |query: &str| Request::new(query.to_string()).data(CurrentUser(UuidScalar(user_id)));
+
+    let response = schema
+        .execute(as_user("mutation { createJob(name: \"attributed job\") { id createdBy { username } updatedBy { username } } }"))
+        .await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    assert_eq!(
+        data["createJob"]["createdBy"]["username"],
+        username.as_str()
+    );
+    assert_eq!(
+        data["createJob"]["updatedBy"]["username"],
+        username.as_str()
+    );
+    let job_id = data["createJob"]["id"].as_str().unwrap().to_string();
+
+    let response = schema
+        .execute(as_user(&format!(
+            "mutation {{ createTask(jobId: \"{}\", name: \"attributed task\") {{ createdBy {{ username }} }} }}",
+            job_id
+        )))
+        .await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    assert_eq!(
+        data["createTask"]["createdBy"]["username"],
+        username.as_str()
+    );
+
+    // Anonymous updates clear `updatedBy` but keep `createdBy`
+    let data = execute(
+        &schema,
+        "mutation($id: UuidScalar!) { updateJob(id: $id, input: { description: \"anon\" }) { createdBy { username } updatedBy { username } } }",
+        json!({ "id": job_id }),
+    )
+    .await;
+    assert_eq!(
+        data["updateJob"]["createdBy"]["username"],
+        username.as_str()
+    );
+    assert!(data["updateJob"]["updatedBy"].is_null());
+
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_create_job_rejects_non_http_callback_url() {
+    let (schema, _) = setup_test_schema().await;
+
+    let response = schema
+        .execute("mutation { createJob(name: \"hooked\", callbackUrl: \"ftp://example.com/hook\") { id } }")
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+    let code = response.errors[0]
+        .extensions
+        .as_ref()
+        .and_then(|extensions| extensions.get("code"))
+        .cloned();
+    assert_eq!(code, Some(async_graphql::Value::from("BAD_USER_INPUT")));
+
+    let data = execute(
+        &schema,
+        "mutation { createJob(name: \"hooked\", callbackUrl: \"https://example.com/hook\") { callbackUrl } }",
+        json!({}),
+    )
+    .await;
+    assert_eq!(data["createJob"]["callbackUrl"], "https://example.com/hook");
+}
+
+/// Returns the plan of `sql` with sequential and bitmap scans disabled, so it shows whether
+/// an index can produce the requested order without a separate sort
+async fn explain_without_seqscan(pool: &PgPool, sql: &str, bind: Option<Uuid>) -> String {
+    let mut tx = pool.begin().await.unwrap();
+    for setting in ["enable_seqscan", "enable_bitmapscan"] {
+        sqlx::query(&format!("SET LOCAL {} = off", setting))
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+    }
+    let explain = format!("EXPLAIN {}", sql);
+    let mut query = sqlx::query_scalar::<_, String>(&explain);
+    if let Some(bind) = bind {
+        query = query.bind(bind);
+    }
+    let plan = query.fetch_all(&mut *tx).await.unwrap().join("\n");
+    tx.rollback().await.unwrap();
+    plan
+}
+
+#[tokio::test]
+async fn test_job_and_pipeline_run_ordering_is_index_backed() {
+    let (_, pool) = setup_test_schema().await;
+
+    let plan = explain_without_seqscan(
+        &pool,
+        &format!("SELECT * FROM jobs ORDER BY {}", super::JOBS_ORDER),
+        None,
+    )
+    .await;
+    assert!(plan.contains("idx_jobs_created_id"), "{}", plan);
+    assert!(!plan.contains("Sort"), "{}", plan);
+
+    let plan =
+        explain_without_seqscan(&pool, super::PIPELINE_RUNS_BY_JOB_SQL, Some(Uuid::new_v4())).await;
+    assert!(
+        plan.contains("idx_pipeline_runs_job_created_id"),
+        "{}",
+        plan
+    );
+    assert!(!plan.contains("Sort"), "{}", plan);
+}
+
+#[tokio::test]
+async fn test_pipeline_runs_with_equal_created_at_have_stable_order() {
+    let (schema, pool) = setup_test_schema().await;
+
+    let job_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, 'Running')")
+        .bind(job_id)
+        .bind(format!("testjob_{}", job_id))
+        .execute(&pool)
+        .await
+        .expect("Failed to insert job");
+    let created_at = Utc::now();
+    let mut run_ids: Vec<Uuid> = (0..5).map(|_| Uuid::new_v4()).collect();
+    for id in &run_ids {
+        sqlx::query(
+            "INSERT INTO pipeline_runs (id, job_id, status, created_at, updated_at) VALUES ($1, $2, 'Pending', $3, $3)",
+        )
+        .bind(id)
+        .bind(job_id)
+        .bind(created_at)
+        .execute(&pool)
+        .await
+        .expect("Failed to insert pipeline run");
+    }
+    run_ids.sort_by(|a, b| b.cmp(a));
+
+    let query = "query($id: UuidScalar!) { pipelineRuns(jobId: $id) { id } }";
+    let first = execute(&schema, query, json!({ "id": job_id.to_string() })).await;
+    let second = execute(&schema, query, json!({ "id": job_id.to_string() })).await;
+
+    let expected: Vec<_> = run_ids
+        .iter()
+        .map(|id| json!({ "id": id.to_string() }))
+        .collect();
+    assert_eq!(first["pipelineRuns"], json!(expected));
+    assert_eq!(first, second);
+}
+
+#[tokio::test]
+async fn test_pipeline_runs_window_and_duration() {
+    let (schema, pool) = setup_test_schema().await;
+
+    let job_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, 'Running')")
+        .bind(job_id)
+        .bind(format!("testjob_{}", job_id))
+        .execute(&pool)
+        .await
+        .expect("Failed to insert job");
+    let base = chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let runs = [
+        (Uuid::new_v4(), base, "Completed", 1000),
+        (
+            Uuid::new_v4(),
+            base + chrono::Duration::hours(1),
+            "Completed",
+            1500,
+        ),
+        (
+            Uuid::new_v4(),
+            base + chrono::Duration::hours(2),
+            "Running",
+            0,
+        ),
+    ];
+    for (id, created_at, status, duration_ms) in &runs {
+        sqlx::query(
+            "INSERT INTO pipeline_runs (id, job_id, status, created_at, updated_at) VALUES ($1, $2, $3::status, $4, $5)",
+        )
+        .bind(id)
+        .bind(job_id)
+        .bind(status)
+        .bind(created_at)
+        .bind(*created_at + chrono::Duration::milliseconds(*duration_ms))
+        .execute(&pool)
+        .await
+        .expect("Failed to insert pipeline run");
+    }
+
+    let data = execute(
+        &schema,
+        "query($id: UuidScalar!, $since: DateTimeScalar, $until: DateTimeScalar) { pipelineRuns(jobId: $id, since: $since, until: $until) { id durationMs } }",
+        json!({
+            "id": job_id.to_string(),
+            "since": (base + chrono::Duration::hours(1)).to_rfc3339(),
+            "until": (base + chrono::Duration::hours(3)).to_rfc3339(),
+        }),
+    )
+    .await;
+    assert_eq!(
+        data["pipelineRuns"],
+        json!([
+            { "id": runs[2].0.to_string(), "durationMs": null },
+            { "id": runs[1].0.to_string(), "durationMs": 1500 },
+        ])
+    );
+
+    let response = schema
+        .execute(
+            Request::new(
+                "query($id: UuidScalar!, $since: DateTimeScalar, $until: DateTimeScalar) { pipelineRuns(jobId: $id, since: $since, until: $until) { id } }",
+            )
+            .variables(Variables::from_json(json!({
+                "id": job_id.to_string(),
+                "since": (base + chrono::Duration::hours(2)).to_rfc3339(),
+                "until": base.to_rfc3339(),
+            }))),
+        )
+        .await;
+    assert_eq!(response.errors.len(), 1);
+    let code = response.errors[0]
+        .extensions
+        .as_ref()
+        .and_then(|extensions| extensions.get("code"))
+        .cloned();
+    assert_eq!(code, Some(async_graphql::Value::from("BAD_USER_INPUT")));
+}
+
+#[tokio::test]
+async fn test_purge_json_data_requires_admin() {
+    let (schema, _) = setup_test_schema().await;
+
+    let response = schema
+        .execute("mutation { purgeJsonData(olderThanDays: 30) }")
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+    let code = response.errors[0]
+        .extensions
+        .as_ref()
+        .and_then(|extensions| extensions.get("code"))
+        .cloned();
+    assert_eq!(code, Some(async_graphql::Value::from("UNAUTHENTICATED")));
+}
+
+#[tokio::test]
+async fn test_search_users_requires_admin() {
+    let (schema, _) = setup_test_schema().await;
+
+    let response = schema
+        .execute("{ searchUsers(emailPrefix: \"a\") { id } }")
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+    let code = response.errors[0]
+        .extensions
+        .as_ref()
+        .and_then(|extensions| extensions.get("code"))
+        .cloned();
+    assert_eq!(code, Some(async_graphql::Value::from("UNAUTHENTICATED")));
+}
+
+#[tokio::test]
+async fn test_purge_json_data_deletes_only_old_rows() {
+    let (_, pool) = setup_test_schema().await;
+
+    let old_file = format!("old_{}.json", Uuid::new_v4());
+    let recent_file = format!("recent_{}.json", Uuid::new_v4());
+    for (file_name, age_days) in [(&old_file, 4000), (&recent_file, 1)] {
+        sqlx::query(
+            "INSERT INTO json_data (file_name, data, created_at) VALUES ($1, '{}', NOW() - make_interval(days => $2))",
+        )
+        .bind(file_name)
+        .bind(age_days)
+        .execute(&pool)
+        .await
+        .expect("Failed to insert json_data row");
+    }
+
+    assert!(super::purge_json_data(&pool, 0).await.is_err());
+
+    let deleted = super::purge_json_data(&pool, 3650).await.unwrap();
+    assert!(deleted >= 1);
+
+    let remaining: Vec<String> =
+        sqlx::query_scalar("SELECT file_name FROM json_data WHERE file_name = ANY($1)")
+            .bind(vec![old_file.clone(), recent_file.clone()])
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(remaining, vec![recent_file.clone()]);
+
+    sqlx::query("DELETE FROM json_data WHERE file_name = $1")
+        .bind(&recent_file)
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_health_reports_pool_status() {
+    let (schema, pool) = setup_test_schema().await;
+
+    let query = "{ health { status poolHealthy dbLatencyMs uptimeSecs } }";
+    let data = execute(&schema, query, json!({})).await;
+    assert_eq!(data["health"]["poolHealthy"], true);
+    assert!(data["health"]["dbLatencyMs"].is_i64(), "{}", data);
+    assert!(data["health"]["uptimeSecs"].as_i64().unwrap() >= 0);
+    assert!(
+        matches!(data["health"]["status"].as_str(), Some("ok" | "degraded")),
+        "{}",
+        data
+    );
+
+    pool.close().await;
+    let data = execute(&schema, query, json!({})).await;
+    assert_eq!(data["health"]["poolHealthy"], false);
+    assert_eq!(data["health"]["status"], "down");
+    assert!(data["health"]["dbLatencyMs"].is_null());
+}
+
+#[tokio::test]
+async fn test_tenants_cannot_read_each_others_jobs() {
+    let (schema, _) = setup_test_schema().await;
+    let tenant_a = (Uuid::new_v4().as_u128() >> 65) as i64;
+    let tenant_b = tenant_a + 1;
+    let as_tenant = |tenant: i64, query: &str, variables: serde_json::Value| {
+        Request::new(query.to_string())
+            .variables(Variables::from_json(variables))
+            .data(CurrentTenant(tenant))
+    };
+
+    let mut job_ids = Vec::new();
+    for tenant in [tenant_a, tenant_b] {
+        let response = schema
+            .execute(as_tenant(
+                tenant,
+                "mutation { createJobWithTasks(name: \"tenant job\", tasks: [{ name: \"noop\" }]) { id } }",
+                json!({}),
+            ))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        job_ids.push(
+            data["createJobWithTasks"]["id"]
+                .as_str()
+                .unwrap()
+                .to_string(),
+        );
+    }
+    let (job_a, job_b) = (&job_ids[0], &job_ids[1]);
+
+    let read = r#"
+        query($id: UuidScalar!) {
+            jobs { items { id } }
+            job(id: $id) { id }
+            jobDetail(id: $id) { job { id } }
+            tasks(jobId: $id) { id }
+            taskCount(jobId: $id)
+            allTasks { jobId }
+        }
+    "#;
+
+    // Tenant A sees its own job and its tasks...
+    let response = schema
+        .execute(as_tenant(tenant_a, read, json!({ "id": job_a })))
+        .await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    let job_list: Vec<&str> = data["jobs"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|job| job["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(job_list, vec![job_a.as_str()]);
+    assert_eq!(data["job"]["id"], job_a.as_str());
+    assert_eq!(data["jobDetail"]["job"]["id"], job_a.as_str());
+    assert_eq!(data["tasks"].as_array().unwrap().len(), 1);
+    assert_eq!(data["taskCount"], 1);
+    assert!(data["allTasks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|task| task["jobId"] == job_a.as_str()));
+
+    // ...but nothing of tenant B's
+    let response = schema
+        .execute(as_tenant(tenant_a, read, json!({ "id": job_b })))
+        .await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    assert!(data["job"].is_null());
+    assert!(data["jobDetail"].is_null());
+    assert_eq!(data["tasks"], json!([]));
+    assert_eq!(data["taskCount"], 0);
+
+    // Requests without a tenant only see global jobs
+    let data = execute(&schema, read, json!({ "id": job_a })).await;
+    assert!(data["job"].is_null());
+    assert!(data["jobs"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|job| job["id"] != job_a.as_str() && job["id"] != job_b.as_str()));
+}
+
+#[tokio::test]
+async fn test_update_job_status_rejects_illegal_transition() {
+    let (schema, pool) = setup_test_schema().await;
+
+    let job_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, 'Completed')")
+        .bind(job_id)
+        .bind(format!("testjob_{}", job_id))
+        .execute(&pool)
+        .await
+        .expect("Failed to insert job");
+
+    for mutation in [
+        "mutation($id: UuidScalar!) { updateJobStatus(id: $id, status: PENDING) { id } }",
+        "mutation($id: UuidScalar!) { updateJob(id: $id, input: { status: PENDING }) { id } }",
+    ] {
+        let response = schema
+            .execute(
+                Request::new(mutation)
+                    .variables(Variables::from_json(json!({ "id": job_id.to_string() }))),
+            )
+            .await;
+        assert_eq!(response.errors.len(), 1, "{}", mutation);
+        let code = response.errors[0]
+            .extensions
+            .as_ref()
+            .and_then(|extensions| extensions.get("code"))
+            .cloned();
+        assert_eq!(code, Some(async_graphql::Value::from("BAD_USER_INPUT")));
+    }
+
+    let status: Status = sqlx::query_scalar("SELECT status FROM jobs WHERE id = $1")
+        .bind(job_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(status, Status::Completed);
+}
+
+#[tokio::test]
+async fn test_run_job_only_starts_pending_jobs() {
+    let (schema, pool) = setup_test_schema().await;
+
+    for (status, expected_code) in [
+        ("Completed", "BAD_USER_INPUT"),
+        ("Failed", "BAD_USER_INPUT"),
+        ("Cancelled", "BAD_USER_INPUT"),
+        ("Running", "CONFLICT"),
+    ] {
+        let job_id = Uuid::new_v4();
+        sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, $3::status)")
+            .bind(job_id)
+            .bind(format!("testjob_{}", job_id))
+            .bind(status)
+            .execute(&pool)
+            .await
+            .expect("Failed to insert job");
+
+        let response = schema
+            .execute(
+                Request::new("mutation($id: UuidScalar!) { runJob(id: $id) { id } }")
+                    .variables(Variables::from_json(json!({ "id": job_id.to_string() }))),
+            )
+            .await;
+        assert_eq!(response.errors.len(), 1, "{}", status);
+        let code = response.errors[0]
+            .extensions
+            .as_ref()
+            .and_then(|extensions| extensions.get("code"))
+            .cloned();
+        assert_eq!(
+            code,
+            Some(async_graphql::Value::from(expected_code)),
+            "{}",
+            status
+        );
+
+        let current: String = sqlx::query_scalar("SELECT status::text FROM jobs WHERE id = $1")
+            .bind(job_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(current, status);
+    }
+}
+
+#[tokio::test]
+async fn test_update_pipeline_run_status_rejects_illegal_transition() {
+    let (schema, pool) = setup_test_schema().await;
+
+    let job_id = Uuid::new_v4();
+    let run_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, 'Completed')")
+        .bind(job_id)
+        .bind(format!("testjob_{}", job_id))
+        .execute(&pool)
+        .await
+        .expect("Failed to insert job");
+    sqlx::query("INSERT INTO pipeline_runs (id, job_id, status) VALUES ($1, $2, 'Completed')")
+        .bind(run_id)
+        .bind(job_id)
+        .execute(&pool)
+        .await
+        .expect("Failed to insert pipeline run");
+
+    let response = schema
+        .execute(
+            Request::new(
+                "mutation($id: UuidScalar!) { updatePipelineRunStatus(id: $id, status: RUNNING) { id } }",
+            )
+            .variables(Variables::from_json(json!({ "id": run_id.to_string() }))),
+        )
+        .await;
+    assert_eq!(response.errors.len(), 1);
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(
+        extensions.get("code").cloned(),
+        Some(async_graphql::Value::from("BAD_USER_INPUT"))
+    );
+    assert_eq!(
+        extensions.get("from").cloned(),
+        Some(async_graphql::Value::from("Completed"))
+    );
+
+    let status: Status = sqlx::query_scalar("SELECT status FROM pipeline_runs WHERE id = $1")
+        .bind(run_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(status, Status::Completed);
+}
+
+#[tokio::test]
+async fn test_create_task_validates_input_against_kind_schema() {
+    let (schema, pool) = setup_test_schema().await;
+
+    let job_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, 'Pending')")
+        .bind(job_id)
+        .bind(format!("testjob_{}", job_id))
+        .execute(&pool)
+        .await
+        .expect("Failed to insert job");
+    let mutation = "mutation($jobId: UuidScalar!, $input: JSON) { createTask(jobId: $jobId, name: \"http_fetch\", inputData: $input) { id } }";
+
+    execute(
+        &schema,
+        mutation,
+        json!({ "jobId": job_id.to_string(), "input": { "url": "http://example.com" } }),
+    )
+    .await;
+
+    let response = schema
+        .execute(Request::new(mutation).variables(Variables::from_json(
+            json!({ "jobId": job_id.to_string(), "input": { "url": 5 } }),
+        )))
+        .await;
+    assert_eq!(response.errors.len(), 1);
+    assert!(
+        response.errors[0].message.contains("/url"),
+        "{}",
+        response.errors[0].message
+    );
+    let extensions = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(
+        extensions.get("code").cloned(),
+        Some(async_graphql::Value::from("BAD_USER_INPUT"))
+    );
+    assert_eq!(
+        extensions.get("path").cloned(),
+        Some(async_graphql::Value::from("/url"))
+    );
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tasks WHERE job_id = $1")
+        .bind(job_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+}
+
+/// Returns the ID and event ID of the next `etlEvents` event about one of `entity_ids`
+async fn next_own_event(
+    stream: &mut (impl futures::Stream<Item = async_graphql::Response> + Unpin),
+    entity_ids: &[Uuid],
+) -> (Uuid, i64) {
+    loop {
+        let response = tokio::time::timeout(std::time::Duration::from_secs(5), stream.next())
+            .await
+            .expect("no event received")
+            .expect("subscription ended");
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let event = response.data.into_json().unwrap()["etlEvents"].clone();
+        let entity_id = Uuid::parse_str(event["entityId"].as_str().unwrap()).unwrap();
+        if entity_ids.contains(&entity_id) {
+            return (entity_id, event["eventId"].as_i64().unwrap());
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_etl_events_replays_persisted_events_then_goes_live() {
+    let (_, pool) = setup_test_schema().await;
+    let events = EventChannels::persisted(&pool, 100);
+    let publisher = events.publisher.clone();
+    let subscriber = events.subscriber.clone();
+    let schema = create_schema_with_auth(pool.clone(), events, Arc::new(NoAuth));
+
+    let entity_ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+    let event = |entity_id: Uuid| ETLEvent {
+        event_id: None,
+        event_type: "TestEvent".to_string(),
+        entity_id: UuidScalar(entity_id),
+        status: None,
+        previous_status: None,
+        data: None,
+        business_group_id: None,
+    };
+
+    // Publish two events before subscribing and wait until they are stored
+    let mut delivered = subscriber.subscribe();
+    let mut first_event_id = None;
+    for entity_id in &entity_ids[..2] {
+        publisher.send(event(*entity_id)).unwrap();
+        loop {
+            let received = delivered.recv().await.unwrap();
+            if received.entity_id.0 == *entity_id {
+                first_event_id = first_event_id.or(received.event_id);
+                break;
+            }
+        }
+    }
+    let since = first_event_id.expect("stored events carry their ID") - 1;
+
+    let mut stream = schema.execute_stream(
+        Request::new(
+            "subscription($since: Int) { etlEvents(sinceEventId: $since) { eventId entityId } }",
+        )
+        .variables(Variables::from_json(json!({ "since": since }))),
+    );
+
+    let (replayed_first, first_id) = next_own_event(&mut stream, &entity_ids).await;
+    let (replayed_second, second_id) = next_own_event(&mut stream, &entity_ids).await;
+    assert_eq!(replayed_first, entity_ids[0]);
+    assert_eq!(replayed_second, entity_ids[1]);
+
+    // The subscription is now live; the replayed events are not delivered twice
+    publisher.send(event(entity_ids[2])).unwrap();
+    let (live, live_id) = next_own_event(&mut stream, &entity_ids).await;
+    assert_eq!(live, entity_ids[2]);
+    assert!(first_id < second_id && second_id < live_id);
+}
+
+#[tokio::test]
+async fn test_etl_events_skip_other_tenants_events() {
+    let (_, pool) = setup_test_schema().await;
+    let events = EventChannels::persisted(&pool, 100);
+    let publisher = events.publisher.clone();
+    let subscriber = events.subscriber.clone();
+    let schema = create_schema_with_auth(pool.clone(), events, Arc::new(NoAuth));
+
+    let tenant_a = (Uuid::new_v4().as_u128() >> 65) as i64;
+    let tenant_b = tenant_a + 1;
+    let entity_ids: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+    let event = |entity_id: Uuid, tenant: i64| ETLEvent {
+        event_id: None,
+        event_type: "TestEvent".to_string(),
+        entity_id: UuidScalar(entity_id),
+        status: None,
+        previous_status: None,
+        data: None,
+        business_group_id: Some(tenant),
+    };
+
+    // Store one event of each tenant before subscribing
+    let mut delivered = subscriber.subscribe();
+    let mut first_event_id = None;
+    for (entity_id, tenant) in [(entity_ids[0], tenant_a), (entity_ids[1], tenant_b)] {
+        publisher.send(event(entity_id, tenant)).unwrap();
+        loop {
+            let received = delivered.recv().await.unwrap();
+            if received.entity_id.0 == entity_id {
+                first_event_id = first_event_id.or(received.event_id);
+                break;
+            }
+        }
+    }
+    let since = first_event_id.expect("stored events carry their ID") - 1;
+
+    let mut stream = schema.execute_stream(
+        Request::new(
+            "subscription($since: Int) { etlEvents(sinceEventId: $since) { eventId entityId } }",
+        )
+        .variables(Variables::from_json(json!({ "since": since })))
+        .data(CurrentTenant(tenant_b)),
+    );
+
+    // Only tenant B's stored event is replayed, and only its live event is delivered
+    let (replayed, _) = next_own_event(&mut stream, &entity_ids).await;
+    assert_eq!(replayed, entity_ids[1]);
+    publisher.send(event(entity_ids[2], tenant_a)).unwrap();
+    publisher.send(event(entity_ids[3], tenant_b)).unwrap();
+    let (live, _) = next_own_event(&mut stream, &entity_ids).await;
+    assert_eq!(live, entity_ids[3]);
+}
+
+fn fan_out_event(index: usize, data: Option<Arc<str>>) -> ETLEvent {
+    ETLEvent {
+        event_id: None,
+        event_type: format!("FanOut{}", index),
+        entity_id: UuidScalar(Uuid::nil()),
+        status: None,
+        previous_status: None,
+        data,
+        business_group_id: None,
+    }
+}
+
+#[tokio::test]
+async fn test_etl_events_fan_out_to_many_subscribers() {
+    const SUBSCRIBERS: usize = 100;
+    const EVENTS: usize = 1000;
+
+    let (_, pool) = setup_test_schema().await;
+    let (event_sender, _) = broadcast::channel(EVENTS);
+    let schema = create_schema_with_auth(pool, event_sender.clone(), Arc::new(NoAuth));
+
+    let mut streams: Vec<_> = (0..SUBSCRIBERS)
+        .map(|_| schema.execute_stream("subscription { etlEvents { eventType } }"))
+        .collect();
+    // Subscriptions attach to the channel on their first poll
+    for stream in &mut streams {
+        let _ = tokio::time::timeout(std::time::Duration::from_millis(1), stream.next()).await;
+    }
+    assert_eq!(event_sender.receiver_count(), SUBSCRIBERS);
+
+    let payload: Arc<str> = Arc::from(r#"{"rows":1}"#);
+    let mut raw = event_sender.subscribe();
+    for index in 0..EVENTS {
+        event_sender
+            .send(fan_out_event(index, Some(payload.clone())))
+            .unwrap();
+    }
+    // Every receiver shares the publisher's serialized payload
+    let shared = raw.recv().await.unwrap().data.unwrap();
+    assert!(Arc::ptr_eq(&shared, &payload));
+
+    for stream in &mut streams {
+        for index in 0..EVENTS {
+            let response = tokio::time::timeout(std::time::Duration::from_secs(5), stream.next())
+                .await
+                .expect("no event received")
+                .expect("subscription ended");
+            let event = response.data.into_json().unwrap();
+            assert_eq!(
+                event["etlEvents"]["eventType"],
+                json!(format!("FanOut{}", index))
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_lagging_etl_events_subscriber_skips_ahead() {
+    let (_, pool) = setup_test_schema().await;
+    let (event_sender, _) = broadcast::channel(4);
+    let schema = create_schema_with_auth(pool, event_sender.clone(), Arc::new(NoAuth));
+
+    let mut stream = schema.execute_stream("subscription { etlEvents { eventType } }");
+    let _ = tokio::time::timeout(std::time::Duration::from_millis(1), stream.next()).await;
+    assert_eq!(event_sender.receiver_count(), 1);
+
+    // Overflow the buffer before the subscriber reads anything
+    for index in 0..10 {
+        event_sender.send(fan_out_event(index, None)).unwrap();
+    }
+
+    let mut received = Vec::new();
+    for _ in 0..4 {
+        let response = tokio::time::timeout(std::time::Duration::from_secs(5), stream.next())
+            .await
+            .expect("no event received")
+            .expect("subscription ended");
+        received.push(response.data.into_json().unwrap()["etlEvents"]["eventType"].clone());
+    }
+    assert_eq!(
+        received,
+        vec![
+            json!("FanOut6"),
+            json!("FanOut7"),
+            json!("FanOut8"),
+            json!("FanOut9")
+        ]
+    );
+
+    // The subscription stays live after lagging
+    event_sender.send(fan_out_event(10, None)).unwrap();
+    let response = tokio::time::timeout(std::time::Duration::from_secs(5), stream.next())
+        .await
+        .expect("no event received")
+        .expect("subscription ended");
+    assert_eq!(
+        response.data.into_json().unwrap()["etlEvents"]["eventType"],
+        json!("FanOut10")
+    );
+}
+
+#[tokio::test]
+async fn test_search_finds_task_by_name_term() {
+    let (schema, _) = setup_test_schema().await;
+    let term = format!("reconcile{}", Uuid::new_v4().simple());
+
+    let data = execute(
+        &schema,
+        "mutation { createJob(name: \"search job\") { id } }",
+        json!({}),
+    )
+    .await;
+    let job_id = data["createJob"]["id"].as_str().unwrap().to_string();
+    let data = execute(
+        &schema,
+        "mutation($jobId: UuidScalar!, $name: String!) { createTask(jobId: $jobId, name: $name) { id } }",
+        json!({ "jobId": job_id, "name": format!("nightly {} ledger", term) }),
+    )
+    .await;
+    let task_id = data["createTask"]["id"].clone();
+
+    let data = execute(
+        &schema,
+        "query($q: String!) { search(query: $q, limit: 100) { snippet rank item { __typename ... on Task { id } ... on Job { id } } } }",
+        json!({ "q": term }),
+    )
+    .await;
+    let results = data["search"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["item"]["__typename"], json!("Task"));
+    assert_eq!(results[0]["item"]["id"], task_id);
+    assert_eq!(
+        results[0]["snippet"],
+        json!(format!("nightly <b>{}</b> ledger", term))
+    );
+    assert!(results[0]["rank"].as_f64().unwrap() > 0.0);
+}
+
+#[tokio::test]
+async fn test_jobs_page_total_count_matches_unpaginated_count() {
+    let (schema, _) = setup_test_schema().await;
+    let tenant = (Uuid::new_v4().as_u128() >> 65) as i64;
+    let as_tenant = |query: &str, variables: serde_json::Value| {
+        Request::new(query.to_string())
+            .variables(Variables::from_json(variables))
+            .data(CurrentTenant(tenant))
+    };
+
+    for i in 0..5 {
+        let response = schema
+            .execute(as_tenant(
+                "mutation($name: String!) { createJob(name: $name) { id } }",
+                json!({ "name": format!("paged job {}", i) }),
+            ))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+    }
+
+    let read = "query($limit: Int, $offset: Int) { jobCount jobs(limit: $limit, offset: $offset) { totalCount items { id } } }";
+    for (limit, offset, page_len) in [(2, 0, 2), (2, 4, 1), (2, 10, 0), (100, 0, 5)] {
+        let response = schema
+            .execute(as_tenant(read, json!({ "limit": limit, "offset": offset })))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        assert_eq!(data["jobCount"], 5);
+        assert_eq!(data["jobs"]["totalCount"], 5, "offset {}", offset);
+        assert_eq!(
+            data["jobs"]["items"].as_array().unwrap().len(),
+            page_len,
+            "offset {}",
+            offset
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_recent_activity_interleaves_kinds_by_update_time() {
+    let (schema, pool) = setup_test_schema().await;
+    let tenant = (Uuid::new_v4().as_u128() >> 65) as i64;
+    let now = Utc::now();
+    let minutes_ago = |minutes: i64| now - Duration::minutes(minutes);
+
+    let job_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO jobs (id, name, status, business_group_id, updated_at) VALUES ($1, 'activity job', 'Pending', $2, $3)",
+    )
+    .bind(job_id)
+    .bind(tenant)
+    .bind(minutes_ago(3))
+    .execute(&pool)
+    .await
+    .unwrap();
+    let (new_task, old_task, run) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    for (task_id, minutes) in [(new_task, 1), (old_task, 4)] {
+        sqlx::query(
+            "INSERT INTO tasks (id, job_id, name, status, updated_at) VALUES ($1, $2, 'activity task', 'Pending', $3)",
+        )
+        .bind(task_id)
+        .bind(job_id)
+        .bind(minutes_ago(minutes))
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+    sqlx::query(
+        "INSERT INTO pipeline_runs (id, job_id, status, updated_at) VALUES ($1, $2, 'Running', $3)",
+    )
+    .bind(run)
+    .bind(job_id)
+    .bind(minutes_ago(2))
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let read = |limit: i32| {
+        Request::new(
+            "query($limit: Int) { recentActivity(limit: $limit) { __typename ... on Job { id } ... on Task { id } ... on PipelineRun { id status } } }",
+        )
+        .variables(Variables::from_json(json!({ "limit": limit })))
+        .data(CurrentTenant(tenant))
+    };
+    let response = schema.execute(read(10)).await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    let items: Vec<(String, String)> = data["recentActivity"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| {
+            (
+                item["__typename"].as_str().unwrap().to_string(),
+                item["id"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        items,
+        vec![
+            ("Task".to_string(), new_task.to_string()),
+            ("PipelineRun".to_string(), run.to_string()),
+            ("Job".to_string(), job_id.to_string()),
+            ("Task".to_string(), old_task.to_string()),
+        ]
+    );
+    assert_eq!(data["recentActivity"][1]["status"], "RUNNING");
+
+    let response = schema.execute(read(2)).await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    assert_eq!(data["recentActivity"].as_array().unwrap().len(), 2);
+
+    sqlx::query("DELETE FROM jobs WHERE id = $1")
+        .bind(job_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_status_only_mutations_are_deprecated() {
+    let (schema, _) = setup_test_schema().await;
+
+    let data = execute(
+        &schema,
+        "{ __type(name: \"Mutation\") { fields(includeDeprecated: true) { name isDeprecated deprecationReason } } }",
+        json!({}),
+    )
+    .await;
+
+    let fields = data["__type"]["fields"].as_array().unwrap();
+    let field = |name: &str| {
+        fields
+            .iter()
+            .find(|field| field["name"] == name)
+            .unwrap_or_else(|| panic!("no field {}", name))
+            .clone()
+    };
+    assert_eq!(field("updateJobStatus")["isDeprecated"], json!(true));
+    assert_eq!(
+        field("updateJobStatus")["deprecationReason"],
+        json!("use updateJob")
+    );
+    assert_eq!(field("updateTaskStatus")["isDeprecated"], json!(true));
+    assert_eq!(
+        field("updateTaskStatus")["deprecationReason"],
+        json!("use updateTask")
+    );
+    assert_eq!(field("updateJob")["isDeprecated"], json!(false));
+}
+
+/// Auth provider for tests that only accepts `KnownToken::TOKEN`
+struct KnownToken;
+
+impl KnownToken {
+    const TOKEN: &'static str = "known-token";
+}
+
+#[async_trait]
+impl AuthProvider for KnownToken {
+    async fn login(
+        &self,
+        _email: String,
+        _password: String,
+    ) -> async_graphql::Result<AuthResponse> {
+        Err(async_graphql::Error::new("login disabled in tests"))
+    }
+
+    async fn validate_token(&self, token: &str) -> async_graphql::Result<TokenClaims> {
+        if token != Self::TOKEN {
+            return Err(async_graphql::Error::new("InvalidSignature"));
+        }
+        Ok(TokenClaims {
+            sub: "auth0|debug-user".to_string(),
+            exp: 2_000_000_000,
+            iat: 1_700_000_000,
+            iss: Some("https://issuer.example.com/".to_string()),
+            aud: None,
+            email: Some("debug@example.com".to_string()),
+            business_group_id: None,
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_debug_token_decodes_the_request_token() {
+    let (_, pool) = setup_test_schema().await;
+    let (event_sender, _) = broadcast::channel(100);
+    let schema = create_schema_with_auth(pool, event_sender, Arc::new(KnownToken));
+    let query = "{ debugToken { sub exp iss aud email } }";
+
+    let response = schema
+        .execute(Request::new(query).data(BearerToken(KnownToken::TOKEN.to_string())))
+        .await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    assert_eq!(
+        response.data.into_json().unwrap()["debugToken"],
+        json!({
+            "sub": "auth0|debug-user",
+            "exp": 2_000_000_000,
+            "iss": "https://issuer.example.com/",
+            "aud": null,
+            "email": "debug@example.com",
+        })
+    );
+
+    // Validation failures explain themselves
+    let response = schema
+        .execute(Request::new(query).data(BearerToken("forged".to_string())))
+        .await;
+    assert_eq!(response.errors.len(), 1);
+    assert!(
+        response.errors[0].message.contains("InvalidSignature"),
+        "{}",
+        response.errors[0].message
+    );
+
+    let response = schema.execute(query).await;
+    assert_eq!(response.errors.len(), 1);
+    assert!(response.errors[0].message.contains("No Authorization"));
+}
+
+#[tokio::test]
+async fn test_update_tasks_status_updates_in_bulk_and_skips_illegal_moves() {
+    let (schema, pool, event_sender) = setup_test_schema_with_events().await;
+    let mut events = event_sender.subscribe();
+
+    let job_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, 'Running')")
+        .bind(job_id)
+        .bind(format!("testjob_{}", job_id))
+        .execute(&pool)
+        .await
+        .expect("Failed to insert job");
+    let mut running = Vec::new();
+    for i in 0..3 {
+        let task_id = Uuid::new_v4();
+        sqlx::query("INSERT INTO tasks (id, job_id, name, status) VALUES ($1, $2, $3, 'Running')")
+            .bind(task_id)
+            .bind(job_id)
+            .bind(format!("task_{}", i))
+            .execute(&pool)
+            .await
+            .expect("Failed to insert task");
+        running.push(task_id);
+    }
+    let completed = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO tasks (id, job_id, name, status) VALUES ($1, $2, 'done', 'Completed')",
+    )
+    .bind(completed)
+    .bind(job_id)
+    .execute(&pool)
+    .await
+    .expect("Failed to insert task");
+
+    let mut ids: Vec<String> = running.iter().map(Uuid::to_string).collect();
+    ids.push(completed.to_string());
+    let data = execute(
+        &schema,
+        "mutation($ids: [UuidScalar!]!) { updateTasksStatus(ids: $ids, status: FAILED) { count skipped { id status } } }",
+        json!({ "ids": ids }),
+    )
+    .await;
+
+    assert_eq!(data["updateTasksStatus"]["count"], json!(3));
+    assert_eq!(
+        data["updateTasksStatus"]["skipped"],
+        json!([{ "id": completed.to_string(), "status": "COMPLETED" }])
+    );
+    let failed: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM tasks WHERE job_id = $1 AND status = 'Failed'")
+            .bind(job_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(failed, 3);
+
+    // One event for the whole batch
+    let event = events.recv().await.unwrap();
+    assert_eq!(event.event_type, "TasksStatusUpdated");
+    assert!(events.try_recv().is_err());
+
+    sqlx::query("DELETE FROM jobs WHERE id = $1")
+        .bind(job_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_ingest_history_lists_loaded_file() {
+    let (schema, pool) = setup_test_schema().await;
+
+    let dir = std::env::temp_dir().join(format!("dds_ingest_{}", Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file_name = format!("history_{}.json", Uuid::new_v4());
+    std::fs::write(dir.join(&file_name), r#"{"id":1}"#).unwrap();
+    crate::etl::ETLPipeline::new(pool.clone())
+        .process_file(&dir.join(&file_name))
+        .await
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let data = execute(
+        &schema,
+        "query($fileName: String) { ingestHistory(fileName: $fileName, limit: 5) { fileName ingestedAt byteSize } }",
+        json!({ "fileName": file_name }),
+    )
+    .await;
+
+    sqlx::query("DELETE FROM json_data WHERE file_name = $1")
+        .bind(&file_name)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let history = data["ingestHistory"].as_array().unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0]["fileName"], file_name);
+    // Stored as jsonb, which prints `{"id": 1}`
+    assert_eq!(history[0]["byteSize"], 9);
+    assert!(history[0]["ingestedAt"].is_string());
+}
+
+#[tokio::test]
+async fn test_task_job_resolves_parent_job() {
+    let (schema, pool) = setup_test_schema().await;
+
+    let data = execute(
+        &schema,
+        r#"mutation { createJobWithTasks(name: "parent", tasks: [{ name: "a" }, { name: "b" }]) { id } }"#,
+        json!({}),
+    )
+    .await;
+    let job_id = data["createJobWithTasks"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let data = execute(
+        &schema,
+        "query($id: UuidScalar!) { tasks(jobId: $id) { name job { id name } } }",
+        json!({ "id": job_id }),
+    )
+    .await;
+
+    sqlx::query("DELETE FROM jobs WHERE id = $1::uuid")
+        .bind(&job_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let tasks = data["tasks"].as_array().unwrap();
+    assert_eq!(tasks.len(), 2);
+    for task in tasks {
+        assert_eq!(task["job"]["id"], job_id);
+        assert_eq!(task["job"]["name"], "parent");
+    }
+}
+
+#[tokio::test]
+async fn test_start_pipeline_run_executes_job_to_completion() {
+    let (schema, pool, events) = setup_test_schema_with_events().await;
+    let mut events = events.subscribe();
+
+    let data = execute(
+        &schema,
+        r#"mutation { createJobWithTasks(name: "pipeline", tasks: [{ name: "noop" }, { name: "noop" }]) { id } }"#,
+        json!({}),
+    )
+    .await;
+    let job_id = data["createJobWithTasks"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let data = execute(
+        &schema,
+        "mutation($jobId: UuidScalar!) { startPipelineRun(jobId: $jobId) { id status } }",
+        json!({ "jobId": job_id }),
+    )
+    .await;
+    assert_eq!(data["startPipelineRun"]["status"], "RUNNING");
+    let run_id: Uuid = data["startPipelineRun"]["id"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+
+    let finished = tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        loop {
+            let event = events.recv().await.unwrap();
+            if event.event_type == "PipelineRunStatusUpdated" && event.entity_id.0 == run_id {
+                break event;
+            }
+        }
+    })
+    .await
+    .expect("pipeline run should finish");
+    assert_eq!(finished.status, Some(Status::Completed));
+
+    let (status, metrics): (Status, serde_json::Value) =
+        sqlx::query_as("SELECT status, metrics FROM pipeline_runs WHERE id = $1")
+            .bind(run_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    let job_status: Status = sqlx::query_scalar("SELECT status FROM jobs WHERE id = $1::uuid")
+        .bind(&job_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM jobs WHERE id = $1::uuid")
+        .bind(&job_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(status, Status::Completed);
+    assert_eq!(job_status, Status::Completed);
+    assert_eq!(metrics["rowsProcessed"], 2);
+    assert_eq!(metrics["rowsFailed"], 0);
+    assert!(metrics["durationMs"].is_i64());
+}
+
+/// A `Pending` job of a fresh tenant, with one `Pending` task and pipeline run, for
+/// checking that other tenants can't mutate them
+struct TenantFixture {
+    job_id: Uuid,
+    task_id: Uuid,
+    run_id: Uuid,
+    other_tenant: i64,
+}
+
+impl TenantFixture {
+    async fn insert(pool: &PgPool) -> Self {
+        let tenant = (Uuid::new_v4().as_u128() >> 65) as i64;
+        let (job_id, task_id, run_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        sqlx::query(
+            "INSERT INTO jobs (id, name, status, business_group_id) VALUES ($1, 'tenant fixture', 'Pending', $2)",
+        )
+        .bind(job_id)
+        .bind(tenant)
+        .execute(pool)
+        .await
+        .expect("Failed to insert job");
+        sqlx::query(
+            "INSERT INTO tasks (id, job_id, name, status) VALUES ($1, $2, 'noop', 'Pending')",
+        )
+        .bind(task_id)
+        .bind(job_id)
+        .execute(pool)
+        .await
+        .expect("Failed to insert task");
+        sqlx::query("INSERT INTO pipeline_runs (id, job_id, status) VALUES ($1, $2, 'Pending')")
+            .bind(run_id)
+            .bind(job_id)
+            .execute(pool)
+            .await
+            .expect("Failed to insert pipeline run");
+        Self {
+            job_id,
+            task_id,
+            run_id,
+            other_tenant: tenant + 1,
+        }
+    }
+
+    /// Runs `query` as a different tenant than the fixture's
+    async fn execute_as_other_tenant(
+        &self,
+        schema: &Schema<Query, Mutation, Subscription>,
+        query: &str,
+    ) -> async_graphql::Response {
+        let variables = json!({
+            "job": self.job_id.to_string(),
+            "task": self.task_id.to_string(),
+            "run": self.run_id.to_string(),
+        });
+        schema
+            .execute(
+                Request::new(query)
+                    .variables(Variables::from_json(variables))
+                    .data(CurrentTenant(self.other_tenant)),
+            )
+            .await
+    }
+
+    /// Asserts the job, task and pipeline run are all still `Pending` and that the job
+    /// gained no tasks or pipeline runs
+    async fn assert_untouched(&self, pool: &PgPool) {
+        let (job, task, run, tasks, runs): (Status, Status, Status, i64, i64) = sqlx::query_as(
+            r#"
+            SELECT j.status, t.status, r.status,
+                   (SELECT COUNT(*) FROM tasks WHERE job_id = j.id),
+                   (SELECT COUNT(*) FROM pipeline_runs WHERE job_id = j.id)
+            FROM jobs j, tasks t, pipeline_runs r
+            WHERE j.id = $1 AND t.id = $2 AND r.id = $3
+            "#,
+        )
+        .bind(self.job_id)
+        .bind(self.task_id)
+        .bind(self.run_id)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            (job, task, run, tasks, runs),
+            (Status::Pending, Status::Pending, Status::Pending, 1, 1)
+        );
+    }
+}
+
+/// Asserts a cross-tenant mutation returned null for `field` without errors
+fn assert_null_result(response: &async_graphql::Response, field: &str) {
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    let data = response.data.clone().into_json().unwrap();
+    assert!(data[field].is_null(), "{}", data);
+}
+
+/// Asserts a cross-tenant mutation failed with `NOT_FOUND`
+fn assert_not_found(response: &async_graphql::Response) {
+    assert_eq!(response.errors.len(), 1, "{:?}", response.errors);
+    let code = response.errors[0]
+        .extensions
+        .as_ref()
+        .and_then(|extensions| extensions.get("code"))
+        .cloned();
+    assert_eq!(
+        code,
+        Some(async_graphql::Value::String("NOT_FOUND".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn test_update_job_status_rejects_other_tenants_job() {
+    let (schema, pool) = setup_test_schema().await;
+    let fixture = TenantFixture::insert(&pool).await;
+
+    let response = fixture
+        .execute_as_other_tenant(
+            &schema,
+            "mutation($job: UuidScalar!) { updateJobStatus(id: $job, status: RUNNING) { id } }",
+        )
+        .await;
+
+    assert_null_result(&response, "updateJobStatus");
+    fixture.assert_untouched(&pool).await;
+}
+
+#[tokio::test]
+async fn test_update_job_rejects_other_tenants_job() {
+    let (schema, pool) = setup_test_schema().await;
+    let fixture = TenantFixture::insert(&pool).await;
+
+    let response = fixture
+        .execute_as_other_tenant(
+            &schema,
+            "mutation($job: UuidScalar!) { updateJob(id: $job, input: { name: \"hijacked\", status: RUNNING }) { id } }",
+        )
+        .await;
+
+    assert_null_result(&response, "updateJob");
+    fixture.assert_untouched(&pool).await;
+    let name: String = sqlx::query_scalar("SELECT name FROM jobs WHERE id = $1")
+        .bind(fixture.job_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(name, "tenant fixture");
+}
+
+#[tokio::test]
+async fn test_cancel_job_rejects_other_tenants_job() {
+    let (schema, pool) = setup_test_schema().await;
+    let fixture = TenantFixture::insert(&pool).await;
+
+    let response = fixture
+        .execute_as_other_tenant(
+            &schema,
+            "mutation($job: UuidScalar!) { cancelJob(id: $job) { id } }",
+        )
+        .await;
+
+    assert_null_result(&response, "cancelJob");
+    fixture.assert_untouched(&pool).await;
+}
+
+#[tokio::test]
+async fn test_create_task_rejects_other_tenants_job() {
+    let (schema, pool) = setup_test_schema().await;
+    let fixture = TenantFixture::insert(&pool).await;
+
+    let response = fixture
+        .execute_as_other_tenant(
+            &schema,
+            "mutation($job: UuidScalar!) { createTask(jobId: $job, name: \"noop\") { id } }",
+        )
+        .await;
+
+    assert_not_found(&response);
+    fixture.assert_untouched(&pool).await;
+}
+
+#[tokio::test]
+async fn test_update_task_status_rejects_other_tenants_task() {
+    let (schema, pool) = setup_test_schema().await;
+    let fixture = TenantFixture::insert(&pool).await;
+
+    let response = fixture
+        .execute_as_other_tenant(
+            &schema,
+            "mutation($task: UuidScalar!) { updateTaskStatus(id: $task, status: RUNNING) { id } }",
+        )
+        .await;
+
+    assert_null_result(&response, "updateTaskStatus");
+    fixture.assert_untouched(&pool).await;
+}
+
+#[tokio::test]
+async fn test_update_task_rejects_other_tenants_task() {
+    let (schema, pool) = setup_test_schema().await;
+    let fixture = TenantFixture::insert(&pool).await;
+
+    let response = fixture
+        .execute_as_other_tenant(
+            &schema,
+            "mutation($task: UuidScalar!) { updateTask(id: $task, input: { status: RUNNING }) { id } }",
+        )
+        .await;
+
+    assert_null_result(&response, "updateTask");
+    fixture.assert_untouched(&pool).await;
+}
+
+#[tokio::test]
+async fn test_update_tasks_status_ignores_other_tenants_tasks() {
+    let (schema, pool) = setup_test_schema().await;
+    let fixture = TenantFixture::insert(&pool).await;
+
+    let response = fixture
+        .execute_as_other_tenant(
+            &schema,
+            "mutation($task: UuidScalar!) { updateTasksStatus(ids: [$task], status: RUNNING) { count skipped { id } } }",
+        )
+        .await;
+
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    assert_eq!(data["updateTasksStatus"]["count"], 0);
+    assert_eq!(data["updateTasksStatus"]["skipped"], json!([]));
+    fixture.assert_untouched(&pool).await;
+}
+
+#[tokio::test]
+async fn test_cancel_task_rejects_other_tenants_task() {
+    let (schema, pool) = setup_test_schema().await;
+    let fixture = TenantFixture::insert(&pool).await;
+
+    let response = fixture
+        .execute_as_other_tenant(
+            &schema,
+            "mutation($task: UuidScalar!) { cancelTask(id: $task) { id } }",
+        )
+        .await;
+
+    assert_null_result(&response, "cancelTask");
+    fixture.assert_untouched(&pool).await;
+}
+
+#[tokio::test]
+async fn test_run_job_rejects_other_tenants_job() {
+    let (schema, pool) = setup_test_schema().await;
+    let fixture = TenantFixture::insert(&pool).await;
+
+    let response = fixture
+        .execute_as_other_tenant(
+            &schema,
+            "mutation($job: UuidScalar!) { runJob(id: $job) { id } }",
+        )
+        .await;
+
+    assert_null_result(&response, "runJob");
+    let audited: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM audit_log WHERE entity_id = $1 AND operation = 'runJob'",
+    )
+    .bind(fixture.job_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(audited, 0);
+    fixture.assert_untouched(&pool).await;
+}
+
+#[tokio::test]
+async fn test_create_pipeline_run_rejects_other_tenants_job() {
+    let (schema, pool) = setup_test_schema().await;
+    let fixture = TenantFixture::insert(&pool).await;
+
+    let response = fixture
+        .execute_as_other_tenant(
+            &schema,
+            "mutation($job: UuidScalar!) { createPipelineRun(jobId: $job) { id } }",
+        )
+        .await;
+
+    assert_not_found(&response);
+    fixture.assert_untouched(&pool).await;
+}
+
+#[tokio::test]
+async fn test_update_pipeline_run_status_rejects_other_tenants_run() {
+    let (schema, pool) = setup_test_schema().await;
+    let fixture = TenantFixture::insert(&pool).await;
+
+    let response = fixture
+        .execute_as_other_tenant(
+            &schema,
+            "mutation($run: UuidScalar!) { updatePipelineRunStatus(id: $run, status: RUNNING) { id } }",
+        )
+        .await;
+
+    assert_null_result(&response, "updatePipelineRunStatus");
+    fixture.assert_untouched(&pool).await;
+}
@@ -0,0 +1,987 @@
+use crate::auth::{AuthProvider, AuthResponse, TokenClaims};
+use crate::graphql::resolver_test::NoAuth;
+use crate::graphql::{
+    build_router, create_router, create_schema_with_auth, operation_hash, ExportState,
+    OperationAllowlist, CSV_CHUNK_BYTES, DEFAULT_GRAPHQL_MAX_BATCH_SIZE,
+};
+use async_trait::async_trait;
+use axum::Router;
+use futures::{SinkExt, StreamExt};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+use uuid::Uuid;
+
+async fn test_pool() -> PgPool {
+    PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .expect("Failed to create test database")
+}
+
+/// Accepts any token, treating the token itself as the user id; a `user:tenant` token
+/// also carries that `business_group_id`
+struct TokenIsUserId;
+
+#[async_trait]
+impl AuthProvider for TokenIsUserId {
+    async fn login(
+        &self,
+        _email: String,
+        _password: String,
+    ) -> async_graphql::Result<AuthResponse> {
+        Err(async_graphql::Error::new("login disabled in tests"))
+    }
+
+    async fn validate_token(&self, token: &str) -> async_graphql::Result<TokenClaims> {
+        let (sub, tenant) = match token.split_once(':') {
+            Some((sub, tenant)) => (sub, tenant.parse().ok()),
+            None => (token, None),
+        };
+        Ok(TokenClaims {
+            sub: sub.to_string(),
+            exp: usize::MAX,
+            iat: 0,
+            iss: None,
+            aud: None,
+            email: None,
+            business_group_id: tenant,
+        })
+    }
+}
+
+/// Serves the GraphQL router on an ephemeral local port and returns its base URL
+async fn spawn_router() -> String {
+    spawn_router_with_auth(Arc::new(NoAuth)).await
+}
+
+/// Like `spawn_router`, authenticating callers with `auth_provider`
+async fn spawn_router_with_auth(auth_provider: Arc<dyn AuthProvider>) -> String {
+    let pool = test_pool().await;
+    let (event_sender, _) = broadcast::channel(100);
+    serve(
+        create_router(
+            create_schema_with_auth(pool.clone(), event_sender, auth_provider.clone()),
+            ExportState {
+                pool,
+                auth_provider,
+            },
+        )
+        .unwrap(),
+    )
+    .await
+}
+
+/// Serves `router` on an ephemeral local port and returns its base URL
+async fn serve(router: Router) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_oversized_graphql_body_is_rejected() {
+    let base_url = spawn_router().await;
+
+    // Well over the default 1 MiB limit
+    let padding = "x".repeat(2 * 1024 * 1024);
+    let body = serde_json::json!({
+        "query": "{ serverInfo { version } }",
+        "variables": { "padding": padding },
+    });
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/graphql", base_url))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status().as_u16(), 413);
+}
+
+#[tokio::test]
+async fn test_small_graphql_body_is_accepted() {
+    let base_url = spawn_router().await;
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/graphql", base_url))
+        .json(&serde_json::json!({ "query": "{ serverInfo { version } }" }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn test_raw_application_graphql_body_is_executed() {
+    let base_url = spawn_router().await;
+
+    let body: serde_json::Value = reqwest::Client::new()
+        .post(format!("{}/graphql", base_url))
+        .header("Content-Type", "application/graphql; charset=utf-8")
+        .body("query Version { serverInfo { version } }")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    assert!(body.get("errors").is_none(), "{}", body);
+    assert_eq!(
+        body["data"]["serverInfo"]["version"],
+        env!("CARGO_PKG_VERSION")
+    );
+}
+
+#[tokio::test]
+async fn test_allowlist_runs_only_listed_operations() {
+    let allowed = "query Version { serverInfo { version } }";
+    let path = std::env::temp_dir().join(format!("dds_allowlist_{}.json", Uuid::new_v4()));
+    std::fs::write(
+        &path,
+        serde_json::json!([operation_hash(allowed)]).to_string(),
+    )
+    .unwrap();
+    let allowlist = OperationAllowlist::from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let pool = test_pool().await;
+    let (event_sender, _) = broadcast::channel(100);
+    let base_url = serve(build_router(
+        create_schema_with_auth(pool.clone(), event_sender, Arc::new(NoAuth)),
+        ExportState {
+            pool,
+            auth_provider: Arc::new(NoAuth),
+        },
+        Duration::from_secs(30),
+        Some(allowlist),
+    ))
+    .await;
+
+    let client = reqwest::Client::new();
+    let post = |query: &'static str| {
+        client
+            .post(format!("{}/graphql", base_url))
+            .json(&serde_json::json!({ "query": query }))
+            .send()
+    };
+
+    // Whitespace differences don't matter
+    let body: serde_json::Value = post("query Version {\n  serverInfo { version }\n}")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        body["data"]["serverInfo"]["version"],
+        env!("CARGO_PKG_VERSION")
+    );
+
+    let body: serde_json::Value = post("{ serverInfo { gitSha } }")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["errors"][0]["message"], "operation not allowed");
+    assert_eq!(
+        body["errors"][0]["extensions"]["code"],
+        "OPERATION_NOT_ALLOWED"
+    );
+
+    // The WebSocket endpoint enforces the same list
+    let mut request = format!("{}/graphql/ws", base_url.replacen("http", "ws", 1))
+        .into_client_request()
+        .unwrap();
+    request.headers_mut().insert(
+        "Sec-WebSocket-Protocol",
+        "graphql-transport-ws".parse().unwrap(),
+    );
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+    socket
+        .send(Message::text(r#"{"type":"connection_init"}"#))
+        .await
+        .unwrap();
+    let ack = socket.next().await.unwrap().unwrap();
+    assert!(ack.to_text().unwrap().contains("connection_ack"), "{}", ack);
+    for (id, query, expected) in [
+        ("1", allowed, env!("CARGO_PKG_VERSION")),
+        (
+            "2",
+            "mutation { createJob(name: \"ws bypass\") { id } }",
+            "operation not allowed",
+        ),
+    ] {
+        let subscribe = serde_json::json!({
+            "type": "subscribe",
+            "id": id,
+            "payload": { "query": query },
+        });
+        socket
+            .send(Message::text(subscribe.to_string()))
+            .await
+            .unwrap();
+        // Skip the `complete` that follows the previous operation's result
+        let reply = loop {
+            let message = socket.next().await.unwrap().unwrap();
+            if !message.to_text().unwrap().contains("\"complete\"") {
+                break message;
+            }
+        };
+        assert!(reply.to_text().unwrap().contains(expected), "{}", reply);
+    }
+}
+
+#[tokio::test]
+async fn test_large_response_is_compressed() {
+    let base_url = spawn_router().await;
+
+    // Aliasing serverInfo many times makes the response well above the compression threshold
+    let fields: Vec<String> = (0..200)
+        .map(|i| format!("s{}: serverInfo {{ version startedAt }}", i))
+        .collect();
+    let response = reqwest::Client::new()
+        .post(format!("{}/graphql", base_url))
+        .header("Accept-Encoding", "gzip")
+        .json(&serde_json::json!({ "query": format!("{{ {} }}", fields.join(" ")) }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response
+            .headers()
+            .get("content-encoding")
+            .and_then(|value| value.to_str().ok()),
+        Some("gzip")
+    );
+}
+
+#[tokio::test]
+async fn test_batch_returns_responses_in_order_with_independent_failures() {
+    let base_url = spawn_router().await;
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/graphql", base_url))
+        .json(&serde_json::json!([
+            { "query": "{ serverInfo { version } }" },
+            { "query": "{ noSuchField }" },
+        ]))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let responses = body.as_array().expect("batch should return an array");
+    assert_eq!(responses.len(), 2);
+    assert!(responses[0]["data"]["serverInfo"]["version"].is_string());
+    assert!(responses[0].get("errors").is_none());
+    assert!(responses[1]["errors"][0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("noSuchField"));
+}
+
+#[tokio::test]
+async fn test_batch_over_limit_is_rejected() {
+    let base_url = spawn_router().await;
+
+    let operations: Vec<_> = (0..DEFAULT_GRAPHQL_MAX_BATCH_SIZE + 1)
+        .map(|_| serde_json::json!({ "query": "{ serverInfo { version } }" }))
+        .collect();
+    let response = reqwest::Client::new()
+        .post(format!("{}/graphql", base_url))
+        .json(&operations)
+        .send()
+        .await
+        .unwrap();
+
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["errors"][0]["extensions"]["code"], "BATCH_TOO_LARGE");
+    assert!(body.get("data").is_none_or(|data| data.is_null()));
+}
+
+#[tokio::test]
+async fn test_validate_reports_unknown_field() {
+    let base_url = spawn_router().await;
+
+    let body: serde_json::Value = reqwest::Client::new()
+        .post(format!("{}/graphql/validate", base_url))
+        .json(&serde_json::json!({ "query": "{ serverInfo { noSuchField } }" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    assert_eq!(body["valid"], false);
+    let errors = body["errors"].as_array().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("noSuchField"));
+}
+
+#[tokio::test]
+async fn test_validate_accepts_raw_application_graphql_body() {
+    let base_url = spawn_router().await;
+    let client = reqwest::Client::new();
+    let validate = |document: &'static str| {
+        client
+            .post(format!("{}/graphql/validate", base_url))
+            .header("Content-Type", "application/graphql")
+            .body(document)
+            .send()
+    };
+
+    let response = validate("{ serverInfo { version } }").await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body, serde_json::json!({ "valid": true }));
+
+    let body: serde_json::Value = validate("{ serverInfo { noSuchField } }")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["valid"], false);
+}
+
+#[tokio::test]
+async fn test_validate_does_not_execute_valid_documents() {
+    let base_url = spawn_router().await;
+    let name = format!("validate-only-{}", uuid::Uuid::new_v4());
+
+    let body: serde_json::Value = reqwest::Client::new()
+        .post(format!("{}/graphql/validate", base_url))
+        .json(&serde_json::json!({
+            "query": "mutation($name: String!) { createJob(name: $name) { id } }",
+            "variables": { "name": name },
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    assert_eq!(body, serde_json::json!({ "valid": true }));
+
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&std::env::var("DATABASE_URL").unwrap())
+        .await
+        .unwrap();
+    let jobs: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM jobs WHERE name = $1")
+        .bind(&name)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(jobs, 0);
+}
+
+#[tokio::test]
+async fn test_graphql_rejects_unsupported_content_type() {
+    let base_url = spawn_router().await;
+
+    for content_type in [Some("text/plain"), None] {
+        let mut request = reqwest::Client::new()
+            .post(format!("{}/graphql", base_url))
+            .body(r#"{ "query": "{ serverInfo { version } }" }"#);
+        if let Some(content_type) = content_type {
+            request = request.header("Content-Type", content_type);
+        }
+        let response = request.send().await.unwrap();
+
+        assert_eq!(response.status().as_u16(), 415, "{:?}", content_type);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(
+            body["errors"][0]["extensions"]["code"],
+            "UNSUPPORTED_MEDIA_TYPE"
+        );
+    }
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/graphql", base_url))
+        .header("Content-Type", "application/json; charset=utf-8")
+        .body(r#"{ "query": "{ serverInfo { version } }" }"#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/graphiql", base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn test_slow_requests_time_out_but_subscriptions_stay_open() {
+    let pool = test_pool().await;
+    let (event_sender, _) = broadcast::channel(100);
+    let base_url = serve(build_router(
+        create_schema_with_auth(pool.clone(), event_sender, Arc::new(NoAuth)),
+        ExportState {
+            pool: pool.clone(),
+            auth_provider: Arc::new(NoAuth),
+        },
+        Duration::from_secs(1),
+        None,
+    ))
+    .await;
+
+    // Open a subscription over the graphql-transport-ws protocol
+    let mut request = format!("{}/graphql/ws", base_url.replacen("http", "ws", 1))
+        .into_client_request()
+        .unwrap();
+    request.headers_mut().insert(
+        "Sec-WebSocket-Protocol",
+        "graphql-transport-ws".parse().unwrap(),
+    );
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+    socket
+        .send(Message::text(r#"{"type":"connection_init"}"#))
+        .await
+        .unwrap();
+    let ack = socket.next().await.unwrap().unwrap();
+    assert!(ack.to_text().unwrap().contains("connection_ack"), "{}", ack);
+    socket
+        .send(Message::text(
+            r#"{"type":"subscribe","id":"1","payload":{"query":"subscription { etlEvents { eventType } }"}}"#,
+        ))
+        .await
+        .unwrap();
+
+    // Hold a lock on a task so updating it blocks past the deadline
+    let job_id = Uuid::new_v4();
+    let task_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, 'Running')")
+        .bind(job_id)
+        .bind(format!("testjob_{}", job_id))
+        .execute(&pool)
+        .await
+        .expect("Failed to insert job");
+    sqlx::query("INSERT INTO tasks (id, job_id, name, status) VALUES ($1, $2, 'slow', 'Running')")
+        .bind(task_id)
+        .bind(job_id)
+        .execute(&pool)
+        .await
+        .expect("Failed to insert task");
+    let mut lock = pool.begin().await.unwrap();
+    sqlx::query("SELECT id FROM tasks WHERE id = $1 FOR UPDATE")
+        .bind(task_id)
+        .execute(&mut *lock)
+        .await
+        .unwrap();
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/graphql", base_url))
+        .json(&serde_json::json!({
+            "query": format!(
+                "mutation {{ updateTaskStatus(id: \"{}\", status: COMPLETED) {{ id }} }}",
+                task_id
+            )
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 408);
+    lock.rollback().await.unwrap();
+
+    // More than the deadline has passed since the subscription started; it still answers
+    socket
+        .send(Message::text(r#"{"type":"ping"}"#))
+        .await
+        .unwrap();
+    let pong = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let message = socket.next().await.unwrap().unwrap();
+            if message.to_text().unwrap().contains("pong") {
+                break message;
+            }
+        }
+    })
+    .await
+    .expect("subscription connection was closed");
+    assert!(pong.to_text().unwrap().contains("pong"));
+}
+
+/// Splits CSV text into records of fields, undoing the quoting of `csv_field`
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    records
+}
+
+/// Admin user the CSV export and admin-guard tests authenticate as (with `TokenIsUserId`)
+const EXPORT_ADMIN_ID: &str = "5a4e0b8e-8a34-4c55-9a5d-6c2f4a0e7d11";
+
+#[tokio::test]
+async fn test_users_csv_export_escapes_values() {
+    let admin_id = EXPORT_ADMIN_ID;
+    std::env::set_var("ADMIN_USER_IDS", admin_id);
+    let base_url = spawn_router_with_auth(Arc::new(TokenIsUserId)).await;
+    let pool = test_pool().await;
+
+    let suffix = Uuid::new_v4().simple().to_string();
+    let username = format!("O'Brien, \"Pat\" {}", suffix);
+    let email = format!("pat.{}@example.com", suffix);
+    let user_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO users (id, username, email, created_at, updated_at) VALUES ($1, $2, $3, NOW(), NOW())",
+    )
+    .bind(user_id)
+    .bind(&username)
+    .bind(&email)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let client = reqwest::Client::new();
+    let response = client
+        .get(format!("{}/export/users.csv", base_url))
+        .bearer_auth(admin_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.headers()["content-type"].to_str().unwrap(),
+        "text/csv; charset=utf-8"
+    );
+    let records = parse_csv(&response.text().await.unwrap());
+
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(records[0], ["id", "username", "email", "created_at"]);
+    let record = records
+        .iter()
+        .find(|record| record[0] == user_id.to_string())
+        .expect("exported user");
+    assert_eq!(record.len(), 4);
+    assert_eq!(record[1], username);
+    assert_eq!(record[2], email);
+    assert!(chrono::DateTime::parse_from_rfc3339(&record[3]).is_ok());
+
+    // Only admins may export
+    let response = client
+        .get(format!("{}/export/users.csv", base_url))
+        .bearer_auth(Uuid::new_v4().to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 403);
+    let response = client
+        .get(format!("{}/export/users.csv", base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn test_schema_sdl_is_served_as_text() {
+    let base_url = spawn_router().await;
+
+    let response = reqwest::get(format!("{}/schema.graphql", base_url))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.headers()["content-type"].to_str().unwrap(),
+        "text/plain; charset=utf-8"
+    );
+    let sdl = response.text().await.unwrap();
+    assert!(sdl.contains("type Job {"), "{}", sdl);
+    assert!(sdl.contains("type Query {"));
+}
+
+#[tokio::test]
+async fn test_users_csv_export_streams_large_tables_in_bounded_chunks() {
+    std::env::set_var("ADMIN_USER_IDS", EXPORT_ADMIN_ID);
+    let base_url = spawn_router_with_auth(Arc::new(TokenIsUserId)).await;
+    let pool = test_pool().await;
+
+    // Many times the cursor batch and the chunk size
+    const USERS: i64 = 30_000;
+    let domain = format!("bulk-{}.example.com", Uuid::new_v4().simple());
+    sqlx::query(
+        "INSERT INTO users (id, username, email, created_at, updated_at) \
+         SELECT gen_random_uuid(), $1 || '_' || n, 'user' || n || '@' || $1, NOW(), NOW() \
+         FROM generate_series(1, $2) AS n",
+    )
+    .bind(&domain)
+    .bind(USERS)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let client = reqwest::Client::new();
+    let mut response = client
+        .get(format!("{}/export/users.csv", base_url))
+        .bearer_auth(EXPORT_ADMIN_ID)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Read the body chunk by chunk, only keeping the rows of interest
+    let mut chunks = 0;
+    let mut largest_chunk = 0;
+    let mut exported = 0;
+    let mut partial = String::new();
+    while let Some(chunk) = response.chunk().await.unwrap() {
+        chunks += 1;
+        largest_chunk = largest_chunk.max(chunk.len());
+        partial.push_str(std::str::from_utf8(&chunk).unwrap());
+        let complete = partial.rfind('\n').map_or(0, |end| end + 1);
+        exported += partial[..complete]
+            .lines()
+            .filter(|line| {
+                line.ends_with(&format!("@{}", domain)) || line.contains(&format!("@{},", domain))
+            })
+            .count();
+        partial.drain(..complete);
+    }
+
+    let limited = client
+        .get(format!("{}/export/users.csv?limit=5", base_url))
+        .bearer_auth(EXPORT_ADMIN_ID)
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    sqlx::query("DELETE FROM users WHERE email LIKE '%@' || $1")
+        .bind(&domain)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(exported as i64, USERS);
+    assert!(chunks > 1, "export arrived in {} chunk(s)", chunks);
+    // A chunk is flushed once it passes the threshold, so it exceeds it by under one row
+    assert!(largest_chunk < CSV_CHUNK_BYTES + 1024, "{}", largest_chunk);
+    // Header plus five users
+    assert_eq!(parse_csv(&limited).len(), 6);
+}
+
+#[tokio::test]
+async fn test_bearer_token_authenticates_admin_operations() {
+    std::env::set_var("ADMIN_USER_IDS", EXPORT_ADMIN_ID);
+    let base_url = spawn_router_with_auth(Arc::new(TokenIsUserId)).await;
+    let client = reqwest::Client::new();
+    let body = serde_json::json!({
+        "query": "{ searchUsers(emailPrefix: \"nobody-\") { id } }",
+    });
+
+    let response: serde_json::Value = client
+        .post(format!("{}/graphql", base_url))
+        .bearer_auth(EXPORT_ADMIN_ID)
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(response.get("errors").is_none(), "{}", response);
+    assert!(response["data"]["searchUsers"].is_array());
+
+    // A valid token for someone else is authenticated but not an admin
+    let response: serde_json::Value = client
+        .post(format!("{}/graphql", base_url))
+        .bearer_auth(Uuid::new_v4().to_string())
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(response["errors"][0]["extensions"]["code"], "FORBIDDEN");
+
+    // Without a token the guard rejects the caller as anonymous
+    let response: serde_json::Value = client
+        .post(format!("{}/graphql", base_url))
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        response["errors"][0]["extensions"]["code"],
+        "UNAUTHENTICATED"
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_bearer_token_is_rejected() {
+    let base_url = spawn_router().await;
+
+    let response: serde_json::Value = reqwest::Client::new()
+        .post(format!("{}/graphql", base_url))
+        .bearer_auth("not-a-token")
+        .json(&serde_json::json!({ "query": "{ serverInfo { version } }" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    assert!(response.get("data").is_none() || response["data"].is_null());
+    assert_eq!(
+        response["errors"][0]["extensions"]["code"],
+        "UNAUTHENTICATED"
+    );
+    assert!(response["errors"][0]["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("Token validation failed"));
+}
+
+#[tokio::test]
+async fn test_mutations_record_the_authenticated_user() {
+    let base_url = spawn_router_with_auth(Arc::new(TokenIsUserId)).await;
+    let pool = test_pool().await;
+    let user_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO users (id, username, email, created_at, updated_at) VALUES ($1, $2, $3, NOW(), NOW())",
+    )
+    .bind(user_id)
+    .bind(format!("router-actor-{}", user_id.simple()))
+    .bind(format!("router-actor-{}@example.com", user_id.simple()))
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let response: serde_json::Value = reqwest::Client::new()
+        .post(format!("{}/graphql", base_url))
+        .bearer_auth(user_id.to_string())
+        .json(&serde_json::json!({
+            "query": "mutation { createJob(name: \"router audit job\") { id } }",
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(response.get("errors").is_none(), "{}", response);
+    let job_id = Uuid::parse_str(response["data"]["createJob"]["id"].as_str().unwrap()).unwrap();
+
+    let (created_by, updated_by): (Option<Uuid>, Option<Uuid>) =
+        sqlx::query_as("SELECT created_by, updated_by FROM jobs WHERE id = $1")
+            .bind(job_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    let actor: Option<Uuid> = sqlx::query_scalar(
+        "SELECT actor_user_id FROM audit_log WHERE entity_id = $1 AND operation = 'createJob'",
+    )
+    .bind(job_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    sqlx::query("DELETE FROM jobs WHERE id = $1")
+        .bind(job_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(created_by, Some(user_id));
+    assert_eq!(updated_by, Some(user_id));
+    assert_eq!(actor, Some(user_id));
+}
+
+#[tokio::test]
+async fn test_bearer_token_tenant_scopes_reads() {
+    let base_url = spawn_router_with_auth(Arc::new(TokenIsUserId)).await;
+    let pool = test_pool().await;
+    let tenant = (Uuid::new_v4().as_u128() >> 65) as i64;
+    let job_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO jobs (id, name, status, business_group_id) VALUES ($1, 'router tenant job', 'Pending', $2)",
+    )
+    .bind(job_id)
+    .bind(tenant)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let client = reqwest::Client::new();
+    let body = serde_json::json!({
+        "query": "query($id: UuidScalar!) { job(id: $id) { id } }",
+        "variables": { "id": job_id },
+    });
+    let mut found = Vec::new();
+    for token in [
+        format!("{}:{}", Uuid::new_v4(), tenant),
+        format!("{}:{}", Uuid::new_v4(), tenant + 1),
+        Uuid::new_v4().to_string(),
+    ] {
+        let response: serde_json::Value = client
+            .post(format!("{}/graphql", base_url))
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert!(response.get("errors").is_none(), "{}", response);
+        found.push(!response["data"]["job"].is_null());
+    }
+    sqlx::query("DELETE FROM jobs WHERE id = $1")
+        .bind(job_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Only the owning tenant sees the job; other tenants and tenant-less tokens don't
+    assert_eq!(found, [true, false, false]);
+}
+
+/// Opens a graphql-transport-ws connection whose `connection_init` carries `token`
+async fn connect_ws(
+    base_url: &str,
+    token: &str,
+) -> tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>> {
+    let mut request = format!("{}/graphql/ws", base_url.replacen("http", "ws", 1))
+        .into_client_request()
+        .unwrap();
+    request.headers_mut().insert(
+        "Sec-WebSocket-Protocol",
+        "graphql-transport-ws".parse().unwrap(),
+    );
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+    let init = serde_json::json!({
+        "type": "connection_init",
+        "payload": { "Authorization": format!("Bearer {}", token) },
+    });
+    socket.send(Message::text(init.to_string())).await.unwrap();
+    socket
+}
+
+#[tokio::test]
+async fn test_subscriptions_only_receive_their_tenants_events() {
+    let base_url = spawn_router_with_auth(Arc::new(TokenIsUserId)).await;
+    let tenant_a = (Uuid::new_v4().as_u128() >> 65) as i64;
+    let tenant_b = tenant_a + 1;
+    let token_a = format!("ws-a:{}", tenant_a);
+    let token_b = format!("ws-b:{}", tenant_b);
+
+    let mut sockets = Vec::new();
+    for token in [&token_a, &token_b] {
+        let mut socket = connect_ws(&base_url, token).await;
+        let ack = socket.next().await.unwrap().unwrap();
+        assert!(ack.to_text().unwrap().contains("connection_ack"), "{}", ack);
+        socket
+            .send(Message::text(
+                r#"{"type":"subscribe","id":"1","payload":{"query":"subscription { etlEvents { eventType data } }"}}"#,
+            ))
+            .await
+            .unwrap();
+        sockets.push(socket);
+    }
+    // Give both subscriptions time to start listening
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let create_job = |token: String, name: &'static str| {
+        let base_url = base_url.clone();
+        async move {
+            let body: serde_json::Value = reqwest::Client::new()
+                .post(format!("{}/graphql", base_url))
+                .bearer_auth(token)
+                .json(&serde_json::json!({
+                    "query": format!("mutation {{ createJob(name: \"{}\") {{ id }} }}", name)
+                }))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            assert!(body["errors"].is_null(), "{}", body);
+        }
+    };
+    create_job(token_a.clone(), "tenant a job").await;
+    create_job(token_b.clone(), "tenant b job").await;
+
+    // Events arrive in publish order, so tenant B's first event would be tenant A's job
+    // if it leaked
+    for (socket, expected) in sockets.iter_mut().zip(["tenant a job", "tenant b job"]) {
+        let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("no event received")
+            .unwrap()
+            .unwrap();
+        let text = message.to_text().unwrap();
+        assert!(text.contains("JobCreated"), "{}", text);
+        assert!(text.contains(expected), "{}", text);
+    }
+}
+
+#[tokio::test]
+async fn test_subscription_connections_with_invalid_tokens_are_closed() {
+    let base_url = spawn_router_with_auth(Arc::new(NoAuth)).await;
+
+    let mut socket = connect_ws(&base_url, "not-a-token").await;
+
+    let reply = socket.next().await.unwrap().unwrap();
+    assert!(reply.is_close(), "{}", reply);
+}
@@ -1,6 +1,39 @@
 pub mod auth;
 pub mod db;
 pub mod etl;
+pub mod event_bus;
 pub mod graphql;
 pub mod logging;
 pub mod models;
+pub mod notifier;
+pub mod reaper;
+pub mod server;
+pub mod task_handlers;
+pub mod task_runner;
+
+#[cfg(test)]
+mod auth_test;
+
+#[cfg(test)]
+mod etl_test;
+
+#[cfg(test)]
+mod event_bus_test;
+
+#[cfg(test)]
+mod logging_test;
+
+#[cfg(test)]
+mod notifier_test;
+
+#[cfg(test)]
+mod reaper_test;
+
+#[cfg(test)]
+mod server_test;
+
+#[cfg(test)]
+mod task_handlers_test;
+
+#[cfg(test)]
+mod task_runner_test;
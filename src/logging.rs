@@ -1,11 +1,18 @@
-use std::path::PathBuf;
-use tracing;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use thiserror::Error;
+use tracing::Level;
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::{
-    filter::EnvFilter,
+    filter::{filter_fn, EnvFilter, FilterFn},
     fmt::{self, format::FmtSpan},
     layer::SubscriberExt,
-    util::SubscriberInitExt,
+    util::{SubscriberInitExt, TryInitError},
     Layer,
 };
 
@@ -38,19 +45,178 @@ impl LogLevel {
     }
 }
 
+/// Default base name for log files
+pub const DEFAULT_LOG_FILE_PREFIX: &str = "dds.log";
+
+/// Parses a `LOG_ROTATION` value into a file rotation policy.
+///
+/// Accepts `daily`, `hourly`, `minutely` and `never`, case-insensitively.
+///
+/// # Arguments
+/// * `value` - The configured rotation policy
+///
+/// # Returns
+/// * `Option<Rotation>` - The rotation policy, or None if the value is not recognized
+pub fn parse_rotation(value: &str) -> Option<Rotation> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "daily" => Some(Rotation::DAILY),
+        "hourly" => Some(Rotation::HOURLY),
+        "minutely" => Some(Rotation::MINUTELY),
+        "never" => Some(Rotation::NEVER),
+        _ => None,
+    }
+}
+
+/// Errors that stop `init_logging` from setting up logging at all.
+///
+/// Problems with the log directory are not among them: file logging is skipped instead.
+#[derive(Error, Debug)]
+pub enum LoggingError {
+    /// The OpenTelemetry exporter could not be built
+    #[error("Failed to start the OpenTelemetry exporter: {0}")]
+    Telemetry(String),
+
+    /// Another global tracing subscriber was installed first
+    #[error("Failed to install the tracing subscriber: {0}")]
+    AlreadyInitialized(#[from] TryInitError),
+}
+
+/// Opens the rolling log file in `dir`, creating the directory if it is missing.
+///
+/// # Returns
+/// * `Result<RollingFileAppender, String>` - The appender, or why the directory can't be logged to
+pub fn open_log_file(
+    dir: &Path,
+    rotation: Rotation,
+    file_prefix: &str,
+) -> Result<RollingFileAppender, String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("cannot create log directory {}: {}", dir.display(), e))?;
+    RollingFileAppender::builder()
+        .rotation(rotation)
+        .filename_prefix(file_prefix)
+        .build(dir)
+        .map_err(|e| format!("cannot write log files to {}: {}", dir.display(), e))
+}
+
+/// Default fraction of info and lower spans and events that are logged (all of them)
+pub const DEFAULT_LOG_SAMPLE_RATE: f64 = 1.0;
+
+/// Least severe level that is always logged, whatever `LOG_SAMPLE_RATE` says
+pub const UNSAMPLED_LEVEL: Level = Level::WARN;
+
+/// Parses a `LOG_SAMPLE_RATE` value, clamping it to between 0.0 and 1.0.
+///
+/// # Arguments
+/// * `value` - The configured sample rate
+///
+/// # Returns
+/// * `Option<f64>` - The rate, or None if the value is not a number
+pub fn parse_sample_rate(value: &str) -> Option<f64> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|rate| !rate.is_nan())
+        .map(|rate| rate.clamp(0.0, 1.0))
+}
+
+/// Builds a filter that keeps every span and event at `UNSAMPLED_LEVEL` or above and a
+/// random `rate` fraction of the rest
+fn sampling_filter(rate: f64) -> FilterFn<impl Fn(&tracing::Metadata<'_>) -> bool> {
+    filter_fn(move |metadata| {
+        *metadata.level() <= UNSAMPLED_LEVEL || rate >= 1.0 || rand::random::<f64>() < rate
+    })
+}
+
+/// The `service.name` resource attribute reported to the OpenTelemetry collector
+pub const OTEL_SERVICE_NAME: &str = "dds";
+
+/// Whether the OpenTelemetry exporter was enabled by `init_logging`
+static OTEL_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Returns `true` if spans are being exported to an OpenTelemetry collector.
+pub fn otel_enabled() -> bool {
+    OTEL_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Builds the OTLP tracer provider when `OTEL_EXPORTER_OTLP_ENDPOINT` is set.
+///
+/// Spans are exported over gRPC in batches using the Tokio runtime, with the
+/// resource attribute `service.name = "dds"`. The W3C trace-context propagator is
+/// installed globally so incoming `traceparent` headers can be honored.
+///
+/// # Returns
+/// * `Result<Option<TracerProvider>, LoggingError>` - The provider, or None if the endpoint is unset
+fn init_otel_provider() -> Result<Option<TracerProvider>, LoggingError> {
+    let endpoint = match std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+        Ok(endpoint) if !endpoint.is_empty() => endpoint,
+        _ => return Ok(None),
+    };
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|e| LoggingError::Telemetry(e.to_string()))?;
+
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new(vec![KeyValue::new(
+            "service.name",
+            OTEL_SERVICE_NAME,
+        )]))
+        .build();
+
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+    opentelemetry::global::set_tracer_provider(provider.clone());
+    OTEL_ENABLED.store(true, Ordering::Relaxed);
+
+    Ok(Some(provider))
+}
+
+/// Flushes and shuts down the OpenTelemetry exporter, if one was started.
+///
+/// Call this before the process exits so buffered spans are not lost.
+pub fn shutdown_telemetry() {
+    if otel_enabled() {
+        opentelemetry::global::shutdown_tracer_provider();
+    }
+}
+
 /// Initializes the logging system for the application.
 ///
 /// This function sets up the logging system with the following components:
 /// 1. A console logger for development
 /// 2. A file logger for production
 /// 3. Environment variable based filtering
+/// 4. An optional OpenTelemetry exporter, enabled by `OTEL_EXPORTER_OTLP_ENDPOINT`
+///
+/// Log files rotate according to `LOG_ROTATION` (`daily`, `hourly`, `minutely` or
+/// `never`; default `daily`) and are named after `LOG_FILE_PREFIX` (default `dds.log`).
+/// An unrecognized rotation value logs a warning and falls back to daily.
+///
+/// `LOG_SAMPLE_RATE` (0.0 to 1.0, default 1.0) is the fraction of info, debug and trace
+/// spans and events written to the console and log files; warnings and errors are always
+/// written. Spans exported to OpenTelemetry are not sampled here.
+///
+/// A missing log directory is created. If it still can't be written to, a warning is
+/// logged to the console and the application runs without file logging.
 ///
 /// # Arguments
 /// * `log_dir` - Optional directory path for log files
 ///
 /// # Returns
-/// * `Result<(), Box<dyn std::error::Error>>` - Ok(()) if successful, or an error if initialization fails
-pub fn init_logging(log_dir: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+/// * `Result<(), LoggingError>` - Ok(()) if successful, or an error if initialization fails
+pub fn init_logging(log_dir: Option<PathBuf>) -> Result<(), LoggingError> {
+    // Resolve the sample rate, remembering invalid values to report once logging is up
+    let sample_setting = std::env::var("LOG_SAMPLE_RATE").ok();
+    let sample_rate = sample_setting
+        .as_deref()
+        .and_then(parse_sample_rate)
+        .unwrap_or(DEFAULT_LOG_SAMPLE_RATE);
+    let invalid_sample_rate = sample_setting.filter(|value| parse_sample_rate(value).is_none());
+
     // Create console layer
     let console_layer = fmt::layer()
         .with_target(false)
@@ -59,11 +225,27 @@ pub fn init_logging(log_dir: Option<PathBuf>) -> Result<(), Box<dyn std::error::
         .with_file(true)
         .with_line_number(true)
         .with_span_events(FmtSpan::CLOSE)
-        .with_filter(EnvFilter::from_default_env());
+        .with_filter(EnvFilter::from_default_env())
+        .with_filter(sampling_filter(sample_rate));
+
+    // Resolve the file rotation policy, remembering invalid values to report once logging is up
+    let rotation_setting = std::env::var("LOG_ROTATION").ok();
+    let rotation = rotation_setting
+        .as_deref()
+        .and_then(parse_rotation)
+        .unwrap_or(Rotation::DAILY);
+    let invalid_rotation = rotation_setting.filter(|value| parse_rotation(value).is_none());
+    let file_prefix = std::env::var("LOG_FILE_PREFIX")
+        .ok()
+        .filter(|prefix| !prefix.is_empty())
+        .unwrap_or_else(|| DEFAULT_LOG_FILE_PREFIX.to_string());
 
-    // Create file layer if log directory is provided
-    let file_layer = if let Some(dir) = log_dir {
-        let file_appender = RollingFileAppender::new(Rotation::DAILY, dir, "dds.log");
+    // Create file layer if log directory is provided and usable, remembering why it isn't
+    let file_appender = log_dir.map(|dir| open_log_file(&dir, rotation, &file_prefix));
+    let file_error = file_appender
+        .as_ref()
+        .and_then(|appender| appender.as_ref().err().cloned());
+    let file_layer = if let Some(Ok(file_appender)) = file_appender {
         let file_layer = fmt::layer()
             .with_target(false)
             .with_level(true)
@@ -72,18 +254,45 @@ pub fn init_logging(log_dir: Option<PathBuf>) -> Result<(), Box<dyn std::error::
             .with_line_number(true)
             .with_span_events(FmtSpan::CLOSE)
             .with_writer(file_appender)
-            .with_filter(EnvFilter::from_default_env());
+            .with_filter(EnvFilter::from_default_env())
+            .with_filter(sampling_filter(sample_rate));
         Some(file_layer)
     } else {
         None
     };
 
-    // Initialize the subscriber with both layers
+    // Create OpenTelemetry layer if an OTLP endpoint is configured
+    let otel_layer = init_otel_provider()?.map(|provider| {
+        tracing_opentelemetry::layer()
+            .with_tracer(provider.tracer(OTEL_SERVICE_NAME))
+            .with_filter(EnvFilter::from_default_env())
+    });
+
+    // Initialize the subscriber with all layers
     let subscriber = tracing_subscriber::registry()
         .with(console_layer)
-        .with(file_layer);
+        .with(file_layer)
+        .with(otel_layer);
+
+    subscriber.try_init()?;
+
+    if let Some(value) = invalid_sample_rate {
+        tracing::warn!(
+            "Invalid LOG_SAMPLE_RATE value {:?}; expected a number from 0.0 to 1.0. Logging everything",
+            value
+        );
+    }
 
-    subscriber.init();
+    if let Some(error) = file_error {
+        tracing::warn!("File logging disabled: {}", error);
+    }
+
+    if let Some(value) = invalid_rotation {
+        tracing::warn!(
+            "Invalid LOG_ROTATION value {:?}; expected daily, hourly, minutely or never. Falling back to daily",
+            value
+        );
+    }
 
     Ok(())
 }
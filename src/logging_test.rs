@@ -0,0 +1,36 @@
+use crate::logging::{open_log_file, parse_sample_rate, DEFAULT_LOG_FILE_PREFIX};
+use tracing_appender::rolling::Rotation;
+use uuid::Uuid;
+
+#[test]
+fn test_open_log_file_creates_missing_directory() {
+    let root = std::env::temp_dir().join(format!("dds_logs_{}", Uuid::new_v4()));
+    let dir = root.join("nested");
+
+    assert!(open_log_file(&dir, Rotation::NEVER, DEFAULT_LOG_FILE_PREFIX).is_ok());
+    assert!(dir.join(DEFAULT_LOG_FILE_PREFIX).is_file());
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_open_log_file_reports_unwritable_directory() {
+    // A path below a regular file can't be created, even by root
+    let file = std::env::temp_dir().join(format!("dds_logs_{}", Uuid::new_v4()));
+    std::fs::write(&file, "").unwrap();
+    let dir = file.join("logs");
+
+    let error = open_log_file(&dir, Rotation::DAILY, DEFAULT_LOG_FILE_PREFIX).unwrap_err();
+    std::fs::remove_file(&file).unwrap();
+    assert!(error.contains(&dir.display().to_string()), "{}", error);
+}
+
+#[test]
+fn test_parse_sample_rate_clamps_to_unit_interval() {
+    assert_eq!(parse_sample_rate("0.25"), Some(0.25));
+    assert_eq!(parse_sample_rate(" 1 "), Some(1.0));
+    assert_eq!(parse_sample_rate("0"), Some(0.0));
+    assert_eq!(parse_sample_rate("2.5"), Some(1.0));
+    assert_eq!(parse_sample_rate("-0.5"), Some(0.0));
+    assert_eq!(parse_sample_rate("half"), None);
+    assert_eq!(parse_sample_rate("NaN"), None);
+}
@@ -1,23 +1,20 @@
-mod auth;
-/// The main module of the application.
-///
-/// This module contains the entry point of the application and demonstrates the usage of
-/// the database operations and ETL pipeline functionality.
-mod db;
-mod etl;
-mod graphql;
-mod logging;
-mod models;
-
-use axum::Router;
-use db::DbConnection;
+//! The main module of the application.
+//!
+//! This module contains the entry point of the application and demonstrates the usage of
+//! the database operations and ETL pipeline functionality.
+
+use dds::db::{spawn_keepalive, spawn_orphan_cleanup, DbConnection};
+use dds::event_bus::EventChannels;
+use dds::graphql::{auth_provider_from_env, create_router, create_schema_with_auth, ExportState};
+use dds::logging::{init_logging, shutdown_telemetry, LogLevel};
+use dds::notifier::CallbackNotifier;
+use dds::reaper::spawn_reaper;
+use dds::server::{
+    bind_addr_from_env, serve_with_shutdown, shutdown_drain_from_env, shutdown_signal,
+};
 use dotenv::dotenv;
-use futures::StreamExt;
-use graphql::{create_router, create_schema};
-use logging::{init_logging, LogLevel};
 use std::path::PathBuf;
 use tokio::net::TcpListener;
-use tokio::sync::broadcast;
 
 /// The main entry point of the application.
 ///
@@ -55,34 +52,53 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let db = DbConnection::new().await?;
     tracing::info!("Database connection established");
 
-    // Create event channel for GraphQL subscriptions
-    let (event_sender, _) = broadcast::channel(100);
-    tracing::debug!("GraphQL event channel created");
+    // Ping the pool periodically so idle connections aren't reset by the server
+    spawn_keepalive(db.pool.clone());
+
+    // Optionally delete tasks and pipeline runs whose job is gone
+    spawn_orphan_cleanup(db.pool.clone());
+
+    // Create event channels for GraphQL subscriptions (in-process or Postgres NOTIFY)
+    let events = EventChannels::from_env(&db.pool)
+        .await?
+        .with_sink_from_env()
+        .await?;
+    tracing::debug!("GraphQL event channels created");
+
+    // Fail runs left `Running` by a crashed or restarted process
+    spawn_reaper(db.pool.clone(), events.sink.clone());
+
+    // POST to job callback URLs when jobs finish
+    CallbackNotifier::new(db.pool.clone()).spawn(&events.publisher);
 
     // Create GraphQL schema and router
-    let schema = create_schema(db.pool.clone(), event_sender);
-    let router = create_router(schema);
+    let auth_provider = auth_provider_from_env(db.pool.clone());
+    let schema = create_schema_with_auth(db.pool.clone(), events, auth_provider.clone());
+    let router = create_router(
+        schema,
+        ExportState {
+            pool: db.pool.clone(),
+            auth_provider,
+        },
+    )?;
     tracing::info!("GraphQL schema and router initialized");
 
     // Start the GraphQL server
-    let port = std::env::var("PORT").unwrap_or_else(|_| "3000".to_string());
-    let addr = format!("0.0.0.0:{}", port);
+    let addr = bind_addr_from_env(3000)?;
+    let drain = shutdown_drain_from_env();
 
     // Check if TLS is enabled via environment variable
     let use_https = std::env::var("USE_HTTPS").unwrap_or_else(|_| "false".to_string()) == "true";
 
     if use_https {
         // Get certificate and key paths from environment
-        let cert_path =
+        let _cert_path =
             std::env::var("TLS_CERT_PATH").expect("TLS_CERT_PATH must be set when USE_HTTPS=true");
-        let key_path =
+        let _key_path =
             std::env::var("TLS_KEY_PATH").expect("TLS_KEY_PATH must be set when USE_HTTPS=true");
 
-        tracing::info!("Starting HTTPS GraphQL server on https://0.0.0.0:{}", port);
-        tracing::info!(
-            "GraphiQL playground available at https://0.0.0.0:{}/graphiql",
-            port
-        );
+        tracing::info!("Starting HTTPS GraphQL server on https://{}", addr);
+        tracing::info!("GraphiQL playground available at https://{}/graphiql", addr);
 
         // Since direct TLS support is complex with axum 0.8.4, recommend using a reverse proxy
         tracing::warn!("Direct TLS support in axum 0.8 is complex. For production, consider:");
@@ -94,21 +110,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         tracing::info!(
             "Falling back to HTTP for development. Use a reverse proxy for TLS in production."
         );
-        let listener = TcpListener::bind(&addr).await?;
-        axum::serve(listener, router).await?;
+        let listener = TcpListener::bind(addr).await?;
+        serve_with_shutdown(listener, router, shutdown_signal(), drain).await?;
     } else {
-        tracing::info!("Starting HTTP GraphQL server on http://0.0.0.0:{}", port);
-        tracing::info!(
-            "GraphiQL playground available at http://0.0.0.0:{}/graphiql",
-            port
-        );
+        tracing::info!("Starting HTTP GraphQL server on http://{}", addr);
+        tracing::info!("GraphiQL playground available at http://{}/graphiql", addr);
         tracing::info!("Press Ctrl+C to stop the server");
 
         // Start HTTP server
-        let listener = TcpListener::bind(&addr).await?;
-        axum::serve(listener, router).await?;
+        let listener = TcpListener::bind(addr).await?;
+        serve_with_shutdown(listener, router, shutdown_signal(), drain).await?;
+    }
+
+    // Connections still checked out after the drain are closed without waiting further
+    if tokio::time::timeout(drain, db.pool.close()).await.is_err() {
+        tracing::warn!("Timed out closing the database pool");
     }
 
     tracing::info!("Server stopped");
+    shutdown_telemetry();
     Ok(())
 }
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::models::etl::{DateTimeScalar, JsonValueScalar, UuidScalar};
+
+/// Represents a recorded mutation in the audit log.
+///
+/// An entry is written in the same transaction as the mutation it describes, so the log
+/// never records changes that were rolled back.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, async_graphql::SimpleObject)]
+pub struct AuditLogEntry {
+    /// The unique identifier for the entry
+    pub id: i64,
+    /// The user who performed the mutation, if authenticated
+    pub actor_user_id: Option<UuidScalar>,
+    /// The GraphQL mutation that was performed, e.g. `createJob`
+    pub operation: String,
+    /// The kind of entity that was changed, e.g. `Job`
+    pub entity_type: String,
+    /// The ID of the entity that was changed
+    pub entity_id: UuidScalar,
+    /// The entity (or mutation arguments) as JSON
+    pub payload: Option<JsonValueScalar>,
+    /// When the mutation was performed
+    pub occurred_at: DateTimeScalar,
+}
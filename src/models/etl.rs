@@ -1,4 +1,4 @@
-use async_graphql::{InputObject, ScalarType, SimpleObject, Value};
+use async_graphql::{ComplexObject, InputObject, ScalarType, SimpleObject, Value};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
@@ -19,6 +19,63 @@ pub enum Status {
     Completed,
     /// The entity has failed
     Failed,
+    /// The entity was cancelled before it finished
+    Cancelled,
+}
+
+impl Status {
+    /// Every variant, in the order of the Postgres `status` enum's labels
+    pub const ALL: [Status; 5] = [
+        Status::Pending,
+        Status::Running,
+        Status::Completed,
+        Status::Failed,
+        Status::Cancelled,
+    ];
+
+    /// Returns the label of this status in the Postgres `status` enum
+    pub fn db_label(&self) -> &'static str {
+        match self {
+            Status::Pending => "Pending",
+            Status::Running => "Running",
+            Status::Completed => "Completed",
+            Status::Failed => "Failed",
+            Status::Cancelled => "Cancelled",
+        }
+    }
+
+    /// Returns true for statuses that end a run: `Completed`, `Failed` and `Cancelled`
+    ///
+    /// Only `Failed` can be left again, by retrying (see `can_transition_to`).
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Status::Completed | Status::Failed | Status::Cancelled)
+    }
+
+    /// Returns true if an entity in this status may be moved to `next`.
+    ///
+    /// | From        | Allowed next statuses              |
+    /// |-------------|------------------------------------|
+    /// | `Pending`   | `Running`, `Cancelled`             |
+    /// | `Running`   | `Completed`, `Failed`, `Cancelled` |
+    /// | `Failed`    | `Pending` (retry)                  |
+    /// | `Completed` | none                               |
+    /// | `Cancelled` | none                               |
+    ///
+    /// Setting the status an entity already has is always allowed, as it changes nothing.
+    pub fn can_transition_to(&self, next: Status) -> bool {
+        use Status::*;
+
+        *self == next
+            || matches!(
+                (self, next),
+                (Pending, Running)
+                    | (Pending, Cancelled)
+                    | (Running, Completed)
+                    | (Running, Failed)
+                    | (Running, Cancelled)
+                    | (Failed, Pending)
+            )
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -58,21 +115,90 @@ impl Type<Postgres> for UuidScalar {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DateTimeScalar(pub DateTime<Utc>);
+/// A string that isn't empty or whitespace-only, such as a username
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String")]
+pub struct NonEmptyString(pub String);
+
+impl TryFrom<String> for NonEmptyString {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value.trim().is_empty() {
+            Err("value must not be empty".to_string())
+        } else {
+            Ok(NonEmptyString(value))
+        }
+    }
+}
 
 #[async_graphql::Scalar]
-impl ScalarType for DateTimeScalar {
+impl ScalarType for NonEmptyString {
     fn parse(value: Value) -> async_graphql::InputValueResult<Self> {
         if let Value::String(s) = value {
-            Ok(DateTimeScalar(
-                DateTime::parse_from_rfc3339(&s)?.with_timezone(&Utc),
-            ))
+            Ok(NonEmptyString::try_from(s)?)
         } else {
             Err(async_graphql::InputValueError::expected_type(value))
         }
     }
 
+    fn to_value(&self) -> Value {
+        Value::String(self.0.clone())
+    }
+}
+
+impl<'q> Encode<'q, Postgres> for NonEmptyString {
+    fn encode_by_ref(&self, buf: &mut sqlx::postgres::PgArgumentBuffer) -> sqlx::encode::IsNull {
+        <String as Encode<'q, Postgres>>::encode_by_ref(&self.0, buf)
+    }
+}
+
+impl Type<Postgres> for NonEmptyString {
+    fn type_info() -> sqlx::postgres::PgTypeInfo {
+        <String as Type<Postgres>>::type_info()
+    }
+}
+
+/// Numeric `DateTimeScalar` inputs smaller than this (in absolute value) are read as Unix
+/// seconds rather than milliseconds; it is the year 5138 in seconds and 1973 in milliseconds
+pub const EPOCH_SECONDS_THRESHOLD: f64 = 1e11;
+
+/// A timestamp, written as an RFC 3339 string.
+///
+/// Input may also be a number of milliseconds since the Unix epoch, or of seconds when
+/// below `EPOCH_SECONDS_THRESHOLD`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DateTimeScalar(pub DateTime<Utc>);
+
+#[async_graphql::Scalar]
+impl ScalarType for DateTimeScalar {
+    fn parse(value: Value) -> async_graphql::InputValueResult<Self> {
+        match &value {
+            Value::String(s) => Ok(DateTimeScalar(
+                DateTime::parse_from_rfc3339(s)?.with_timezone(&Utc),
+            )),
+            Value::Number(n) => {
+                let epoch = n
+                    .as_f64()
+                    .ok_or_else(|| async_graphql::InputValueError::expected_type(value.clone()))?;
+                let millis = if epoch.abs() < EPOCH_SECONDS_THRESHOLD {
+                    epoch * 1000.0
+                } else {
+                    epoch
+                };
+                DateTime::from_timestamp_millis(millis.round() as i64)
+                    .map(DateTimeScalar)
+                    .ok_or_else(|| {
+                        async_graphql::InputValueError::custom(format!(
+                            "Timestamp {} is out of range",
+                            n
+                        ))
+                    })
+            }
+            _ => Err(async_graphql::InputValueError::expected_type(value)),
+        }
+    }
+
     fn to_value(&self) -> Value {
         Value::String(self.0.to_rfc3339())
     }
@@ -103,11 +229,11 @@ pub struct JsonValueScalar(pub JsonValue);
 #[async_graphql::Scalar]
 impl ScalarType for JsonValueScalar {
     fn parse(value: Value) -> async_graphql::InputValueResult<Self> {
-        Ok(JsonValueScalar(serde_json::to_value(value)?))
+        Ok(JsonValueScalar(value.into_json()?))
     }
 
     fn to_value(&self) -> Value {
-        serde_json::from_value(self.0.clone()).unwrap_or(Value::Null)
+        Value::from_json(self.0.clone()).unwrap_or(Value::Null)
     }
 }
 
@@ -131,7 +257,10 @@ impl Type<Postgres> for JsonValueScalar {
 }
 
 /// Represents a job in the ETL system
+///
+/// Computed fields such as `progress` are resolved in `crate::graphql`.
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow, SimpleObject)]
+#[graphql(complex)]
 pub struct Job {
     /// Unique identifier for the job
     pub id: UuidScalar,
@@ -145,6 +274,30 @@ pub struct Job {
     pub created_at: DateTimeScalar,
     /// When the job was last updated
     pub updated_at: DateTimeScalar,
+    /// URL that receives a POST when the job reaches a terminal status
+    pub callback_url: Option<String>,
+    /// ID of the user who created the job (resolved as `createdBy`)
+    #[graphql(skip)]
+    pub created_by: Option<UuidScalar>,
+    /// ID of the user who last updated the job (resolved as `updatedBy`)
+    #[graphql(skip)]
+    pub updated_by: Option<UuidScalar>,
+    /// Tenant that owns the job (`None` for globally-scoped jobs)
+    #[graphql(skip)]
+    pub business_group_id: Option<i64>,
+}
+
+/// A failure recorded against a job in `job_errors`
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, SimpleObject)]
+pub struct JobError {
+    /// Unique identifier for the error
+    pub id: i64,
+    /// ID of the job that failed
+    pub job_id: UuidScalar,
+    /// What went wrong
+    pub message: String,
+    /// When the failure happened
+    pub occurred_at: DateTimeScalar,
 }
 
 /// Input for creating a new job
@@ -154,6 +307,8 @@ pub struct CreateJob {
     pub name: String,
     /// Description of the job
     pub description: Option<String>,
+    /// http(s) URL that receives a POST when the job reaches a terminal status
+    pub callback_url: Option<String>,
 }
 
 /// Input for updating an existing job
@@ -168,7 +323,10 @@ pub struct UpdateJob {
 }
 
 /// Represents a task in the ETL system
+///
+/// Computed fields such as `createdBy` are resolved in `crate::graphql`.
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow, SimpleObject)]
+#[graphql(complex)]
 pub struct Task {
     /// Unique identifier for the task
     pub id: UuidScalar,
@@ -184,10 +342,20 @@ pub struct Task {
     pub input_data: Option<JsonValueScalar>,
     /// Output data from the task
     pub output_data: Option<JsonValueScalar>,
+    /// Error message if the task failed
+    pub error_message: Option<String>,
+    /// ID of the task that must complete before this one runs
+    pub depends_on: Option<UuidScalar>,
     /// When the task was created
     pub created_at: DateTimeScalar,
     /// When the task was last updated
     pub updated_at: DateTimeScalar,
+    /// ID of the user who created the task (resolved as `createdBy`)
+    #[graphql(skip)]
+    pub created_by: Option<UuidScalar>,
+    /// ID of the user who last updated the task (resolved as `updatedBy`)
+    #[graphql(skip)]
+    pub updated_by: Option<UuidScalar>,
 }
 
 /// Input for creating a new task
@@ -201,6 +369,21 @@ pub struct CreateTask {
     pub description: Option<String>,
     /// Input data for the task
     pub input_data: Option<JsonValueScalar>,
+    /// ID of the task that must complete before this one runs
+    pub depends_on: Option<UuidScalar>,
+}
+
+/// Input for a task created together with its job
+#[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
+pub struct CreateTaskInput {
+    /// Name of the task
+    pub name: String,
+    /// Description of the task
+    pub description: Option<String>,
+    /// Input data for the task
+    pub input_data: Option<JsonValueScalar>,
+    /// ID of an existing task that must complete before this one runs
+    pub depends_on: Option<UuidScalar>,
 }
 
 /// Input for updating an existing task
@@ -214,12 +397,15 @@ pub struct UpdateTask {
     pub status: Option<Status>,
     /// New output data for the task
     pub output_data: Option<JsonValueScalar>,
+    /// New dependency for the task
+    pub depends_on: Option<UuidScalar>,
     /// Error message if the task failed
     pub error_message: Option<String>,
 }
 
 /// Represents a pipeline run in the ETL system
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow, SimpleObject)]
+#[graphql(complex)]
 pub struct PipelineRun {
     /// Unique identifier for the pipeline run
     pub id: UuidScalar,
@@ -235,6 +421,49 @@ pub struct PipelineRun {
     pub updated_at: DateTimeScalar,
 }
 
+#[ComplexObject]
+impl PipelineRun {
+    /// `metrics` parsed into a typed object (null when absent or not in the expected shape)
+    async fn typed_metrics(&self) -> Option<PipelineMetrics> {
+        PipelineMetrics::from_json(self.metrics.as_ref()?)
+    }
+
+    /// Milliseconds from creation to the last update of a finished run (null while the run
+    /// is `Pending` or `Running`)
+    async fn duration_ms(&self) -> Option<i64> {
+        self.status
+            .is_terminal()
+            .then(|| (self.updated_at.0 - self.created_at.0).num_milliseconds())
+    }
+}
+
+/// Typed view of the metrics recorded for a pipeline run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineMetrics {
+    /// Number of rows processed
+    #[serde(alias = "rows_processed")]
+    pub rows_processed: Option<i64>,
+    /// Number of rows that failed to process
+    #[serde(alias = "rows_failed")]
+    pub rows_failed: Option<i64>,
+    /// Duration of the run in milliseconds
+    #[serde(alias = "duration_ms")]
+    pub duration_ms: Option<i64>,
+    /// Number of bytes read
+    #[serde(alias = "bytes_read")]
+    pub bytes_read: Option<i64>,
+}
+
+impl PipelineMetrics {
+    /// Deserializes stored metrics, accepting camelCase or snake_case keys.
+    ///
+    /// Returns `None` when the JSON is not an object or a field has the wrong type.
+    pub fn from_json(metrics: &JsonValueScalar) -> Option<Self> {
+        serde_json::from_value(metrics.0.clone()).ok()
+    }
+}
+
 /// Input for creating a new pipeline run
 #[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
 pub struct CreatePipelineRun {
@@ -252,4 +481,3 @@ pub struct UpdatePipelineRun {
     /// Error message if the pipeline run failed
     pub error_message: Option<String>,
 }
-
@@ -0,0 +1,166 @@
+use crate::models::etl::{
+    DateTimeScalar, JsonValueScalar, NonEmptyString, PipelineMetrics, Status,
+};
+use async_graphql::{Number, ScalarType, Value};
+use chrono::{TimeZone, Utc};
+use serde_json::json;
+
+fn nested_json() -> serde_json::Value {
+    json!({
+        "name": "export",
+        "count": 42,
+        "ratio": 0.5,
+        "negative": -7,
+        "active": true,
+        "deleted": false,
+        "missing": null,
+        "tags": ["a", 1, 2.5, null, { "deep": [true, { "n": 3 }] }],
+        "nested": { "inner": { "value": 10, "flag": false } }
+    })
+}
+
+#[test]
+fn test_json_value_scalar_round_trips_nested_object() {
+    let json = nested_json();
+    let value = Value::from_json(json.clone()).unwrap();
+
+    let parsed = JsonValueScalar::parse(value.clone()).unwrap();
+    assert_eq!(parsed.0, json);
+    assert_eq!(parsed.to_value(), value);
+}
+
+#[test]
+fn test_json_value_scalar_preserves_number_types() {
+    let parsed = JsonValueScalar::parse(Value::from_json(nested_json()).unwrap()).unwrap();
+
+    assert!(parsed.0["count"].is_i64());
+    assert!(parsed.0["ratio"].is_f64());
+    assert!(parsed.0["active"].is_boolean());
+    assert!(parsed.0["missing"].is_null());
+    assert_eq!(parsed.0["nested"]["inner"]["value"], json!(10));
+}
+
+#[test]
+fn test_pipeline_metrics_round_trips_through_json() {
+    let metrics = PipelineMetrics {
+        rows_processed: Some(120),
+        rows_failed: Some(3),
+        duration_ms: Some(4500),
+        bytes_read: Some(1 << 20),
+    };
+    let stored = JsonValueScalar(serde_json::to_value(&metrics).unwrap());
+
+    assert_eq!(stored.0["rowsProcessed"], json!(120));
+    assert_eq!(PipelineMetrics::from_json(&stored), Some(metrics));
+}
+
+#[test]
+fn test_pipeline_metrics_accepts_snake_case_and_rejects_malformed() {
+    let snake = JsonValueScalar(json!({ "rows_processed": 7, "duration_ms": 12 }));
+    let parsed = PipelineMetrics::from_json(&snake).unwrap();
+    assert_eq!(parsed.rows_processed, Some(7));
+    assert_eq!(parsed.duration_ms, Some(12));
+    assert_eq!(parsed.bytes_read, None);
+
+    assert_eq!(
+        PipelineMetrics::from_json(&JsonValueScalar(json!("fast"))),
+        None
+    );
+    assert_eq!(
+        PipelineMetrics::from_json(&JsonValueScalar(json!({ "rowsProcessed": "many" }))),
+        None
+    );
+}
+
+#[test]
+fn test_status_transitions_follow_state_machine() {
+    use Status::*;
+
+    let all = [Pending, Running, Completed, Failed, Cancelled];
+    let legal = [
+        (Pending, Running),
+        (Pending, Cancelled),
+        (Running, Completed),
+        (Running, Failed),
+        (Running, Cancelled),
+        (Failed, Pending),
+    ];
+
+    for from in all {
+        for to in all {
+            let expected = from == to || legal.contains(&(from, to));
+            assert_eq!(
+                from.can_transition_to(to),
+                expected,
+                "{:?} -> {:?}",
+                from,
+                to
+            );
+        }
+    }
+}
+
+#[test]
+fn test_finished_statuses_cannot_return_to_pending() {
+    assert!(!Status::Completed.can_transition_to(Status::Pending));
+    assert!(!Status::Cancelled.can_transition_to(Status::Pending));
+    assert!(Status::Failed.can_transition_to(Status::Pending));
+}
+
+#[test]
+fn test_non_empty_string_parses_non_blank_value() {
+    let parsed = NonEmptyString::parse(Value::from("johndoe")).unwrap();
+
+    assert_eq!(parsed, NonEmptyString("johndoe".to_string()));
+    assert_eq!(parsed.to_value(), Value::from("johndoe"));
+}
+
+#[test]
+fn test_non_empty_string_rejects_empty_and_blank_values() {
+    for value in ["", "   ", "\t\n"] {
+        let err = NonEmptyString::parse(Value::from(value)).unwrap_err();
+        assert!(
+            err.into_server_error(Default::default())
+                .message
+                .contains("must not be empty"),
+            "{:?}",
+            value
+        );
+    }
+    assert!(NonEmptyString::parse(Value::from(5)).is_err());
+    assert!(serde_json::from_str::<NonEmptyString>("\"\"").is_err());
+}
+
+#[test]
+fn test_date_time_scalar_parses_rfc3339_string() {
+    let parsed = DateTimeScalar::parse(Value::String("2024-05-01T12:30:00+02:00".into())).unwrap();
+    assert_eq!(
+        parsed.0,
+        Utc.with_ymd_and_hms(2024, 5, 1, 10, 30, 0).unwrap()
+    );
+    assert_eq!(
+        parsed.to_value(),
+        Value::String("2024-05-01T10:30:00+00:00".into())
+    );
+}
+
+#[test]
+fn test_date_time_scalar_parses_epoch_millis_and_seconds() {
+    let expected = Utc.timestamp_millis_opt(1_714_559_400_123).unwrap();
+    let millis = DateTimeScalar::parse(Value::Number(Number::from(1_714_559_400_123_i64))).unwrap();
+    assert_eq!(millis.0, expected);
+
+    let seconds = DateTimeScalar::parse(Value::Number(Number::from(1_714_559_400_i64))).unwrap();
+    assert_eq!(seconds.0, Utc.timestamp_opt(1_714_559_400, 0).unwrap());
+}
+
+#[test]
+fn test_date_time_scalar_rejects_invalid_values() {
+    for value in [
+        Value::String("yesterday".into()),
+        Value::Boolean(true),
+        Value::Number(Number::from(i64::MAX)),
+    ] {
+        assert!(DateTimeScalar::parse(value.clone()).is_err(), "{:?}", value);
+    }
+}
@@ -1,4 +1,7 @@
+pub mod audit;
 pub mod etl;
 pub mod per_user;
 pub mod user;
 
+#[cfg(test)]
+mod etl_test;
@@ -1,13 +1,15 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::fmt;
 
-use crate::models::etl::{DateTimeScalar, UuidScalar};
+use crate::models::etl::{DateTimeScalar, NonEmptyString, UuidScalar};
 
 /// Represents a user in the system.
 ///
-/// This struct is used to represent a user entity in the database and includes all user-related information.
+/// This struct is used to represent a user entity in the database and includes all user-related information
+/// except the `password_hash` of local accounts, which is never read back into it.
 /// It implements `Serialize`, `Deserialize`, and `FromRow` for JSON serialization and database row mapping.
-#[derive(Debug, Serialize, Deserialize, FromRow, async_graphql::SimpleObject)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, async_graphql::SimpleObject)]
 pub struct User {
     /// The unique identifier for the user
     pub id: UuidScalar,
@@ -24,13 +26,28 @@ pub struct User {
 /// Represents the data needed to create a new user.
 ///
 /// This struct is used when creating a new user and contains only the required fields.
-/// It implements `Serialize` and `Deserialize` for JSON serialization.
-#[derive(Debug, Serialize, Deserialize, async_graphql::InputObject)]
+/// It implements `Serialize` and `Deserialize` for JSON serialization; the password is
+/// never serialized, and `Debug` redacts it.
+#[derive(Serialize, Deserialize, async_graphql::InputObject)]
 pub struct CreateUser {
-    /// The username for the new user
-    pub username: String,
+    /// The username for the new user (must not be empty)
+    pub username: NonEmptyString,
     /// The email address for the new user
     pub email: String,
+    /// Password of a local account, stored as an Argon2 hash (`None` for Auth0/Okta users)
+    #[serde(default, skip_serializing)]
+    #[graphql(secret)]
+    pub password: Option<String>,
+}
+
+impl fmt::Debug for CreateUser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreateUser")
+            .field("username", &self.username)
+            .field("email", &self.email)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
 }
 
 /// Represents the data that can be updated for an existing user.
@@ -40,8 +57,8 @@ pub struct CreateUser {
 /// It implements `Serialize` and `Deserialize` for JSON serialization.
 #[derive(Debug, Serialize, Deserialize, async_graphql::InputObject)]
 pub struct UpdateUser {
-    /// The new username (if provided)
-    pub username: Option<String>,
+    /// The new username (if provided; must not be empty)
+    pub username: Option<NonEmptyString>,
     /// The new email address (if provided)
     pub email: Option<String>,
 }
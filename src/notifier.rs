@@ -0,0 +1,274 @@
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+use sqlx::FromRow;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::graphql::ETLEvent;
+use crate::models::etl::{Status, UuidScalar};
+
+/// Default number of delivery attempts per callback
+pub const DEFAULT_CALLBACK_MAX_ATTEMPTS: u32 = 3;
+
+/// Default timeout of a single callback request, in seconds
+pub const DEFAULT_CALLBACK_TIMEOUT_SECS: u64 = 10;
+
+/// Default delay before the first retry; later retries double it
+pub const DEFAULT_CALLBACK_RETRY_DELAY_MS: u64 = 500;
+
+/// Checks that a callback URL is an absolute http(s) URL.
+///
+/// # Returns
+/// * `Result<(), String>` - `Ok` if the URL is usable, or a message explaining why not
+pub fn validate_callback_url(url: &str) -> Result<(), String> {
+    let parsed = url::Url::parse(url).map_err(|e| format!("invalid callback URL: {}", e))?;
+    match parsed.scheme() {
+        "http" | "https" if parsed.host().is_some() => Ok(()),
+        "http" | "https" => Err("callback URL must include a host".to_string()),
+        scheme => Err(format!(
+            "callback URL must use http or https, not {}",
+            scheme
+        )),
+    }
+}
+
+/// JSON body POSTed to a job's `callback_url`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallbackPayload {
+    /// The job that finished
+    pub job_id: Uuid,
+    /// The job's terminal status
+    pub status: Status,
+    /// When the job finished
+    pub completed_at: DateTime<Utc>,
+}
+
+/// A recorded webhook delivery, successful or not
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CallbackDelivery {
+    /// Unique identifier for the delivery
+    pub id: i64,
+    /// The job the callback was for
+    pub job_id: UuidScalar,
+    /// The URL the callback was POSTed to
+    pub url: String,
+    /// The job's status when the callback was sent
+    pub job_status: Status,
+    /// Whether any attempt got a 2xx response
+    pub succeeded: bool,
+    /// How many requests were made
+    pub attempts: i32,
+    /// HTTP status of the last response, if one was received
+    pub response_status: Option<i32>,
+    /// Why the last attempt failed
+    pub error: Option<String>,
+    /// When the delivery finished
+    pub delivered_at: DateTime<Utc>,
+}
+
+/// Delivers job-completion webhooks.
+///
+/// When a job reaches a terminal status its `callback_url`, if set, receives a
+/// `CallbackPayload` by POST. Failed requests (errors, timeouts and non-2xx responses)
+/// are retried with exponential backoff, and the outcome is recorded in
+/// `job_callback_deliveries`.
+#[derive(Clone)]
+pub struct CallbackNotifier {
+    pool: PgPool,
+    client: Client,
+    max_attempts: u32,
+    retry_delay: Duration,
+}
+
+impl CallbackNotifier {
+    /// Creates a notifier with the default attempts, timeout and retry delay.
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            client: Self::client(Duration::from_secs(DEFAULT_CALLBACK_TIMEOUT_SECS)),
+            max_attempts: DEFAULT_CALLBACK_MAX_ATTEMPTS,
+            retry_delay: Duration::from_millis(DEFAULT_CALLBACK_RETRY_DELAY_MS),
+        }
+    }
+
+    /// Sets how many times a delivery is attempted (at least once).
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn with_retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Sets the timeout of each request.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = Self::client(timeout);
+        self
+    }
+
+    fn client(timeout: Duration) -> Client {
+        Client::builder()
+            .timeout(timeout)
+            .build()
+            .unwrap_or_default()
+    }
+
+    /// Delivers the completion callback for a job, if it has one and has finished.
+    ///
+    /// # Arguments
+    /// * `job_id` - The ID of the job
+    ///
+    /// # Returns
+    /// * `Result<Option<CallbackDelivery>, sqlx::Error>` - The recorded delivery, `None` if
+    ///   the job doesn't exist, has no `callback_url` or hasn't finished
+    pub async fn notify(&self, job_id: Uuid) -> Result<Option<CallbackDelivery>, sqlx::Error> {
+        let job: Option<(Option<String>, Status, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT callback_url, status, COALESCE(completed_at, updated_at) FROM jobs WHERE id = $1",
+        )
+        .bind(job_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some((Some(url), status, completed_at)) = job else {
+            return Ok(None);
+        };
+        if !status.is_terminal() {
+            return Ok(None);
+        }
+
+        let payload = CallbackPayload {
+            job_id,
+            status,
+            completed_at,
+        };
+        let (attempts, response_status, error) = self.deliver(&url, &payload).await;
+        if let Some(ref error) = error {
+            warn!(
+                "Callback for job {} to {} failed after {} attempts: {}",
+                job_id, url, attempts, error
+            );
+        } else {
+            info!("Delivered callback for job {} to {}", job_id, url);
+        }
+
+        let delivery = sqlx::query_as::<_, CallbackDelivery>(
+            r#"
+            INSERT INTO job_callback_deliveries
+                (job_id, url, job_status, succeeded, attempts, response_status, error)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(job_id)
+        .bind(&url)
+        .bind(status)
+        .bind(error.is_none())
+        .bind(attempts as i32)
+        .bind(response_status.map(i32::from))
+        .bind(error)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(Some(delivery))
+    }
+
+    /// POSTs the payload until it succeeds or the attempts run out.
+    ///
+    /// Returns the number of attempts, the last HTTP status received and, on failure, the
+    /// last error.
+    async fn deliver(
+        &self,
+        url: &str,
+        payload: &CallbackPayload,
+    ) -> (u32, Option<u16>, Option<String>) {
+        let mut delay = self.retry_delay;
+        let mut response_status = None;
+        let mut last_error = None;
+
+        for attempt in 1..=self.max_attempts {
+            match self.client.post(url).json(payload).send().await {
+                Ok(response) if response.status().is_success() => {
+                    return (attempt, Some(response.status().as_u16()), None);
+                }
+                Ok(response) => {
+                    response_status = Some(response.status().as_u16());
+                    last_error = Some(format!("callback returned {}", response.status()));
+                }
+                Err(e) => {
+                    response_status = None;
+                    last_error = Some(e.to_string());
+                }
+            }
+
+            if attempt < self.max_attempts {
+                debug!(
+                    "Callback attempt {} to {} failed; retrying in {:?}",
+                    attempt, url, delay
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+        }
+
+        (self.max_attempts, response_status, last_error)
+    }
+
+    /// Spawns a task that delivers callbacks for jobs reaching a terminal status.
+    ///
+    /// Listens for `JobStatusUpdated`, `JobUpdated` and `JobCancelled` events on
+    /// `event_sender`. Pass the publishing side of the event bus, so that with several
+    /// replicas each transition is only delivered by the replica that made it.
+    ///
+    /// # Arguments
+    /// * `event_sender` - The broadcast channel jobs' status events are published on
+    ///
+    /// # Returns
+    /// * `JoinHandle<()>` - The handle of the spawned notifier task
+    pub fn spawn(self, event_sender: &broadcast::Sender<ETLEvent>) -> JoinHandle<()> {
+        let mut receiver = event_sender.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                let event = match receiver.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Callback notifier lagged; {} events were skipped", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if !is_job_completion(&event) {
+                    continue;
+                }
+
+                // Deliver in the background so a slow endpoint doesn't hold up other jobs
+                let notifier = self.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = notifier.notify(event.entity_id.0).await {
+                        error!(
+                            "Failed to record callback for job {}: {}",
+                            event.entity_id.0, e
+                        );
+                    }
+                });
+            }
+        })
+    }
+}
+
+/// Returns true for job events that move a job into a terminal status.
+fn is_job_completion(event: &ETLEvent) -> bool {
+    matches!(
+        event.event_type.as_str(),
+        "JobStatusUpdated" | "JobUpdated" | "JobCancelled"
+    ) && event
+        .status
+        .is_some_and(|status| status.is_terminal() && event.previous_status != Some(status))
+}
@@ -0,0 +1,139 @@
+use crate::models::etl::Status;
+use crate::notifier::{validate_callback_url, CallbackNotifier, CallbackPayload};
+use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Requests received by the mock callback server
+#[derive(Clone, Default)]
+struct Received {
+    calls: Arc<AtomicUsize>,
+    payloads: Arc<Mutex<Vec<CallbackPayload>>>,
+}
+
+async fn test_pool() -> PgPool {
+    PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .expect("Failed to create test database")
+}
+
+/// Serves a callback endpoint that fails the first `failures` requests with a 500
+async fn spawn_callback_server(failures: usize) -> (String, Received) {
+    let received = Received::default();
+    let router =
+        Router::new()
+            .route(
+                "/hook",
+                post(
+                    move |State(received): State<Received>,
+                          Json(payload): Json<CallbackPayload>| async move {
+                        let call = received.calls.fetch_add(1, Ordering::SeqCst);
+                        received.payloads.lock().await.push(payload);
+                        if call < failures {
+                            StatusCode::INTERNAL_SERVER_ERROR
+                        } else {
+                            StatusCode::OK
+                        }
+                    },
+                ),
+            )
+            .with_state(received.clone());
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.unwrap();
+    });
+    (format!("http://{}/hook", addr), received)
+}
+
+async fn insert_job(pool: &PgPool, status: &str, callback_url: &str) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO jobs (id, name, status, callback_url, completed_at) VALUES ($1, $2, $3::status, $4, NOW())",
+    )
+    .bind(id)
+    .bind(format!("testjob_{}", id))
+    .bind(status)
+    .bind(callback_url)
+    .execute(pool)
+    .await
+    .expect("Failed to insert job");
+    id
+}
+
+#[test]
+fn test_validate_callback_url_accepts_only_http_urls() {
+    assert!(validate_callback_url("https://example.com/hooks/etl").is_ok());
+    assert!(validate_callback_url("http://localhost:8080/hook").is_ok());
+    assert!(validate_callback_url("ftp://example.com/hook").is_err());
+    assert!(validate_callback_url("not a url").is_err());
+}
+
+#[tokio::test]
+async fn test_callback_is_retried_until_delivered_and_recorded() {
+    let pool = test_pool().await;
+    let (url, received) = spawn_callback_server(1).await;
+    let job_id = insert_job(&pool, "Completed", &url).await;
+
+    let delivery = CallbackNotifier::new(pool.clone())
+        .with_retry_delay(Duration::from_millis(10))
+        .notify(job_id)
+        .await
+        .unwrap()
+        .expect("a finished job with a callback_url should be delivered");
+
+    assert!(delivery.succeeded);
+    assert_eq!(delivery.attempts, 2);
+    assert_eq!(delivery.response_status, Some(200));
+    assert_eq!(delivery.job_status, Status::Completed);
+
+    let payloads = received.payloads.lock().await;
+    assert_eq!(payloads.len(), 2);
+    assert_eq!(payloads[0].job_id, job_id);
+    assert_eq!(payloads[0].status, Status::Completed);
+}
+
+#[tokio::test]
+async fn test_failed_callback_is_recorded_after_last_attempt() {
+    let pool = test_pool().await;
+    let (url, received) = spawn_callback_server(usize::MAX).await;
+    let job_id = insert_job(&pool, "Failed", &url).await;
+
+    let delivery = CallbackNotifier::new(pool.clone())
+        .with_max_attempts(3)
+        .with_retry_delay(Duration::from_millis(10))
+        .notify(job_id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert!(!delivery.succeeded);
+    assert_eq!(delivery.attempts, 3);
+    assert_eq!(delivery.response_status, Some(500));
+    assert!(delivery.error.unwrap().contains("500"));
+    assert_eq!(received.calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_unfinished_jobs_are_not_notified() {
+    let pool = test_pool().await;
+    let (url, received) = spawn_callback_server(0).await;
+    let job_id = insert_job(&pool, "Running", &url).await;
+
+    let delivery = CallbackNotifier::new(pool.clone())
+        .notify(job_id)
+        .await
+        .unwrap();
+
+    assert!(delivery.is_none());
+    assert_eq!(received.calls.load(Ordering::SeqCst), 0);
+}
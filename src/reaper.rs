@@ -0,0 +1,171 @@
+use sqlx::postgres::PgPool;
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::db::DbConnection;
+use crate::event_bus::EventSink;
+use crate::models::etl::{Job, Status, Task};
+use crate::task_runner::publish;
+
+/// Default age after which a `Running` job or task is considered stale, in seconds
+pub const DEFAULT_STALE_RUN_TIMEOUT_SECS: u64 = 3600;
+
+/// Default delay between reaper sweeps, in seconds
+pub const DEFAULT_REAPER_INTERVAL_SECS: u64 = 60;
+
+/// Error message recorded on jobs and tasks failed by the reaper
+pub const STALE_RUN_ERROR_MESSAGE: &str = "timed out / process restarted";
+
+/// Marks jobs and tasks that have been `Running` for longer than `timeout` as `Failed`.
+///
+/// A run's age is measured from `started_at`, or from `updated_at` when it was never
+/// recorded. Each reaped row is also appended to its job's `job_errors` history. The
+/// updates happen in one transaction, and a `JobStatusUpdated` or `TaskStatusUpdated`
+/// event is published for every reaped row once it commits.
+///
+/// # Arguments
+/// * `pool` - A PostgreSQL connection pool
+/// * `event_sender` - Where status events are published
+/// * `timeout` - How long a run may stay `Running` before it is reaped
+///
+/// # Returns
+/// * `Result<(Vec<Job>, Vec<Task>), sqlx::Error>` - The reaped jobs and tasks, or an error
+pub async fn reap_stale_runs(
+    pool: &PgPool,
+    event_sender: &dyn EventSink,
+    timeout: Duration,
+) -> Result<(Vec<Job>, Vec<Task>), sqlx::Error> {
+    let timeout_secs = timeout.as_secs_f64();
+
+    let mut tx = pool.begin().await?;
+    let jobs = sqlx::query_as::<_, Job>(
+        r#"
+        UPDATE jobs
+        SET status = $1, error_message = $2, completed_at = NOW()
+        WHERE status = $3
+          AND COALESCE(started_at, updated_at) < NOW() - make_interval(secs => $4)
+        RETURNING *
+        "#,
+    )
+    .bind(Status::Failed)
+    .bind(STALE_RUN_ERROR_MESSAGE)
+    .bind(Status::Running)
+    .bind(timeout_secs)
+    .fetch_all(&mut *tx)
+    .await?;
+    let tasks = sqlx::query_as::<_, Task>(
+        r#"
+        UPDATE tasks
+        SET status = $1, error_message = $2, completed_at = NOW()
+        WHERE status = $3
+          AND COALESCE(started_at, updated_at) < NOW() - make_interval(secs => $4)
+        RETURNING *
+        "#,
+    )
+    .bind(Status::Failed)
+    .bind(STALE_RUN_ERROR_MESSAGE)
+    .bind(Status::Running)
+    .bind(timeout_secs)
+    .fetch_all(&mut *tx)
+    .await?;
+    let task_job_ids: Vec<Uuid> = tasks.iter().map(|task| task.job_id.0).collect();
+    let tenants: HashMap<Uuid, Option<i64>> =
+        sqlx::query_as("SELECT id, business_group_id FROM jobs WHERE id = ANY($1)")
+            .bind(&task_job_ids)
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .collect();
+    for job in &jobs {
+        DbConnection::record_job_error(&mut tx, job.id, STALE_RUN_ERROR_MESSAGE).await?;
+    }
+    for task in &tasks {
+        let message = format!("task {} failed: {}", task.name, STALE_RUN_ERROR_MESSAGE);
+        DbConnection::record_job_error(&mut tx, task.job_id, &message).await?;
+    }
+    tx.commit().await?;
+
+    for job in &jobs {
+        publish(
+            event_sender,
+            "JobStatusUpdated",
+            job.id,
+            Some(Status::Running),
+            job.status,
+            job,
+            job.business_group_id,
+        );
+    }
+    for task in &tasks {
+        publish(
+            event_sender,
+            "TaskStatusUpdated",
+            task.id,
+            Some(Status::Running),
+            task.status,
+            task,
+            tenants.get(&task.job_id.0).copied().flatten(),
+        );
+    }
+
+    Ok((jobs, tasks))
+}
+
+/// Spawns the background reaper for stale `Running` jobs and tasks.
+///
+/// The reaper runs `reap_stale_runs` every `REAPER_INTERVAL_SECS` (default 60) with a
+/// timeout of `STALE_RUN_TIMEOUT_SECS` (default 3600). Errors are logged and the next
+/// sweep is attempted as usual.
+///
+/// # Arguments
+/// * `pool` - A PostgreSQL connection pool
+/// * `event_sender` - Where status events are published
+///
+/// # Returns
+/// * `JoinHandle<()>` - The handle of the spawned reaper task
+pub fn spawn_reaper(pool: PgPool, event_sender: Arc<dyn EventSink>) -> JoinHandle<()> {
+    let timeout = Duration::from_secs(env_secs(
+        "STALE_RUN_TIMEOUT_SECS",
+        DEFAULT_STALE_RUN_TIMEOUT_SECS,
+    ));
+    let interval = Duration::from_secs(env_secs(
+        "REAPER_INTERVAL_SECS",
+        DEFAULT_REAPER_INTERVAL_SECS,
+    ));
+    info!(
+        "Starting stale run reaper (timeout {:?}, interval {:?})",
+        timeout, interval
+    );
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match reap_stale_runs(&pool, event_sender.as_ref(), timeout).await {
+                Ok((jobs, tasks)) if !jobs.is_empty() || !tasks.is_empty() => {
+                    warn!(
+                        "Reaped {} stale jobs and {} stale tasks",
+                        jobs.len(),
+                        tasks.len()
+                    );
+                }
+                Ok(_) => {}
+                Err(e) => error!("Failed to reap stale runs: {}", e),
+            }
+        }
+    })
+}
+
+/// Reads a positive number of seconds from an environment variable, falling back to `default`.
+fn env_secs(name: &str, default: u64) -> u64 {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(default)
+}
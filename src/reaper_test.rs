@@ -0,0 +1,73 @@
+use crate::models::etl::Status;
+use crate::reaper::{reap_stale_runs, STALE_RUN_ERROR_MESSAGE};
+use chrono::{Duration, Utc};
+use sqlx::postgres::PgPoolOptions;
+use std::time::Duration as StdDuration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_reaper_fails_stale_runs_and_leaves_fresh_ones() {
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .expect("Failed to create test database");
+    let (event_sender, mut receiver) = broadcast::channel(100);
+
+    let stale_job = Uuid::new_v4();
+    let fresh_job = Uuid::new_v4();
+    for (id, started_at) in [
+        (stale_job, Utc::now() - Duration::hours(2)),
+        (fresh_job, Utc::now()),
+    ] {
+        sqlx::query(
+            "INSERT INTO jobs (id, name, status, started_at) VALUES ($1, $2, 'Running', $3)",
+        )
+        .bind(id)
+        .bind(format!("testjob_{}", id))
+        .bind(started_at)
+        .execute(&pool)
+        .await
+        .expect("Failed to insert job");
+    }
+    let stale_task = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO tasks (id, job_id, name, status, started_at) VALUES ($1, $2, 'stale', 'Running', $3)",
+    )
+    .bind(stale_task)
+    .bind(fresh_job)
+    .bind(Utc::now() - Duration::hours(2))
+    .execute(&pool)
+    .await
+    .expect("Failed to insert task");
+
+    let (jobs, tasks) = reap_stale_runs(&pool, &event_sender, StdDuration::from_secs(3600))
+        .await
+        .unwrap();
+    assert!(jobs.iter().any(|job| job.id.0 == stale_job));
+    assert!(!jobs.iter().any(|job| job.id.0 == fresh_job));
+    assert!(tasks.iter().any(|task| task.id.0 == stale_task));
+
+    let (status, error_message): (Status, Option<String>) =
+        sqlx::query_as("SELECT status, error_message FROM jobs WHERE id = $1")
+            .bind(stale_job)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(status, Status::Failed);
+    assert_eq!(error_message.as_deref(), Some(STALE_RUN_ERROR_MESSAGE));
+
+    let status: Status = sqlx::query_scalar("SELECT status FROM jobs WHERE id = $1")
+        .bind(fresh_job)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(status, Status::Running);
+
+    let event = receiver
+        .try_recv()
+        .expect("reaped job should emit an event");
+    assert_eq!(event.status, Some(Status::Failed));
+    assert_eq!(event.previous_status, Some(Status::Running));
+}
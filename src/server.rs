@@ -0,0 +1,307 @@
+//! Address the HTTP entrypoints listen on, and how they shut down.
+
+use axum::extract::{Request, State};
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use axum::serve::Listener;
+use axum::Router;
+use std::env;
+use std::future::{Future, IntoFuture};
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{oneshot, watch};
+use tokio_util::sync::{CancellationToken, WaitForCancellationFutureOwned};
+
+/// Default interface the HTTP servers bind to
+pub const DEFAULT_BIND_ADDR: &str = "0.0.0.0";
+
+/// Default time in-flight requests get to finish after a shutdown signal, in seconds
+pub const DEFAULT_SHUTDOWN_DRAIN_SECS: u64 = 15;
+
+/// Extra time force-closed connections get to wind down before serving is abandoned
+const FORCED_CLOSE_GRACE: Duration = Duration::from_secs(1);
+
+/// Combines a bind address (an IPv4 or IPv6 address) and a port into a `SocketAddr`.
+///
+/// # Returns
+/// * `Result<SocketAddr, String>` - The address, or a message naming the invalid part
+pub fn parse_bind_addr(bind_addr: &str, port: &str) -> Result<SocketAddr, String> {
+    let ip = bind_addr
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+        .map_err(|e| format!("invalid BIND_ADDR {:?}: {}", bind_addr, e))?;
+    let port = port
+        .trim()
+        .parse::<u16>()
+        .map_err(|e| format!("invalid PORT {:?}: {}", port, e))?;
+    Ok(SocketAddr::new(ip, port))
+}
+
+/// Returns the address to listen on, from `BIND_ADDR` (default `0.0.0.0`) and `PORT`
+/// (default `default_port`).
+///
+/// # Returns
+/// * `Result<SocketAddr, String>` - The address, or a message naming the invalid variable
+pub fn bind_addr_from_env(default_port: u16) -> Result<SocketAddr, String> {
+    let bind_addr = env::var("BIND_ADDR").unwrap_or_else(|_| DEFAULT_BIND_ADDR.to_string());
+    let port = env::var("PORT").unwrap_or_else(|_| default_port.to_string());
+    parse_bind_addr(&bind_addr, &port)
+}
+
+/// Returns how long to wait for in-flight requests on shutdown, from
+/// `SHUTDOWN_DRAIN_SECS` (default 15).
+pub fn shutdown_drain_from_env() -> Duration {
+    let secs = env::var("SHUTDOWN_DRAIN_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_SHUTDOWN_DRAIN_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Completes on Ctrl+C or, on Unix, `SIGTERM`.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// What happened to open work when `serve_with_shutdown` stopped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Requests in flight at the shutdown signal that finished on their own
+    pub drained_requests: usize,
+    /// Connections still open when the drain timeout expired, closed by force
+    pub forced_connections: usize,
+}
+
+/// Serves `router` on `listener` until `signal` completes, then shuts down within
+/// `drain`.
+///
+/// After the signal no new connections are accepted, and in-flight requests and open
+/// connections (including WebSocket subscriptions) get up to `drain` to finish. Whatever
+/// is still open then is closed by force, so shutdown never waits on a request or
+/// subscription that doesn't end. Either outcome is logged.
+///
+/// # Errors
+/// Returns any error of the underlying server.
+pub async fn serve_with_shutdown<F>(
+    listener: TcpListener,
+    router: Router,
+    signal: F,
+    drain: Duration,
+) -> io::Result<ShutdownReport>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let tracker = Arc::new(Tracker {
+        requests: AtomicUsize::new(0),
+        connections: watch::Sender::new(0),
+        force_close: CancellationToken::new(),
+    });
+    let listener = TrackedListener {
+        inner: listener,
+        tracker: tracker.clone(),
+    };
+    let router = router.layer(middleware::from_fn_with_state(
+        tracker.clone(),
+        track_request,
+    ));
+
+    let (signalled_tx, signalled_rx) = oneshot::channel();
+    let server = axum::serve(listener, router).with_graceful_shutdown(async move {
+        signal.await;
+        let _ = signalled_tx.send(());
+    });
+    let mut server = tokio::spawn(server.into_future());
+
+    // The server only ends before the signal if it failed
+    tokio::select! {
+        result = &mut server => {
+            result.map_err(io::Error::other)??;
+            return Ok(ShutdownReport::default());
+        }
+        _ = signalled_rx => {}
+    }
+
+    let started = Instant::now();
+    let in_flight = tracker.requests.load(Ordering::SeqCst);
+    tracing::info!(
+        "Shutting down: waiting up to {:?} for {} in-flight requests",
+        drain,
+        in_flight
+    );
+
+    let mut connections = tracker.connections.subscribe();
+    let drained = tokio::time::timeout(drain, async {
+        let result = (&mut server).await;
+        let _ = connections.wait_for(|open| *open == 0).await;
+        result
+    })
+    .await;
+
+    let mut report = ShutdownReport::default();
+    match drained {
+        Ok(result) => {
+            result.map_err(io::Error::other)??;
+            report.drained_requests = in_flight;
+            tracing::info!(
+                "drained {} requests in {}ms",
+                in_flight,
+                started.elapsed().as_millis()
+            );
+        }
+        Err(_) => {
+            report.drained_requests =
+                in_flight.saturating_sub(tracker.requests.load(Ordering::SeqCst));
+            report.forced_connections = *tracker.connections.borrow();
+            tracker.force_close.cancel();
+            tracing::warn!(
+                "drained {} requests in {}ms; forced close of {} connections",
+                report.drained_requests,
+                started.elapsed().as_millis(),
+                report.forced_connections
+            );
+
+            match tokio::time::timeout(FORCED_CLOSE_GRACE, &mut server).await {
+                Ok(result) => result.map_err(io::Error::other)??,
+                Err(_) => {
+                    tracing::error!("Server did not stop after closing connections; abandoning it");
+                    server.abort();
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Open connections and in-flight requests of a `serve_with_shutdown` server
+struct Tracker {
+    requests: AtomicUsize,
+    connections: watch::Sender<usize>,
+    /// Cancelled when the drain timeout expires, failing all I/O on open connections
+    force_close: CancellationToken,
+}
+
+/// Counts a request as in flight until its response is ready
+async fn track_request(State(tracker): State<Arc<Tracker>>, req: Request, next: Next) -> Response {
+    tracker.requests.fetch_add(1, Ordering::SeqCst);
+    let response = next.run(req).await;
+    tracker.requests.fetch_sub(1, Ordering::SeqCst);
+    response
+}
+
+/// Accepts TCP connections whose I/O fails once the tracker's `force_close` is cancelled
+struct TrackedListener {
+    inner: TcpListener,
+    tracker: Arc<Tracker>,
+}
+
+impl Listener for TrackedListener {
+    type Io = TrackedStream;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        let (stream, addr) = Listener::accept(&mut self.inner).await;
+        self.tracker.connections.send_modify(|open| *open += 1);
+        let stream = TrackedStream {
+            inner: stream,
+            closed: Box::pin(self.tracker.force_close.clone().cancelled_owned()),
+            tracker: self.tracker.clone(),
+        };
+        (stream, addr)
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.inner.local_addr()
+    }
+}
+
+/// A connection counted as open until dropped
+struct TrackedStream {
+    inner: TcpStream,
+    closed: Pin<Box<WaitForCancellationFutureOwned>>,
+    tracker: Arc<Tracker>,
+}
+
+impl TrackedStream {
+    /// Fails with `ConnectionAborted` once the connection has been force-closed
+    fn check_open(&mut self, cx: &mut Context<'_>) -> io::Result<()> {
+        match self.closed.as_mut().poll(cx) {
+            Poll::Ready(()) => Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "connection closed by shutdown",
+            )),
+            Poll::Pending => Ok(()),
+        }
+    }
+}
+
+impl Drop for TrackedStream {
+    fn drop(&mut self) {
+        self.tracker.connections.send_modify(|open| *open -= 1);
+    }
+}
+
+impl AsyncRead for TrackedStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.check_open(cx)?;
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TrackedStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.check_open(cx)?;
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.check_open(cx)?;
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.check_open(cx)?;
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
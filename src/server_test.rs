@@ -0,0 +1,114 @@
+use crate::server::{parse_bind_addr, serve_with_shutdown};
+use axum::routing::get;
+use axum::Router;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+#[test]
+fn test_parse_bind_addr_combines_address_and_port() {
+    assert_eq!(
+        parse_bind_addr("0.0.0.0", "3000").unwrap(),
+        "0.0.0.0:3000".parse::<SocketAddr>().unwrap()
+    );
+    assert_eq!(
+        parse_bind_addr("127.0.0.1", "8080").unwrap(),
+        "127.0.0.1:8080".parse::<SocketAddr>().unwrap()
+    );
+    assert_eq!(
+        parse_bind_addr("::1", "8080").unwrap(),
+        "[::1]:8080".parse::<SocketAddr>().unwrap()
+    );
+    assert_eq!(
+        parse_bind_addr("[::]", "8080").unwrap(),
+        "[::]:8080".parse::<SocketAddr>().unwrap()
+    );
+}
+
+#[test]
+fn test_parse_bind_addr_rejects_invalid_values() {
+    let err = parse_bind_addr("localhost", "3000").unwrap_err();
+    assert!(
+        err.starts_with("invalid BIND_ADDR \"localhost\""),
+        "{}",
+        err
+    );
+
+    let err = parse_bind_addr("127.0.0.1", "70000").unwrap_err();
+    assert!(err.starts_with("invalid PORT \"70000\""), "{}", err);
+}
+
+/// Starts `router` behind `serve_with_shutdown`, sends it one request, and signals
+/// shutdown once the request has reached the handler
+async fn shutdown_during_request(
+    router: Router,
+    drain: Duration,
+) -> (crate::server::ShutdownReport, Duration, Vec<u8>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(serve_with_shutdown(
+        listener,
+        router,
+        async move {
+            let _ = stop_rx.await;
+        },
+        drain,
+    ));
+
+    let mut client = TcpStream::connect(addr).await.unwrap();
+    client
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let started = Instant::now();
+    stop_tx.send(()).unwrap();
+    let report = tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("shutdown should not hang")
+        .unwrap()
+        .unwrap();
+    let elapsed = started.elapsed();
+
+    let mut response = Vec::new();
+    let _ = tokio::time::timeout(Duration::from_secs(1), client.read_to_end(&mut response)).await;
+    (report, elapsed, response)
+}
+
+#[tokio::test]
+async fn test_shutdown_force_closes_request_that_never_completes() {
+    let router = Router::new().route("/", get(std::future::pending::<&'static str>));
+
+    let (report, elapsed, response) =
+        shutdown_during_request(router, Duration::from_millis(200)).await;
+
+    assert_eq!(report.drained_requests, 0);
+    assert_eq!(report.forced_connections, 1);
+    assert!(elapsed >= Duration::from_millis(200), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(2), "{:?}", elapsed);
+    assert!(response.is_empty(), "no response should be sent");
+}
+
+#[tokio::test]
+async fn test_shutdown_drains_request_that_completes_in_time() {
+    let router = Router::new().route(
+        "/",
+        get(|| async {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            "done"
+        }),
+    );
+
+    let (report, elapsed, response) = shutdown_during_request(router, Duration::from_secs(5)).await;
+
+    assert_eq!(report.drained_requests, 1);
+    assert_eq!(report.forced_connections, 0);
+    assert!(elapsed < Duration::from_secs(5), "{:?}", elapsed);
+    let response = String::from_utf8(response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(response.ends_with("done"), "{}", response);
+}
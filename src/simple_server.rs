@@ -1,6 +1,6 @@
 use axum::{routing::get, Router};
+use dds::server::bind_addr_from_env;
 use dotenv::dotenv;
-use std::net::SocketAddr;
 use tokio::net::TcpListener;
 
 #[tokio::main]
@@ -21,8 +21,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("Router initialized with basic routes");
 
     // Start the server
-    let port = std::env::var("PORT").unwrap_or_else(|_| "8080".to_string());
-    let addr = format!("0.0.0.0:{}", port).parse::<SocketAddr>()?;
+    let addr = bind_addr_from_env(8080)?;
 
     tracing::info!("Starting simple test server on http://{}", addr);
 
@@ -0,0 +1,375 @@
+use async_trait::async_trait;
+use jsonschema::JSONSchema;
+use reqwest::{redirect, Client};
+use serde_json::{json, Value as JsonValue};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use url::Host;
+
+use crate::models::etl::Task;
+use crate::task_runner::{EchoTaskHandler, TaskHandler};
+
+/// Default timeout for requests made by `HttpFetchTaskHandler`, in seconds
+pub const DEFAULT_HTTP_FETCH_TIMEOUT_SECS: u64 = 30;
+
+/// A task payload that doesn't match its kind's JSON schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// JSON pointer to the offending value (`/` for the whole document)
+    pub path: String,
+    /// What is wrong with the value
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// The compiled input and output schemas of one task kind
+#[derive(Clone, Default)]
+struct TaskSchemas {
+    input: Option<Arc<JSONSchema>>,
+    output: Option<Arc<JSONSchema>>,
+}
+
+/// Dispatches each task to the handler registered under the task's `name`.
+///
+/// Tasks whose name has no registered handler fail with an error naming the task, so a
+/// typo never silently succeeds.
+///
+/// Each task kind may also have JSON schemas for its `input_data` and `output_data`,
+/// checked with `validate_input` and `validate_output` before the data is stored. Kinds
+/// without a schema accept any data.
+///
+/// # Example
+/// ```
+/// use dds::task_handlers::{HandlerRegistry, NoopTaskHandler};
+/// use std::sync::Arc;
+///
+/// let registry = HandlerRegistry::with_builtins().with_handler("cleanup", Arc::new(NoopTaskHandler));
+/// assert!(registry.get("cleanup").is_some());
+/// ```
+#[derive(Clone, Default)]
+pub struct HandlerRegistry {
+    handlers: HashMap<String, Arc<dyn TaskHandler>>,
+    schemas: HashMap<String, TaskSchemas>,
+}
+
+impl HandlerRegistry {
+    /// Creates a registry with no handlers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry with the built-in `noop`, `echo` and `http_fetch` handlers.
+    ///
+    /// `http_fetch` tasks must have an input with a string `url`, and their output must
+    /// have an integer `status`.
+    pub fn with_builtins() -> Self {
+        Self::new()
+            .with_handler("noop", Arc::new(NoopTaskHandler))
+            .with_handler("echo", Arc::new(EchoTaskHandler))
+            .with_handler("http_fetch", Arc::new(HttpFetchTaskHandler::from_env()))
+            .with_input_schema(
+                "http_fetch",
+                &json!({
+                    "type": "object",
+                    "required": ["url"],
+                    "properties": { "url": { "type": "string" } }
+                }),
+            )
+            .and_then(|registry| {
+                registry.with_output_schema(
+                    "http_fetch",
+                    &json!({
+                        "type": "object",
+                        "required": ["status"],
+                        "properties": { "status": { "type": "integer" } }
+                    }),
+                )
+            })
+            .expect("built-in task schemas are valid")
+    }
+
+    /// Registers `handler` for tasks named `name`, replacing any existing handler.
+    pub fn with_handler(mut self, name: impl Into<String>, handler: Arc<dyn TaskHandler>) -> Self {
+        self.handlers.insert(name.into(), handler);
+        self
+    }
+
+    /// Sets the JSON schema that `input_data` of tasks named `name` must match.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>` - The registry, or an error if `schema` is not a valid JSON schema
+    pub fn with_input_schema(
+        mut self,
+        name: impl Into<String>,
+        schema: &JsonValue,
+    ) -> Result<Self, String> {
+        self.schemas.entry(name.into()).or_default().input = Some(compile_schema(schema)?);
+        Ok(self)
+    }
+
+    /// Sets the JSON schema that `output_data` of tasks named `name` must match.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>` - The registry, or an error if `schema` is not a valid JSON schema
+    pub fn with_output_schema(
+        mut self,
+        name: impl Into<String>,
+        schema: &JsonValue,
+    ) -> Result<Self, String> {
+        self.schemas.entry(name.into()).or_default().output = Some(compile_schema(schema)?);
+        Ok(self)
+    }
+
+    /// Returns the handler registered for `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn TaskHandler>> {
+        self.handlers.get(name)
+    }
+
+    /// Checks a task's input against the input schema of its kind, if there is one.
+    ///
+    /// A missing input is checked as `null`.
+    ///
+    /// # Returns
+    /// * `Result<(), Vec<SchemaViolation>>` - Every violation found, if the input doesn't match
+    pub fn validate_input(
+        &self,
+        name: &str,
+        input: Option<&JsonValue>,
+    ) -> Result<(), Vec<SchemaViolation>> {
+        let schema = self.schemas.get(name).and_then(|s| s.input.as_deref());
+        validate(schema, input.unwrap_or(&JsonValue::Null))
+    }
+
+    /// Checks a task's output against the output schema of its kind, if there is one.
+    ///
+    /// # Returns
+    /// * `Result<(), Vec<SchemaViolation>>` - Every violation found, if the output doesn't match
+    pub fn validate_output(
+        &self,
+        name: &str,
+        output: &JsonValue,
+    ) -> Result<(), Vec<SchemaViolation>> {
+        let schema = self.schemas.get(name).and_then(|s| s.output.as_deref());
+        validate(schema, output)
+    }
+}
+
+fn compile_schema(schema: &JsonValue) -> Result<Arc<JSONSchema>, String> {
+    JSONSchema::compile(schema)
+        .map(Arc::new)
+        .map_err(|e| format!("invalid JSON schema: {}", e))
+}
+
+fn validate(schema: Option<&JSONSchema>, value: &JsonValue) -> Result<(), Vec<SchemaViolation>> {
+    let Some(schema) = schema else {
+        return Ok(());
+    };
+    schema.validate(value).map_err(|errors| {
+        errors
+            .map(|error| {
+                let path = error.instance_path.to_string();
+                SchemaViolation {
+                    path: if path.is_empty() {
+                        "/".to_string()
+                    } else {
+                        path
+                    },
+                    message: error.to_string(),
+                }
+            })
+            .collect()
+    })
+}
+
+#[async_trait]
+impl TaskHandler for HandlerRegistry {
+    async fn handle(&self, task: &Task) -> Result<Option<JsonValue>, String> {
+        match self.get(&task.name) {
+            Some(handler) => handler.handle(task).await,
+            None => Err(format!(
+                "no handler registered for task kind '{}'",
+                task.name
+            )),
+        }
+    }
+}
+
+/// Handler that does nothing and produces no output.
+pub struct NoopTaskHandler;
+
+#[async_trait]
+impl TaskHandler for NoopTaskHandler {
+    async fn handle(&self, _task: &Task) -> Result<Option<JsonValue>, String> {
+        Ok(None)
+    }
+}
+
+/// Returns whether `ip` is a publicly routable address: not loopback, private (RFC 1918
+/// or IPv6 unique local), link-local, shared (100.64.0.0/10), unspecified, broadcast,
+/// documentation or multicast.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ip(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+/// Handler that fetches a URL with `GET`.
+///
+/// The task's `input_data` must be an object with an http(s) `url`. The output is
+/// `{ "status": <code>, "body": <body> }`, where the body is parsed as JSON when possible
+/// and kept as a string otherwise. Non-2xx responses fail the task.
+///
+/// Only public addresses are fetched (see `is_public_ip`), so tasks can't reach the
+/// server's own network: the URL's host is resolved once, refused if any address isn't
+/// public, and the request is pinned to the checked addresses. Redirects aren't
+/// followed. Hosts passed to `with_allowed_hosts` skip the address check.
+pub struct HttpFetchTaskHandler {
+    timeout: Duration,
+    allowed_hosts: HashSet<String>,
+}
+
+impl HttpFetchTaskHandler {
+    /// Creates a handler whose requests time out after `timeout`.
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            allowed_hosts: HashSet::new(),
+        }
+    }
+
+    /// Creates a handler with the default timeout that may also reach the hosts listed
+    /// in `HTTP_FETCH_ALLOWED_HOSTS` (comma-separated), wherever they resolve.
+    pub fn from_env() -> Self {
+        let hosts = std::env::var("HTTP_FETCH_ALLOWED_HOSTS").unwrap_or_default();
+        Self::default().with_allowed_hosts(
+            hosts
+                .split(',')
+                .map(str::trim)
+                .filter(|host| !host.is_empty())
+                .map(str::to_string),
+        )
+    }
+
+    /// Lets requests reach `hosts` (names or IP literals, compared case-insensitively)
+    /// even when they resolve to loopback, private or link-local addresses.
+    pub fn with_allowed_hosts(mut self, hosts: impl IntoIterator<Item = String>) -> Self {
+        self.allowed_hosts
+            .extend(hosts.into_iter().map(|host| host.to_ascii_lowercase()));
+        self
+    }
+
+    /// Builds the client for one request to `url`, refusing non-public destinations
+    /// unless the host is allowed.
+    async fn client_for(&self, url: &url::Url) -> Result<Client, String> {
+        let builder = Client::builder()
+            .timeout(self.timeout)
+            .redirect(redirect::Policy::none());
+        let host = url
+            .host()
+            .ok_or_else(|| format!("url {} has no host", url))?;
+        let host_name = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        if self.allowed_hosts.contains(&host_name) {
+            return builder.build().map_err(|e| e.to_string());
+        }
+
+        let port = url.port_or_known_default().unwrap_or(80);
+        let (domain, addrs): (Option<&str>, Vec<SocketAddr>) = match host {
+            Host::Ipv4(ip) => (None, vec![SocketAddr::new(IpAddr::V4(ip), port)]),
+            Host::Ipv6(ip) => (None, vec![SocketAddr::new(IpAddr::V6(ip), port)]),
+            Host::Domain(domain) => (
+                Some(domain),
+                tokio::net::lookup_host((domain, port))
+                    .await
+                    .map_err(|e| format!("failed to resolve {}: {}", domain, e))?
+                    .collect(),
+            ),
+        };
+        if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+            return Err(format!(
+                "http_fetch refuses {}: {} is not a public address",
+                url,
+                addr.ip()
+            ));
+        }
+
+        let builder = match domain {
+            Some(domain) => builder.resolve_to_addrs(domain, &addrs),
+            None => builder,
+        };
+        builder.build().map_err(|e| e.to_string())
+    }
+}
+
+impl Default for HttpFetchTaskHandler {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(DEFAULT_HTTP_FETCH_TIMEOUT_SECS))
+    }
+}
+
+#[async_trait]
+impl TaskHandler for HttpFetchTaskHandler {
+    async fn handle(&self, task: &Task) -> Result<Option<JsonValue>, String> {
+        let url = task
+            .input_data
+            .as_ref()
+            .and_then(|input| input.0.get("url"))
+            .and_then(JsonValue::as_str)
+            .ok_or_else(|| "http_fetch requires input_data.url".to_string())?;
+        let parsed = url::Url::parse(url).map_err(|e| format!("invalid url {}: {}", url, e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(format!(
+                "http_fetch only supports http(s) urls, got {}",
+                url
+            ));
+        }
+
+        let response = self
+            .client_for(&parsed)
+            .await?
+            .get(parsed)
+            .send()
+            .await
+            .map_err(|e| format!("GET {} failed: {}", url, e))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("GET {} returned {}", url, status));
+        }
+        let body = response
+            .text()
+            .await
+            .map_err(|e| format!("failed to read response from {}: {}", url, e))?;
+        let body = serde_json::from_str(&body).unwrap_or(JsonValue::String(body));
+
+        Ok(Some(json!({ "status": status.as_u16(), "body": body })))
+    }
+}
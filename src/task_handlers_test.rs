@@ -0,0 +1,191 @@
+use crate::models::etl::{DateTimeScalar, JsonValueScalar, Status, Task, UuidScalar};
+use crate::task_handlers::{is_public_ip, HandlerRegistry, HttpFetchTaskHandler, NoopTaskHandler};
+use crate::task_runner::TaskHandler;
+use axum::{routing::get, Json, Router};
+use chrono::Utc;
+use serde_json::{json, Value as JsonValue};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use uuid::Uuid;
+
+fn task(name: &str, input: Option<JsonValue>) -> Task {
+    Task {
+        id: UuidScalar(Uuid::new_v4()),
+        job_id: UuidScalar(Uuid::new_v4()),
+        name: name.to_string(),
+        description: None,
+        status: Status::Running,
+        input_data: input.map(JsonValueScalar),
+        output_data: None,
+        error_message: None,
+        depends_on: None,
+        created_at: DateTimeScalar(Utc::now()),
+        updated_at: DateTimeScalar(Utc::now()),
+        created_by: None,
+        updated_by: None,
+    }
+}
+
+/// Serves a JSON endpoint at `/data` on an ephemeral local port and returns its base URL
+async fn spawn_server() -> String {
+    let router = Router::new().route("/data", get(|| async { Json(json!({ "rows": 3 })) }));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_registry_dispatches_by_task_name() {
+    let registry = HandlerRegistry::with_builtins();
+
+    let output = registry
+        .handle(&task("echo", Some(json!({ "a": 1 }))))
+        .await
+        .unwrap();
+    assert_eq!(output, Some(json!({ "a": 1 })));
+
+    let output = registry
+        .handle(&task("noop", Some(json!({ "a": 1 }))))
+        .await
+        .unwrap();
+    assert_eq!(output, None);
+}
+
+#[tokio::test]
+async fn test_registry_rejects_unknown_task_kinds() {
+    let registry = HandlerRegistry::new().with_handler("noop", Arc::new(NoopTaskHandler));
+
+    let err = registry.handle(&task("echo", None)).await.unwrap_err();
+
+    assert_eq!(err, "no handler registered for task kind 'echo'");
+}
+
+#[tokio::test]
+async fn test_http_fetch_returns_status_and_json_body() {
+    let base_url = spawn_server().await;
+    let handler = HttpFetchTaskHandler::default().with_allowed_hosts(["127.0.0.1".to_string()]);
+
+    let output = handler
+        .handle(&task(
+            "http_fetch",
+            Some(json!({ "url": format!("{}/data", base_url) })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(
+        output,
+        Some(json!({ "status": 200, "body": { "rows": 3 } }))
+    );
+
+    let err = handler
+        .handle(&task(
+            "http_fetch",
+            Some(json!({ "url": format!("{}/missing", base_url) })),
+        ))
+        .await
+        .unwrap_err();
+    assert!(err.contains("404"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_http_fetch_refuses_loopback_urls() {
+    let base_url = spawn_server().await;
+    let handler = HttpFetchTaskHandler::default();
+
+    for url in [
+        format!("{}/data", base_url),
+        format!("{}/data", base_url.replace("127.0.0.1", "localhost")),
+        "http://[::1]/data".to_string(),
+    ] {
+        let err = handler
+            .handle(&task("http_fetch", Some(json!({ "url": url }))))
+            .await
+            .unwrap_err();
+        assert!(err.contains("is not a public address"), "{}", err);
+    }
+}
+
+#[test]
+fn test_is_public_ip_rejects_internal_ranges() {
+    for ip in [
+        "127.0.0.1",
+        "10.1.2.3",
+        "172.16.0.1",
+        "192.168.1.1",
+        "169.254.169.254",
+        "100.64.0.1",
+        "0.0.0.0",
+        "::1",
+        "fd00::1",
+        "fe80::1",
+        "::ffff:10.0.0.1",
+    ] {
+        assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+    }
+    for ip in ["93.184.216.34", "2606:4700::1111"] {
+        assert!(is_public_ip(ip.parse().unwrap()), "{}", ip);
+    }
+}
+
+#[tokio::test]
+async fn test_http_fetch_rejects_missing_or_non_http_urls() {
+    let handler = HttpFetchTaskHandler::default();
+
+    let err = handler.handle(&task("http_fetch", None)).await.unwrap_err();
+    assert_eq!(err, "http_fetch requires input_data.url");
+
+    let err = handler
+        .handle(&task(
+            "http_fetch",
+            Some(json!({ "url": "file:///etc/passwd" })),
+        ))
+        .await
+        .unwrap_err();
+    assert!(err.contains("only supports http(s)"), "{}", err);
+}
+
+#[test]
+fn test_registry_validates_data_against_kind_schemas() {
+    let registry = HandlerRegistry::with_builtins();
+
+    assert!(registry
+        .validate_input("http_fetch", Some(&json!({ "url": "http://example.com" })))
+        .is_ok());
+    let violations = registry
+        .validate_input("http_fetch", Some(&json!({ "url": 5 })))
+        .unwrap_err();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].path, "/url");
+    let violations = registry.validate_input("http_fetch", None).unwrap_err();
+    assert_eq!(violations[0].path, "/");
+
+    assert!(registry
+        .validate_output("http_fetch", &json!({ "status": 200, "body": "ok" }))
+        .is_ok());
+    let violations = registry
+        .validate_output("http_fetch", &json!({ "status": "200" }))
+        .unwrap_err();
+    assert_eq!(violations[0].path, "/status");
+}
+
+#[test]
+fn test_registry_skips_validation_for_kinds_without_schemas() {
+    let registry = HandlerRegistry::with_builtins();
+
+    assert!(registry.validate_input("echo", Some(&json!(5))).is_ok());
+    assert!(registry.validate_input("unknown", None).is_ok());
+    assert!(registry.validate_output("unknown", &json!([1, 2])).is_ok());
+}
+
+#[test]
+fn test_registry_rejects_invalid_schemas() {
+    let err = HandlerRegistry::new()
+        .with_input_schema("broken", &json!({ "type": 5 }))
+        .err()
+        .unwrap();
+
+    assert!(err.starts_with("invalid JSON schema"), "{}", err);
+}
@@ -0,0 +1,514 @@
+use async_trait::async_trait;
+use serde_json::Value as JsonValue;
+use sqlx::postgres::PgPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Instant;
+use thiserror::Error;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::db::{DbConnection, TaskOrderError};
+use crate::event_bus::EventSink;
+use crate::graphql::ETLEvent;
+use crate::models::etl::{Job, PipelineMetrics, PipelineRun, Status, Task, UuidScalar};
+use crate::task_handlers::HandlerRegistry;
+
+/// Error types that can occur while running a job's tasks.
+#[derive(Error, Debug)]
+pub enum TaskRunnerError {
+    /// The job does not exist
+    #[error("Job not found: {0}")]
+    JobNotFound(Uuid),
+
+    /// The job isn't `Pending`, so it can't be started
+    #[error("Job {0} is {1:?}, not Pending")]
+    NotPending(Uuid, Status),
+
+    /// The job's tasks could not be put into execution order
+    #[error("Failed to order tasks: {0}")]
+    Ordering(#[from] TaskOrderError),
+
+    /// Error occurred during database operations
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// Executes the work for a single task.
+///
+/// Implementations receive the task in its `Running` state and return the task's output
+/// data, or an error message that is recorded on the task when it fails.
+#[async_trait]
+pub trait TaskHandler: Send + Sync {
+    async fn handle(&self, task: &Task) -> Result<Option<JsonValue>, String>;
+}
+
+/// Default task handler that echoes the task's `input_data` into its `output_data`.
+pub struct EchoTaskHandler;
+
+#[async_trait]
+impl TaskHandler for EchoTaskHandler {
+    async fn handle(&self, task: &Task) -> Result<Option<JsonValue>, String> {
+        Ok(task.input_data.as_ref().map(|input| input.0.clone()))
+    }
+}
+
+/// Runs a job's pending tasks with the built-in handlers.
+///
+/// Each task is dispatched by name through `HandlerRegistry::with_builtins()`, so tasks
+/// named anything other than `noop`, `echo` or `http_fetch` fail. See
+/// `run_job_with_handler` for details.
+pub async fn run_job(
+    pool: PgPool,
+    event_sender: Arc<dyn EventSink>,
+    job_id: UuidScalar,
+) -> Result<Job, TaskRunnerError> {
+    run_job_with_handler(
+        pool,
+        event_sender,
+        job_id,
+        Arc::new(HandlerRegistry::with_builtins()),
+    )
+    .await
+}
+
+/// Runs a job's pending tasks in dependency order.
+///
+/// The job is moved from `Pending` to `Running`, then each `Pending` task is flipped to `Running`, handed
+/// to `handler`, and marked `Completed` or `Failed`. Tasks whose dependency failed (or
+/// was itself skipped) never start and are marked `Cancelled`. Once every task has been attempted the job is marked
+/// `Completed`, or `Failed` if any task failed, unless its status was changed meanwhile
+/// to one that can't move there (see `set_job_status`). Each state change is committed
+/// in its own transaction and published on `event_sender`. A job cancelled before it
+/// started is returned as it is.
+///
+/// # Arguments
+/// * `pool` - A PostgreSQL connection pool
+/// * `event_sender` - Where status events are published
+/// * `job_id` - The ID of the job to run
+/// * `handler` - The handler that performs each task's work
+///
+/// # Returns
+/// * `Result<Job, TaskRunnerError>` - The job in its final state, or an error
+///
+/// # Errors
+/// * `JobNotFound` - If the job does not exist
+/// * `NotPending` - If the job is neither `Pending` nor `Cancelled`
+/// * `Ordering` - If the task dependencies form a cycle
+/// * `Database` - If a database operation fails
+pub async fn run_job_with_handler(
+    pool: PgPool,
+    event_sender: Arc<dyn EventSink>,
+    job_id: UuidScalar,
+    handler: Arc<dyn TaskHandler>,
+) -> Result<Job, TaskRunnerError> {
+    info!("Running job: {}", job_id.0);
+
+    let db = DbConnection { pool: pool.clone() };
+    let tasks = db.task_execution_order(job_id).await?;
+
+    let job = start_job(&pool, event_sender.as_ref(), job_id).await?;
+    if job.status == Status::Cancelled {
+        return Ok(job);
+    }
+    let tenant = job.business_group_id;
+
+    let mut failed: HashSet<Uuid> = HashSet::new();
+    let mut skipped: HashSet<Uuid> = HashSet::new();
+
+    for task in tasks.into_iter().filter(|t| t.status == Status::Pending) {
+        if let Some(dep) = task
+            .depends_on
+            .filter(|dep| failed.contains(&dep.0) || skipped.contains(&dep.0))
+        {
+            debug!(
+                "Skipping task {} because dependency {} did not complete",
+                task.id.0, dep.0
+            );
+            skipped.insert(task.id.0);
+            skip_task(&pool, event_sender.as_ref(), task.id, tenant, dep).await?;
+            continue;
+        }
+
+        let Some(task) = start_task(&pool, event_sender.as_ref(), task.id, tenant).await? else {
+            // Another runner claimed the task first
+            continue;
+        };
+
+        let result = handler.handle(&task).await;
+        if let Err(e) = &result {
+            error!("Task {} failed: {}", task.id.0, e);
+            failed.insert(task.id.0);
+        }
+        finish_task(&pool, event_sender.as_ref(), task.id, tenant, result).await?;
+    }
+
+    let final_status = if failed.is_empty() {
+        Status::Completed
+    } else {
+        Status::Failed
+    };
+    let job = set_job_status(&pool, event_sender.as_ref(), job_id, final_status)
+        .await?
+        .ok_or(TaskRunnerError::JobNotFound(job_id.0))?;
+
+    info!(
+        "Job {} finished with status {:?} ({} failed, {} skipped tasks)",
+        job_id.0,
+        job.status,
+        failed.len(),
+        skipped.len()
+    );
+    Ok(job)
+}
+
+/// Runs the job of a `Running` pipeline run with the built-in handlers, then records the
+/// run's outcome.
+///
+/// The run ends `Completed` when the job does, `Cancelled` when the job was cancelled
+/// meanwhile, and `Failed` otherwise, including when the job couldn't be run at all. Its
+/// metrics count the job's completed (`rowsProcessed`) and failed (`rowsFailed`) tasks
+/// and the run's duration. The final state is published as `PipelineRunStatusUpdated`.
+///
+/// # Arguments
+/// * `pool` - A PostgreSQL connection pool
+/// * `event_sender` - Where status events are published
+/// * `run_id` - The ID of the pipeline run
+/// * `job_id` - The ID of the run's job
+///
+/// # Returns
+/// * `Result<Option<PipelineRun>, TaskRunnerError>` - The run in its final state (None if it was deleted meanwhile), or an error if it couldn't be updated
+pub async fn run_pipeline(
+    pool: PgPool,
+    event_sender: Arc<dyn EventSink>,
+    run_id: UuidScalar,
+    job_id: UuidScalar,
+) -> Result<Option<PipelineRun>, TaskRunnerError> {
+    let started = Instant::now();
+    let status = match run_job(pool.clone(), event_sender.clone(), job_id).await {
+        Ok(job) if matches!(job.status, Status::Completed | Status::Cancelled) => job.status,
+        Ok(_) => Status::Failed,
+        Err(e) => {
+            error!(
+                "Pipeline run {} could not run job {}: {}",
+                run_id.0, job_id.0, e
+            );
+            Status::Failed
+        }
+    };
+
+    let (completed, failed, tenant): (i64, i64, Option<i64>) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*) FILTER (WHERE status = 'Completed'),
+               COUNT(*) FILTER (WHERE status = 'Failed'),
+               (SELECT business_group_id FROM jobs WHERE id = $1)
+        FROM tasks
+        WHERE job_id = $1
+        "#,
+    )
+    .bind(job_id.0)
+    .fetch_one(&pool)
+    .await?;
+    let metrics = PipelineMetrics {
+        rows_processed: Some(completed),
+        rows_failed: Some(failed),
+        duration_ms: Some(i64::try_from(started.elapsed().as_millis()).unwrap_or(i64::MAX)),
+        bytes_read: None,
+    };
+
+    let run = sqlx::query_as::<_, PipelineRun>(
+        r#"
+        UPDATE pipeline_runs
+        SET status = $1, metrics = $2
+        WHERE id = $3
+        RETURNING *
+        "#,
+    )
+    .bind(status)
+    .bind(serde_json::to_value(&metrics).ok())
+    .bind(run_id.0)
+    .fetch_optional(&pool)
+    .await?;
+
+    if let Some(ref run) = run {
+        info!(
+            "Pipeline run {} finished with status {:?}",
+            run.id.0, run.status
+        );
+        publish(
+            event_sender.as_ref(),
+            "PipelineRunStatusUpdated",
+            run.id,
+            Some(Status::Running),
+            run.status,
+            run,
+            tenant,
+        );
+    }
+    Ok(run)
+}
+
+/// Moves a `Pending` job to `Running` and publishes a `JobStatusUpdated` event.
+///
+/// A cancelled job is returned unchanged, as there is nothing left to run.
+async fn start_job(
+    pool: &PgPool,
+    event_sender: &dyn EventSink,
+    job_id: UuidScalar,
+) -> Result<Job, TaskRunnerError> {
+    let started = sqlx::query_as::<_, Job>(
+        r#"
+        UPDATE jobs
+        SET status = $1, started_at = NOW()
+        WHERE id = $2 AND status = $3
+        RETURNING *
+        "#,
+    )
+    .bind(Status::Running)
+    .bind(job_id.0)
+    .bind(Status::Pending)
+    .fetch_optional(pool)
+    .await?;
+    if let Some(job) = started {
+        publish(
+            event_sender,
+            "JobStatusUpdated",
+            job.id,
+            Some(Status::Pending),
+            job.status,
+            &job,
+            job.business_group_id,
+        );
+        return Ok(job);
+    }
+
+    let job = sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE id = $1")
+        .bind(job_id.0)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(TaskRunnerError::JobNotFound(job_id.0))?;
+    match job.status {
+        Status::Cancelled => Ok(job),
+        status => Err(TaskRunnerError::NotPending(job_id.0, status)),
+    }
+}
+
+/// Updates a job's status and publishes a `JobStatusUpdated` event.
+///
+/// A job whose current status can't move to `status` (see `Status::can_transition_to`),
+/// or already has it, is left as it is and returned unchanged: a cancelled job stays
+/// cancelled, and a job the reaper failed stays failed.
+async fn set_job_status(
+    pool: &PgPool,
+    event_sender: &dyn EventSink,
+    job_id: UuidScalar,
+    status: Status,
+) -> Result<Option<Job>, TaskRunnerError> {
+    let mut tx = pool.begin().await?;
+    let previous_status: Option<Status> =
+        sqlx::query_scalar("SELECT status FROM jobs WHERE id = $1 FOR UPDATE")
+            .bind(job_id.0)
+            .fetch_optional(&mut *tx)
+            .await?;
+    if let Some(previous) = previous_status
+        .filter(|&previous| previous == status || !previous.can_transition_to(status))
+    {
+        if previous != Status::Cancelled {
+            warn!(
+                "Leaving job {} {:?} instead of marking it {:?}",
+                job_id.0, previous, status
+            );
+        }
+        let job = sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE id = $1")
+            .bind(job_id.0)
+            .fetch_optional(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        return Ok(job);
+    }
+    let job = sqlx::query_as::<_, Job>(
+        r#"
+        UPDATE jobs
+        SET status = $1,
+            started_at = CASE WHEN $1 = 'Running'::status THEN NOW() ELSE started_at END,
+            completed_at = CASE WHEN $1 IN ('Completed'::status, 'Failed'::status) THEN NOW() ELSE completed_at END
+        WHERE id = $2
+        RETURNING *
+        "#,
+    )
+    .bind(status)
+    .bind(job_id.0)
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    if let Some(ref job) = job {
+        publish(
+            event_sender,
+            "JobStatusUpdated",
+            job.id,
+            previous_status,
+            job.status,
+            job,
+            job.business_group_id,
+        );
+    }
+    Ok(job)
+}
+
+/// Moves a `Pending` task to `Running`, returning `None` if it was no longer pending.
+async fn start_task(
+    pool: &PgPool,
+    event_sender: &dyn EventSink,
+    task_id: UuidScalar,
+    tenant: Option<i64>,
+) -> Result<Option<Task>, TaskRunnerError> {
+    let mut tx = pool.begin().await?;
+    let task = sqlx::query_as::<_, Task>(
+        r#"
+        UPDATE tasks
+        SET status = $1, started_at = NOW()
+        WHERE id = $2 AND status = $3
+        RETURNING *
+        "#,
+    )
+    .bind(Status::Running)
+    .bind(task_id.0)
+    .bind(Status::Pending)
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    if let Some(ref task) = task {
+        publish(
+            event_sender,
+            "TaskStatusUpdated",
+            task.id,
+            Some(Status::Pending),
+            task.status,
+            task,
+            tenant,
+        );
+    }
+    Ok(task)
+}
+
+/// Moves a `Pending` task whose dependency didn't complete to `Cancelled`, noting the
+/// dependency in its error message.
+async fn skip_task(
+    pool: &PgPool,
+    event_sender: &dyn EventSink,
+    task_id: UuidScalar,
+    tenant: Option<i64>,
+    dependency: UuidScalar,
+) -> Result<Option<Task>, TaskRunnerError> {
+    let task = sqlx::query_as::<_, Task>(
+        r#"
+        UPDATE tasks
+        SET status = $1, error_message = $2, completed_at = NOW()
+        WHERE id = $3 AND status = $4
+        RETURNING *
+        "#,
+    )
+    .bind(Status::Cancelled)
+    .bind(format!("dependency {} did not complete", dependency.0))
+    .bind(task_id.0)
+    .bind(Status::Pending)
+    .fetch_optional(pool)
+    .await?;
+
+    if let Some(ref task) = task {
+        publish(
+            event_sender,
+            "TaskStatusUpdated",
+            task.id,
+            Some(Status::Pending),
+            task.status,
+            task,
+            tenant,
+        );
+    }
+    Ok(task)
+}
+
+/// Records a task's outcome as `Completed` with its output, or `Failed` with an error message.
+///
+/// Failures are also appended to the job's `job_errors` history.
+///
+/// Tasks cancelled while they ran keep their `Cancelled` status.
+async fn finish_task(
+    pool: &PgPool,
+    event_sender: &dyn EventSink,
+    task_id: UuidScalar,
+    tenant: Option<i64>,
+    result: Result<Option<JsonValue>, String>,
+) -> Result<Option<Task>, TaskRunnerError> {
+    let (status, output_data, error_message) = match result {
+        Ok(output) => (Status::Completed, output, None),
+        Err(message) => (Status::Failed, None, Some(message)),
+    };
+
+    let mut tx = pool.begin().await?;
+    let task = sqlx::query_as::<_, Task>(
+        r#"
+        UPDATE tasks
+        SET status = $1, output_data = $2, error_message = $3, completed_at = NOW()
+        WHERE id = $4 AND status <> $5
+        RETURNING *
+        "#,
+    )
+    .bind(status)
+    .bind(output_data)
+    .bind(error_message)
+    .bind(task_id.0)
+    .bind(Status::Cancelled)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(Task {
+        job_id,
+        name,
+        error_message: Some(message),
+        ..
+    }) = &task
+    {
+        DbConnection::record_job_error(&mut tx, *job_id, &format!("task {name} failed: {message}"))
+            .await?;
+    }
+    tx.commit().await?;
+
+    if let Some(ref task) = task {
+        publish(
+            event_sender,
+            "TaskStatusUpdated",
+            task.id,
+            Some(Status::Running),
+            task.status,
+            task,
+            tenant,
+        );
+    }
+    Ok(task)
+}
+
+/// Publishes an entity snapshot on the event channel, ignoring the no-subscriber case.
+///
+/// `tenant` is the tenant of the job the entity belongs to.
+pub(crate) fn publish<T: serde::Serialize>(
+    event_sender: &dyn EventSink,
+    event_type: &str,
+    entity_id: UuidScalar,
+    previous_status: Option<Status>,
+    status: Status,
+    entity: &T,
+    tenant: Option<i64>,
+) {
+    event_sender.publish(&ETLEvent {
+        event_id: None,
+        event_type: event_type.to_string(),
+        entity_id,
+        status: Some(status),
+        previous_status,
+        data: serde_json::to_string(entity).ok().map(Into::into),
+        business_group_id: tenant,
+    });
+}
@@ -0,0 +1,188 @@
+use crate::models::etl::{Status, Task, UuidScalar};
+use crate::task_runner::{run_job_with_handler, TaskHandler, TaskRunnerError};
+use async_trait::async_trait;
+use serde_json::Value as JsonValue;
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+async fn test_pool() -> PgPool {
+    PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .expect("Failed to create test database")
+}
+
+/// Inserts a job with the given status and one pending `noop` task, returning the job id
+async fn insert_job(pool: &PgPool, status: &str) -> Uuid {
+    let job_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, $3::status)")
+        .bind(job_id)
+        .bind(format!("testjob_{}", job_id))
+        .bind(status)
+        .execute(pool)
+        .await
+        .expect("Failed to insert job");
+    sqlx::query("INSERT INTO tasks (id, job_id, name, status) VALUES ($1, $2, 'noop', 'Pending')")
+        .bind(Uuid::new_v4())
+        .bind(job_id)
+        .execute(pool)
+        .await
+        .expect("Failed to insert task");
+    job_id
+}
+
+/// Succeeds, but first marks the task's job `Failed` the way the reaper does
+struct ReapedWhileRunning {
+    pool: PgPool,
+}
+
+#[async_trait]
+impl TaskHandler for ReapedWhileRunning {
+    async fn handle(&self, task: &Task) -> Result<Option<JsonValue>, String> {
+        sqlx::query("UPDATE jobs SET status = 'Failed' WHERE id = $1")
+            .bind(task.job_id.0)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(None)
+    }
+}
+
+#[tokio::test]
+async fn test_run_job_refuses_jobs_that_are_not_pending() {
+    let pool = test_pool().await;
+    let (event_sender, mut receiver) = broadcast::channel(100);
+
+    for status in ["Completed", "Failed", "Running"] {
+        let job_id = insert_job(&pool, status).await;
+
+        let result = run_job_with_handler(
+            pool.clone(),
+            Arc::new(event_sender.clone()),
+            UuidScalar(job_id),
+            Arc::new(ReapedWhileRunning { pool: pool.clone() }),
+        )
+        .await;
+
+        assert!(
+            matches!(result, Err(TaskRunnerError::NotPending(id, _)) if id == job_id),
+            "{}: {:?}",
+            status,
+            result
+        );
+        let task_status: Status = sqlx::query_scalar("SELECT status FROM tasks WHERE job_id = $1")
+            .bind(job_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(task_status, Status::Pending, "{}", status);
+    }
+    assert!(
+        receiver.try_recv().is_err(),
+        "no events should be published"
+    );
+}
+
+#[tokio::test]
+async fn test_run_job_keeps_a_status_set_while_it_ran() {
+    let pool = test_pool().await;
+    let (event_sender, mut receiver) = broadcast::channel(100);
+    let job_id = insert_job(&pool, "Pending").await;
+
+    let job = run_job_with_handler(
+        pool.clone(),
+        Arc::new(event_sender),
+        UuidScalar(job_id),
+        Arc::new(ReapedWhileRunning { pool: pool.clone() }),
+    )
+    .await
+    .unwrap();
+
+    // Failed can't move to Completed, so the reaper's verdict stands
+    assert_eq!(job.status, Status::Failed);
+    let mut job_events = Vec::new();
+    while let Ok(event) = receiver.try_recv() {
+        if event.entity_id.0 == job_id {
+            job_events.push((event.previous_status, event.status));
+        }
+    }
+    assert_eq!(
+        job_events,
+        vec![(Some(Status::Pending), Some(Status::Running))]
+    );
+}
+
+/// Fails tasks named `boom` and completes every other task
+struct FailsBoom;
+
+#[async_trait]
+impl TaskHandler for FailsBoom {
+    async fn handle(&self, task: &Task) -> Result<Option<JsonValue>, String> {
+        match task.name.as_str() {
+            "boom" => Err("boom".to_string()),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_run_job_cancels_tasks_whose_dependency_failed() {
+    let pool = test_pool().await;
+    let (event_sender, _) = broadcast::channel(100);
+    let job_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO jobs (id, name, status) VALUES ($1, $2, 'Pending')")
+        .bind(job_id)
+        .bind(format!("testjob_{}", job_id))
+        .execute(&pool)
+        .await
+        .expect("Failed to insert job");
+    // boom <- child <- grandchild
+    let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+    for (i, name) in ["boom", "child", "grandchild"].into_iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO tasks (id, job_id, name, status, depends_on) VALUES ($1, $2, $3, 'Pending', $4)",
+        )
+        .bind(ids[i])
+        .bind(job_id)
+        .bind(name)
+        .bind(i.checked_sub(1).map(|parent| ids[parent]))
+        .execute(&pool)
+        .await
+        .expect("Failed to insert task");
+    }
+
+    let job = run_job_with_handler(
+        pool.clone(),
+        Arc::new(event_sender),
+        UuidScalar(job_id),
+        Arc::new(FailsBoom),
+    )
+    .await
+    .unwrap();
+    assert_eq!(job.status, Status::Failed);
+
+    let statuses: Vec<(Status, Option<String>)> = sqlx::query_as(
+        "SELECT status, error_message FROM tasks WHERE id = ANY($1) ORDER BY array_position($1, id)",
+    )
+    .bind(&ids)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        statuses,
+        vec![
+            (Status::Failed, Some("boom".to_string())),
+            (
+                Status::Cancelled,
+                Some(format!("dependency {} did not complete", ids[0]))
+            ),
+            (
+                Status::Cancelled,
+                Some(format!("dependency {} did not complete", ids[1]))
+            ),
+        ]
+    );
+}